@@ -0,0 +1,98 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::Cursor;
+
+use driver::{DriverError, Result};
+
+// Binary array wire format: ndim (i32), has_nulls (i32), element oid (u32), then for each dimension its length (i32)
+// and lower bound (i32), followed by all elements in row-major order as length (i32, -1 for NULL) and value bytes.
+
+pub struct RawArray<'a> {
+    pub dimensions: Vec<usize>,
+    pub elements: Vec<Option<&'a [u8]>>,
+}
+
+pub fn readArray(raw: &[u8]) -> Result<RawArray> {
+    let mut rdr = Cursor::new(raw);
+    let ndim = rdr.read_i32::<BigEndian>()?;
+    let _hasNulls = rdr.read_i32::<BigEndian>()?;
+    let _elementOid = rdr.read_u32::<BigEndian>()?;
+
+    if ndim < 0 {
+        return Err(DriverError::GenericError(format!("Invalid array dimension count: {}", ndim)));
+    }
+
+    let mut dimensions = Vec::with_capacity(ndim as usize);
+    for _ in 0..ndim {
+        let len = rdr.read_i32::<BigEndian>()?;
+        let _lowerBound = rdr.read_i32::<BigEndian>()?;
+
+        if len < 0 {
+            return Err(DriverError::GenericError(format!("Invalid array dimension length: {}", len)));
+        }
+
+        dimensions.push(len as usize);
+    }
+
+    let count = if dimensions.is_empty() { 0 } else { dimensions.iter().product() };
+    let mut elements = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let len = rdr.read_i32::<BigEndian>()?;
+        if len < 0 {
+            elements.push(None);
+        } else {
+            let start = rdr.position() as usize;
+            let end = start + len as usize;
+            if end > raw.len() {
+                return Err(DriverError::GenericError(String::from("Array element exceeds the received data")));
+            }
+
+            elements.push(Some(&raw[start..end]));
+            rdr.set_position(end as u64);
+        }
+    }
+
+    Ok(RawArray {
+        dimensions: dimensions,
+        elements: elements,
+    })
+}
+
+/// Writes already encoded elements. Arrays with a zero length dimension are written as empty arrays, as Postgres expects.
+pub fn writeArray(out: &mut Vec<u8>, elementOid: u32, dimensions: &[usize], elements: &[Option<Vec<u8>>]) -> Result<()> {
+    let isEmpty = dimensions.is_empty() || dimensions.iter().any(|d| *d == 0);
+    let hasNulls = elements.iter().any(|e| e.is_none());
+
+    out.write_i32::<BigEndian>(if isEmpty { 0 } else { dimensions.len() as i32 })?;
+    out.write_i32::<BigEndian>(if hasNulls { 1 } else { 0 })?;
+    out.write_u32::<BigEndian>(elementOid)?;
+
+    if isEmpty {
+        return Ok(());
+    }
+
+    for dimension in dimensions {
+        out.write_i32::<BigEndian>(toLength(*dimension)?)?;
+        out.write_i32::<BigEndian>(1)?; // Lower bound
+    }
+
+    for element in elements {
+        match *element {
+            Some(ref bytes) => {
+                out.write_i32::<BigEndian>(toLength(bytes.len())?)?;
+                out.extend_from_slice(bytes);
+            }
+            None => out.write_i32::<BigEndian>(-1)?,
+        }
+    }
+
+    Ok(())
+}
+
+fn toLength(len: usize) -> Result<i32> {
+    if len > i32::max_value() as usize {
+        Err(DriverError::GenericError(format!("Array length {} exceeds the protocol limit", len)))
+    } else {
+        Ok(len as i32)
+    }
+}
@@ -0,0 +1,248 @@
+// Batch execution of prepared statements. The postgres crate offers no pipelining, so batches of
+// `INSERT ... VALUES (...)` statements are rewritten to insert many rows per statement instead:
+//
+//   INSERT INTO "Todo" ("id", "title") VALUES ($1, $2)
+//   => INSERT INTO "Todo" ("id", "title") VALUES ($1, $2), ($3, $4), ($5, $6)
+//
+// Only plain inserts of a single row are rewritten. Anything that could make a row insert something other than
+// exactly one row (ON CONFLICT, RETURNING, INSERT ... SELECT) or that can't be split safely (comments, escape
+// strings, dollar quoting) is executed row by row.
+
+/// The number of parameters in a Bind message is sent as a 16 bit integer.
+const MAX_PARAMETERS: usize = 32767;
+
+/// Keeps single statements and their parse time within reason for statements with few parameters.
+const MAX_ROWS: usize = 1000;
+
+pub struct MultiRowInsert {
+    head: String,
+    row: Vec<RowPart>,
+}
+
+enum RowPart {
+    Sql(String),
+    Param(usize),
+}
+
+impl MultiRowInsert {
+    pub fn parse(query: &str) -> Option<MultiRowInsert> {
+        let query = query.trim().trim_right_matches(';').trim_right();
+        if !query.get(..6).map_or(false, |s| s.eq_ignore_ascii_case("insert")) {
+            return None;
+        }
+
+        let tokens = tokenize(query)?;
+        let values = tokens.iter().position(|t| t.depth == 0 && t.isWord(query, "values"))?;
+
+        // The row of values is the only thing after VALUES, and parameters are only used within it.
+        let open = tokens.get(values + 1).filter(|t| t.kind == TokenKind::Open)?;
+        let close = tokens[values + 1..].iter().position(|t| t.depth == 0 && t.kind == TokenKind::Close)? + values + 1;
+        if close != tokens.len() - 1 || tokens[..values].iter().any(|t| t.kind == TokenKind::Param) {
+            return None;
+        }
+
+        let mut row = Vec::new();
+        let mut sqlStart = open.end;
+        for token in tokens[values + 2..close].iter().filter(|t| t.kind == TokenKind::Param) {
+            row.push(RowPart::Sql(query[sqlStart..token.start].to_string()));
+            row.push(RowPart::Param(query[token.start + 1..token.end].parse().ok()?));
+            sqlStart = token.end;
+        }
+        row.push(RowPart::Sql(query[sqlStart..tokens[close].start].to_string()));
+
+        Some(MultiRowInsert {
+            head: query[..open.start].to_string(),
+            row: row,
+        })
+    }
+
+    /// The statement inserting `rows` rows. The parameters of row `n` are numbered after those of the rows before,
+    /// `width` is the number of parameters of the original statement.
+    pub fn statement(&self, rows: usize, width: usize) -> String {
+        let rows: Vec<String> = (0..rows)
+            .map(|r| {
+                let values: Vec<String> = self.row
+                    .iter()
+                    .map(|part| match *part {
+                        RowPart::Sql(ref sql) => sql.clone(),
+                        RowPart::Param(n) => format!("${}", n + r * width),
+                    })
+                    .collect();
+
+                format!("({})", values.concat())
+            })
+            .collect();
+
+        format!("{}{}", self.head, rows.join(", "))
+    }
+}
+
+/// Rows per statement for a statement with `width` parameters per row.
+pub fn chunkSize(width: usize) -> usize {
+    match width {
+        0 => MAX_ROWS,
+        w => (MAX_PARAMETERS / w).min(MAX_ROWS).max(1),
+    }
+}
+
+#[derive(PartialEq)]
+enum TokenKind {
+    Word,
+    Param,
+    Open,
+    Close,
+    Other,
+}
+
+/// Just enough of the SQL lexer to find VALUES, the parentheses around the row and its parameters. Quoted strings
+/// and identifiers are skipped. `depth` is the parenthesis depth outside of the token.
+struct Token {
+    kind: TokenKind,
+    start: usize,
+    end: usize,
+    depth: usize,
+}
+
+impl Token {
+    fn isWord(&self, query: &str, word: &str) -> bool {
+        self.kind == TokenKind::Word && query[self.start..self.end].eq_ignore_ascii_case(word)
+    }
+}
+
+/// None if the query contains anything the rewrite doesn't understand.
+fn tokenize(query: &str) -> Option<Vec<Token>> {
+    let bytes = query.as_bytes();
+    let mut tokens = Vec::new();
+    let mut depth = 0;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let start = i;
+        let c = bytes[i];
+        i += 1;
+
+        let kind = match c {
+            b'\'' | b'"' => {
+                // A doubled quote within a string is read as two adjacent strings, which is just as good here.
+                // Backslashes may escape quotes in escape strings, those aren't split.
+                let len = bytes[i..].iter().position(|b| *b == c)?;
+                if bytes[i..i + len].contains(&b'\\') {
+                    return None;
+                }
+
+                i += len + 1;
+                TokenKind::Other
+            }
+            b'-' if bytes.get(i) == Some(&b'-') => return None,
+            b'/' if bytes.get(i) == Some(&b'*') => return None,
+            b'$' => {
+                while i < bytes.len() && bytes[i].is_ascii_digit() {
+                    i += 1;
+                }
+
+                if i == start + 1 {
+                    return None; // Dollar quoting
+                }
+
+                TokenKind::Param
+            }
+            b'(' => {
+                depth += 1;
+                tokens.push(Token { kind: TokenKind::Open, start: start, end: i, depth: depth - 1 });
+                continue;
+            }
+            b')' => {
+                depth = depth.checked_sub(1)?;
+                TokenKind::Close
+            }
+            c if c.is_ascii_alphabetic() || c == b'_' => {
+                while i < bytes.len() && (bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_' || bytes[i] == b'$') {
+                    i += 1;
+                }
+
+                TokenKind::Word
+            }
+            c if c.is_ascii_whitespace() => continue,
+            _ => TokenKind::Other,
+        };
+
+        tokens.push(Token { kind: kind, start: start, end: i, depth: depth });
+    }
+
+    if depth == 0 {
+        Some(tokens)
+    } else {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rewrite(query: &str, rows: usize, width: usize) -> Option<String> {
+        MultiRowInsert::parse(query).map(|insert| insert.statement(rows, width))
+    }
+
+    #[test]
+    fn rewrites_plain_inserts() {
+        assert_eq!(
+            rewrite(r#"INSERT INTO "Todo" ("id", "title") VALUES ($1, $2);"#, 3, 2).unwrap(),
+            r#"INSERT INTO "Todo" ("id", "title") VALUES ($1, $2), ($3, $4), ($5, $6)"#
+        );
+        assert_eq!(rewrite("insert into t values ($1, now(), lower($2))", 2, 2).unwrap(), "insert into t values ($1, now(), lower($2)), ($3, now(), lower($4))");
+    }
+
+    #[test]
+    fn numbers_reused_and_out_of_order_parameters_per_row() {
+        assert_eq!(rewrite("INSERT INTO t (a, b, c) VALUES ($2, $1, $2)", 2, 2).unwrap(), "INSERT INTO t (a, b, c) VALUES ($2, $1, $2), ($4, $3, $4)");
+    }
+
+    #[test]
+    fn ignores_dollars_and_keywords_in_literals_and_quoted_identifiers() {
+        assert_eq!(
+            rewrite(r#"INSERT INTO "values" ("$1") VALUES ('$1 (', $1)"#, 2, 1).unwrap(),
+            r#"INSERT INTO "values" ("$1") VALUES ('$1 (', $1), ('$1 (', $2)"#
+        );
+        assert_eq!(rewrite("INSERT INTO t VALUES ('it''s', $1)", 2, 1).unwrap(), "INSERT INTO t VALUES ('it''s', $1), ('it''s', $2)");
+    }
+
+    #[test]
+    fn leaves_statements_that_insert_other_than_one_row_per_execution() {
+        assert!(MultiRowInsert::parse("INSERT INTO t (id) VALUES ($1) ON CONFLICT (id) DO NOTHING").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (id) VALUES ($1) RETURNING id").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (id) VALUES ($1), ($2)").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (id) SELECT $1").is_none());
+        assert!(MultiRowInsert::parse("UPDATE t SET id = $1").is_none());
+        assert!(MultiRowInsert::parse("WITH x AS (SELECT $1) INSERT INTO t (id) VALUES ($2)").is_none());
+    }
+
+    #[test]
+    fn leaves_statements_it_cannot_split_safely() {
+        assert!(MultiRowInsert::parse("INSERT INTO t (id) VALUES ($1) -- comment").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (id) /* comment */ VALUES ($1)").is_none());
+        assert!(MultiRowInsert::parse(r"INSERT INTO t (a, b) VALUES (E'\'', $1)").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (a, b) VALUES ($$text$$, $1)").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (a) VALUES ('unterminated, $1)").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (a) VALUES (($1)").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (a) VALUES ($1))").is_none());
+    }
+
+    #[test]
+    fn rejects_parameters_outside_of_the_row() {
+        assert!(MultiRowInsert::parse("INSERT INTO t (a) VALUES ($1) || $2").is_none());
+        assert!(MultiRowInsert::parse("INSERT INTO t (a) OVERRIDING SYSTEM VALUE VALUES (to_char($1, $2))").is_some());
+    }
+
+    #[test]
+    fn keeps_chunks_within_the_parameter_limit() {
+        assert_eq!(chunkSize(0), MAX_ROWS);
+        assert_eq!(chunkSize(2), MAX_ROWS);
+        assert_eq!(chunkSize(100), MAX_PARAMETERS / 100);
+        assert_eq!(chunkSize(MAX_PARAMETERS + 1), 1);
+
+        for width in 1..200 {
+            assert!(chunkSize(width) * width <= MAX_PARAMETERS || chunkSize(width) == 1);
+        }
+    }
+}
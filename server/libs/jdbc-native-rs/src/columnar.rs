@@ -0,0 +1,175 @@
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use postgres;
+use postgres::rows::Rows;
+use postgres::types::{FromSql, Kind, Type};
+use serde_json;
+
+use driver::{DriverError, Result};
+use jdbc_params::JdbcParameterType;
+use serialization;
+
+// Binary alternative to the JSON result format. All numbers are big endian, strings are a length (i32)
+// followed by UTF-8 bytes.
+//
+// Header: format version (u8), column count (i32), row count (i32), followed by name, discriminator,
+// element discriminator and type name of every column. Absent values are empty strings.
+//
+// Body: the values column by column, each as length (i32, -1 for NULL) followed by the value:
+// Boolean as u8, Int as i32, Long as i64, Double as f64 and Bytes as is. Values the JSON format emits
+// as objects or arrays (DateTime, Json, Array, Composite, Range, Interval) are their JSON text,
+// all other values the UTF-8 text of the JSON format's string.
+
+const FORMAT_VERSION: u8 = 1;
+const NULL_LENGTH: i32 = -1;
+
+pub fn encodeRows(rows: &Rows, textFallback: bool) -> Result<Vec<u8>> {
+    let columns: Result<Vec<serialization::ResultColumn>> = rows.columns().iter().map(|c| serialization::mapColumn(c, textFallback)).collect();
+    let columns = columns?;
+    let mut out = Vec::new();
+
+    out.write_u8(FORMAT_VERSION)?;
+    out.write_i32::<BigEndian>(columns.len() as i32)?;
+    out.write_i32::<BigEndian>(rows.len() as i32)?;
+
+    for column in &columns {
+        writeString(&column.name, &mut out)?;
+        writeString(&discriminatorName(Some(column.discriminator))?, &mut out)?;
+        writeString(&discriminatorName(column.elementDiscriminator)?, &mut out)?;
+        writeString(column.typeName.as_ref().map(|n| n.as_str()).unwrap_or(""), &mut out)?;
+    }
+
+    for (i, (column, pgColumn)) in columns.iter().zip(rows.columns().iter()).enumerate() {
+        for row in rows.iter() {
+            match row.get_bytes(i) {
+                Some(raw) => {
+                    let start = out.len();
+                    out.write_i32::<BigEndian>(0)?;
+                    writeValue(column.discriminator, pgColumn.type_(), raw, textFallback, &mut out)?;
+
+                    let len = out.len() - start - 4;
+                    BigEndian::write_i32(&mut out[start..start + 4], len as i32);
+                }
+                None => out.write_i32::<BigEndian>(NULL_LENGTH)?,
+            }
+        }
+    }
+
+    Ok(out)
+}
+
+/// Discriminators are written with the names the JSON format serializes them with.
+fn discriminatorName(discriminator: Option<JdbcParameterType>) -> Result<String> {
+    match discriminator {
+        Some(d) => match serde_json::to_value(d)? {
+            serde_json::Value::String(name) => Ok(name),
+            other => Err(DriverError::GenericError(format!("Invalid discriminator: {}", other))),
+        },
+        None => Ok(String::new()),
+    }
+}
+
+fn writeString(s: &str, out: &mut Vec<u8>) -> Result<()> {
+    out.write_i32::<BigEndian>(s.len() as i32)?;
+    out.extend_from_slice(s.as_bytes());
+
+    Ok(())
+}
+
+fn writeValue(discriminator: JdbcParameterType, ty: &Type, raw: &[u8], textFallback: bool, out: &mut Vec<u8>) -> Result<()> {
+    let base = baseType(ty);
+
+    match discriminator {
+        JdbcParameterType::Boolean => out.write_u8(bool::from_sql(base, raw)? as u8)?,
+        JdbcParameterType::Int if *base == postgres::types::INT2 => out.write_i32::<BigEndian>(i16::from_sql(base, raw)? as i32)?,
+        JdbcParameterType::Int => out.write_i32::<BigEndian>(i32::from_sql(base, raw)?)?,
+        JdbcParameterType::Long if *base == postgres::types::OID => out.write_i64::<BigEndian>(u32::from_sql(base, raw)? as i64)?,
+        JdbcParameterType::Long => out.write_i64::<BigEndian>(i64::from_sql(base, raw)?)?,
+        JdbcParameterType::Double if *base == postgres::types::FLOAT4 => out.write_f64::<BigEndian>(f32::from_sql(base, raw)? as f64)?,
+        JdbcParameterType::Double => out.write_f64::<BigEndian>(f64::from_sql(base, raw)?)?,
+        JdbcParameterType::Bytes => out.extend_from_slice(raw),
+        JdbcParameterType::DateTime
+        | JdbcParameterType::Json
+        | JdbcParameterType::Array
+        | JdbcParameterType::Composite
+        | JdbcParameterType::Range
+        | JdbcParameterType::Interval => serde_json::to_writer(&mut *out, &serialization::valueToJson(ty, raw, textFallback)?)?,
+        _ => match serialization::valueToJson(ty, raw, textFallback)? {
+            serde_json::Value::String(s) => out.extend_from_slice(s.as_bytes()),
+            other => serde_json::to_writer(&mut *out, &other)?,
+        },
+    }
+
+    Ok(())
+}
+
+/// Domains share the binary representation of their base type.
+fn baseType(ty: &Type) -> &Type {
+    match ty.kind() {
+        &Kind::Domain(ref base) => baseType(base),
+        _ => ty,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use postgres::types::{BOOL, BYTEA, FLOAT4, FLOAT8, INT2, INT4, INT8, INTERVAL, JSONB, OID, TEXT};
+
+    fn value(discriminator: JdbcParameterType, ty: &Type, raw: &[u8]) -> Vec<u8> {
+        let mut out = Vec::new();
+        writeValue(discriminator, ty, raw, false, &mut out).unwrap();
+        out
+    }
+
+    #[test]
+    fn writes_numbers_with_fixed_widths() {
+        assert_eq!(value(JdbcParameterType::Boolean, &BOOL, &[1]), vec![1]);
+        assert_eq!(value(JdbcParameterType::Int, &INT2, &[0xff, 0xfe]), vec![0xff, 0xff, 0xff, 0xfe]);
+        assert_eq!(value(JdbcParameterType::Int, &INT4, &[0, 0, 1, 0]), vec![0, 0, 1, 0]);
+        assert_eq!(value(JdbcParameterType::Long, &INT8, &[0x80, 0, 0, 0, 0, 0, 0, 0]), vec![0x80, 0, 0, 0, 0, 0, 0, 0]);
+        assert_eq!(value(JdbcParameterType::Long, &OID, &[0xff, 0xff, 0xff, 0xff]), vec![0, 0, 0, 0, 0xff, 0xff, 0xff, 0xff]);
+    }
+
+    #[test]
+    fn writes_doubles_including_non_finite_values() {
+        let float4 = |f: f32| {
+            let mut raw = [0; 4];
+            BigEndian::write_f32(&mut raw, f);
+            BigEndian::read_f64(&value(JdbcParameterType::Double, &FLOAT4, &raw))
+        };
+        let float8 = |f: f64| {
+            let mut raw = [0; 8];
+            BigEndian::write_f64(&mut raw, f);
+            BigEndian::read_f64(&value(JdbcParameterType::Double, &FLOAT8, &raw))
+        };
+
+        assert_eq!(float4(1.5), 1.5);
+        assert_eq!(float4(::std::f32::INFINITY), ::std::f64::INFINITY);
+        assert!(float4(::std::f32::NAN).is_nan());
+        assert_eq!(float8(-0.1), -0.1);
+        assert_eq!(float8(::std::f64::NEG_INFINITY), ::std::f64::NEG_INFINITY);
+        assert!(float8(::std::f64::NAN).is_nan());
+    }
+
+    #[test]
+    fn writes_bytes_and_strings_as_is() {
+        assert_eq!(value(JdbcParameterType::Bytes, &BYTEA, &[0, 1, 2]), vec![0, 1, 2]);
+        assert_eq!(value(JdbcParameterType::String, &TEXT, "größe".as_bytes()), "größe".as_bytes().to_vec());
+    }
+
+    #[test]
+    fn writes_json_encoded_discriminators_as_json_text() {
+        let json = value(JdbcParameterType::Json, &JSONB, b"\x01{\"a\": [1, \"x\"]}");
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&json).unwrap(), serde_json::from_str::<serde_json::Value>(r#"{"a": [1, "x"]}"#).unwrap());
+
+        let interval = [0, 0, 0, 0, 0, 0, 0, 1, 0, 0, 0, 2, 0, 0, 0, 3];
+        let json = value(JdbcParameterType::Interval, &INTERVAL, &interval);
+        assert_eq!(serde_json::from_slice::<serde_json::Value>(&json).unwrap(), serde_json::from_str::<serde_json::Value>(r#"{"months": 3, "days": 2, "microseconds": 1}"#).unwrap());
+    }
+
+    #[test]
+    fn names_discriminators_like_the_json_format() {
+        assert_eq!(discriminatorName(Some(JdbcParameterType::DateTimeTz)).unwrap(), "DateTimeTz");
+        assert_eq!(discriminatorName(None).unwrap(), "");
+    }
+}
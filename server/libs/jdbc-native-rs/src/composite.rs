@@ -0,0 +1,42 @@
+use byteorder::{BigEndian, ReadBytesExt};
+use std::io::Cursor;
+
+use driver::{DriverError, Result};
+
+// Binary composite wire format: field count (i32), followed by every field as type oid (u32),
+// length (i32, -1 for NULL) and value bytes.
+
+pub struct RawField<'a> {
+    pub oid: u32,
+    pub value: Option<&'a [u8]>,
+}
+
+pub fn readComposite(raw: &[u8]) -> Result<Vec<RawField>> {
+    let mut rdr = Cursor::new(raw);
+    let count = rdr.read_i32::<BigEndian>()?;
+
+    if count < 0 {
+        return Err(DriverError::GenericError(format!("Invalid composite field count: {}", count)));
+    }
+
+    let mut fields = Vec::with_capacity(count as usize);
+    for _ in 0..count {
+        let oid = rdr.read_u32::<BigEndian>()?;
+        let len = rdr.read_i32::<BigEndian>()?;
+
+        if len < 0 {
+            fields.push(RawField { oid: oid, value: None });
+        } else {
+            let start = rdr.position() as usize;
+            let end = start + len as usize;
+            if end > raw.len() {
+                return Err(DriverError::GenericError(String::from("Composite field exceeds the received data")));
+            }
+
+            fields.push(RawField { oid: oid, value: Some(&raw[start..end]) });
+            rdr.set_position(end as u64);
+        }
+    }
+
+    Ok(fields)
+}
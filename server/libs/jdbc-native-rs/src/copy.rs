@@ -0,0 +1,301 @@
+use byteorder::{BigEndian, ByteOrder, WriteBytesExt};
+use postgres::types::{IsNull, ToSql, Type};
+use serde_json;
+use std::io::{self, Read, Write};
+use std::mem;
+use std::sync::mpsc::{sync_channel, Receiver, SyncSender};
+use std::thread::{self, JoinHandle};
+use std::vec;
+
+use driver::{quoteIdentifier, DriverError, Result};
+use handles::SharedConnection;
+use jdbc_params;
+use jdbc_params::JdbcParameter;
+use panics;
+
+// Bulk loading with COPY ... FROM STDIN. Rows in the parameter format are encoded into the binary COPY format
+// while Postgres reads them, CSV is passed through as is.
+//
+// Bulk export with COPY ... TO STDOUT. The copy runs on a thread of its own that hands the data to the JVM in chunks,
+// as the postgres crate only offers to write all of it at once.
+//
+// Binary format: signature, flags (i32) and header extension length (i32), followed by every row as
+// field count (i16) and the fields as length (i32, -1 for NULL) and value in the binary format of the column
+// type. The data ends with a field count of -1.
+
+const SIGNATURE: &[u8] = b"PGCOPY\n\xff\r\n\0";
+const NULL_LENGTH: i32 = -1;
+const TRAILER: i16 = -1;
+
+/// Chunks the export thread may produce ahead of the JVM.
+const EXPORT_QUEUE_SIZE: usize = 4;
+
+pub enum CopyData {
+    Rows(Vec<Vec<JdbcParameter>>),
+    Csv(String),
+}
+
+impl CopyData {
+    /// `json` rows are in the format of `toJdbcParameterList`, `csv` rows have no header line.
+    pub fn parse(format: &str, data: String) -> Result<CopyData> {
+        match format {
+            "json" => Ok(CopyData::Rows(jdbc_params::toJdbcParameterList(&data)?)),
+            "csv" => Ok(CopyData::Csv(data)),
+            x => Err(DriverError::GenericError(format!("Invalid copy format: {}", x))),
+        }
+    }
+}
+
+pub struct CopyTarget {
+    pub schema: Option<String>,
+    pub table: String,
+    pub columns: Vec<String>,
+}
+
+impl CopyTarget {
+    /// `columns` is a JSON array of column names. An empty schema refers to the search path.
+    pub fn parse(schema: String, table: String, columns: &String) -> Result<CopyTarget> {
+        let columns: Vec<String> = serde_json::from_str(columns)?;
+        if columns.is_empty() {
+            return Err(DriverError::GenericError(String::from("Copy requires at least one column")));
+        }
+
+        Ok(CopyTarget {
+            schema: if schema.is_empty() { None } else { Some(schema) },
+            table: table,
+            columns: columns,
+        })
+    }
+
+    pub fn copyStatement(&self, format: &str) -> String {
+        format!("COPY {} ({}) FROM STDIN (FORMAT {})", self.qualifiedTable(), self.columnList(), format)
+    }
+
+    /// Prepared to learn the column types, which the binary format depends on.
+    pub fn typesStatement(&self) -> String {
+        format!("SELECT {} FROM {} WHERE false", self.columnList(), self.qualifiedTable())
+    }
+
+    fn qualifiedTable(&self) -> String {
+        match self.schema {
+            Some(ref schema) => format!("{}.{}", quoteIdentifier(schema), quoteIdentifier(&self.table)),
+            None => quoteIdentifier(&self.table),
+        }
+    }
+
+    fn columnList(&self) -> String {
+        self.columns.iter().map(|c| quoteIdentifier(c)).collect::<Vec<String>>().join(", ")
+    }
+}
+
+/// Encodes one row at a time as Postgres reads the data, so that the encoded rows are never held in memory at once.
+pub struct BinaryRows {
+    types: Vec<Type>,
+    rows: vec::IntoIter<Vec<JdbcParameter>>,
+    row: usize,
+    buf: Vec<u8>,
+    pos: usize,
+    finished: bool,
+}
+
+impl BinaryRows {
+    pub fn new(types: Vec<Type>, rows: Vec<Vec<JdbcParameter>>) -> BinaryRows {
+        let mut buf = Vec::from(SIGNATURE);
+        buf.extend_from_slice(&[0; 8]); // No flags, no header extension
+
+        BinaryRows {
+            types: types,
+            rows: rows.into_iter(),
+            row: 0,
+            buf: buf,
+            pos: 0,
+            finished: false,
+        }
+    }
+
+    fn encodeRow(&mut self, row: &[JdbcParameter]) -> Result<()> {
+        if row.len() != self.types.len() {
+            return Err(DriverError::GenericError(format!("{} values, expected {}", row.len(), self.types.len())));
+        }
+
+        self.buf.write_i16::<BigEndian>(row.len() as i16)?;
+        for (param, ty) in row.iter().zip(self.types.iter()) {
+            let start = self.buf.len();
+            self.buf.write_i32::<BigEndian>(0)?;
+
+            match param.to_sql_checked(ty, &mut self.buf)? {
+                IsNull::Yes => {
+                    self.buf.truncate(start);
+                    self.buf.write_i32::<BigEndian>(NULL_LENGTH)?;
+                }
+                IsNull::No => {
+                    let len = self.buf.len() - start - 4;
+                    BigEndian::write_i32(&mut self.buf[start..start + 4], len as i32);
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Read for BinaryRows {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.buf.len() {
+            self.buf.clear();
+            self.pos = 0;
+
+            match self.rows.next() {
+                Some(row) => {
+                    self.row += 1;
+                    self.encodeRow(&row).map_err(|e| {
+                        io::Error::new(io::ErrorKind::InvalidData, format!("Can't encode row {}: {:?}", self.row, e))
+                    })?;
+                }
+                None if !self.finished => {
+                    self.finished = true;
+                    self.buf.write_i16::<BigEndian>(TRAILER)?;
+                }
+                None => return Ok(0),
+            }
+        }
+
+        let len = out.len().min(self.buf.len() - self.pos);
+        out[..len].copy_from_slice(&self.buf[self.pos..self.pos + len]);
+        self.pos += len;
+
+        Ok(len)
+    }
+}
+
+pub enum ExportFormat {
+    Csv,
+    Ndjson,
+}
+
+impl ExportFormat {
+    pub fn parse(format: &str) -> Result<ExportFormat> {
+        match format {
+            "csv" => Ok(ExportFormat::Csv),
+            "ndjson" => Ok(ExportFormat::Ndjson),
+            x => Err(DriverError::GenericError(format!("Invalid export format: {}", x))),
+        }
+    }
+
+    /// CSV starts with a header line. For NDJSON, every row is converted with `row_to_json`. JSON contains no
+    /// control characters, so with control characters as quote and delimiter the CSV output is the plain JSON.
+    pub fn copyStatement(&self, query: &str) -> String {
+        match *self {
+            ExportFormat::Csv => format!("COPY ({}) TO STDOUT (FORMAT csv, HEADER)", query),
+            ExportFormat::Ndjson => format!(
+                "COPY (SELECT row_to_json(t) FROM ({}) t) TO STDOUT (FORMAT csv, QUOTE e'\\x01', DELIMITER e'\\x02')",
+                query
+            ),
+        }
+    }
+}
+
+pub struct Export {
+    chunks: Option<Receiver<Vec<u8>>>,
+    worker: Option<JoinHandle<Result<u64>>>,
+    rows: Option<u64>,
+}
+
+impl Export {
+    /// Created by `insertExport` before the export is started, it has no data.
+    pub fn pending() -> Export {
+        Export {
+            chunks: None,
+            worker: None,
+            rows: None,
+        }
+    }
+
+    /// The export thread holds the connection until all data has been read or the export is dropped.
+    pub fn start(connection: SharedConnection, statement: String, chunkSize: usize) -> Export {
+        let (sender, receiver) = sync_channel(EXPORT_QUEUE_SIZE);
+        // A panic is reported like one of a call, with its message. It poisons the connection, as it would there.
+        let worker = thread::spawn(move || {
+            panics::catch(move || {
+                let connection = connection
+                    .lock()
+                    .map_err(|e| DriverError::GenericError(format!("Connection lock poisoned: {}", e)))?;
+
+                let mut writer = ChunkWriter {
+                    chunks: sender,
+                    buf: Vec::with_capacity(chunkSize),
+                    chunkSize: chunkSize,
+                };
+
+                let rows = connection.copyOut(&statement, &mut writer)?;
+                writer.flush()?;
+
+                Ok(rows)
+            }).unwrap_or_else(|msg| Err(DriverError::GenericError(msg)))
+        });
+
+        Export {
+            chunks: Some(receiver),
+            worker: Some(worker),
+            rows: None,
+        }
+    }
+
+    /// The next chunk, or None once all data has been read. A failed export reports its error instead of the end.
+    pub fn next(&mut self) -> Result<Option<Vec<u8>>> {
+        if let Some(chunk) = self.chunks.as_ref().and_then(|c| c.recv().ok()) {
+            return Ok(Some(chunk));
+        }
+
+        if let Some(worker) = self.worker.take() {
+            self.rows = Some(join(worker)?);
+        }
+
+        Ok(None)
+    }
+
+    /// Cancels the export if not all data has been read. Returns the number of rows exported, if it completed.
+    pub fn finish(&mut self) -> Result<Option<u64>> {
+        mem::drop(self.chunks.take());
+
+        match self.worker.take() {
+            // A cancelled export fails writing its next chunk, that error is of no interest.
+            Some(worker) => Ok(join(worker).ok()),
+            None => Ok(self.rows),
+        }
+    }
+}
+
+fn join(worker: JoinHandle<Result<u64>>) -> Result<u64> {
+    worker
+        .join()
+        .map_err(|_| DriverError::GenericError(String::from("Export thread panicked")))?
+}
+
+struct ChunkWriter {
+    chunks: SyncSender<Vec<u8>>,
+    buf: Vec<u8>,
+    chunkSize: usize,
+}
+
+impl Write for ChunkWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.buf.extend_from_slice(data);
+        if self.buf.len() >= self.chunkSize {
+            self.flush()?;
+        }
+
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if self.buf.is_empty() {
+            return Ok(());
+        }
+
+        let chunk = mem::replace(&mut self.buf, Vec::with_capacity(self.chunkSize));
+        self.chunks
+            .send(chunk)
+            .map_err(|_| io::Error::new(io::ErrorKind::BrokenPipe, "Export was closed"))
+    }
+}
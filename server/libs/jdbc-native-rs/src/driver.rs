@@ -6,88 +6,442 @@ use chrono::format;
 use rust_decimal::Decimal;
 use serde_json;
 use uuid;
+use base64;
 
 use postgres;
 use postgres::rows::{Row, Rows};
-use postgres::stmt::Statement;
-use postgres::transaction::Transaction;
 use postgres::types::{IsNull, ToSql, Type};
+use postgres::stmt::Statement;
 use postgres::{Connection, Result as PsqlResult, TlsMode};
 
 use std::boxed::Box;
-use std::cell;
-use std::cell::RefCell;
+use std::collections::HashMap;
 use std::error::Error as StdErr;
+use std::io;
+use std::mem;
 use std::result;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
 
 use num_traits::ToPrimitive;
 use num_traits::cast::FromPrimitive;
 
+use batch;
+use copy;
 use jdbc_params;
-use std::os::raw::c_char;
-use std::ffi::{CStr, CString};
+use tls;
+use url_params;
+use openssl;
+use protocol_buffer;
+use handles::Handle;
 
+/// The call result in `error` is length delimited, like the data of a `ProtocolBuffer`.
 #[repr(C)]
 #[no_mangle]
-pub struct PointerAndError<'a> {
-    pub error: *const c_char,
-    pub pointer: *mut PsqlPreparedStatement<'a>
+pub struct HandleAndError {
+    pub error: *mut u8,
+    pub error_len: usize,
+    pub handle: Handle, // 0 if the call failed
 }
 
-impl<'a> Drop for PointerAndError<'a> {
-    fn drop(&mut self) {
-        trace!("Dropping PointerAndError");
-        // Do NOT drop the contained statement, it will be closed separately. This drop is only dropping the protocol structure.
-        if !self.error.is_null() {
-            trace!("Dropping contained error in PointerAndError");
-            drop(unsafe {
-                String::from_utf8(CString::from_raw(self.error as *mut c_char).into_bytes()).unwrap()
-            });
+impl HandleAndError {
+    pub fn new(error: Vec<u8>, handle: Handle) -> HandleAndError {
+        let (ptr, len) = protocol_buffer::into_raw_bytes(error);
+
+        HandleAndError {
+            error: ptr,
+            error_len: len,
+            handle: handle,
         }
     }
 }
 
+impl Drop for HandleAndError {
+    fn drop(&mut self) {
+        trace!("Dropping HandleAndError");
+        protocol_buffer::drop_raw_bytes(self.error, self.error_len);
+    }
+}
+
+/// The session state is only changed through `&mut self`. A postgres `Connection` can't be shared between threads,
+/// so the handle registry keeps every connection behind a mutex, which serializes calls from different JVM threads.
 #[repr(C)]
 #[no_mangle]
 #[allow(non_snake_case)]
-pub struct PsqlConnection<'a> {
-    connection: Connection,
-    transaction: RefCell<Option<Transaction<'a>>>,
+pub struct PsqlConnection {
+    // The statements of open statement handles, see `prepareStatement`. They borrow the boxed `connection`, whose
+    // address doesn't change when the `PsqlConnection` is moved, and are declared before it to be dropped first.
+    statements: HashMap<usize, Statement<'static>>,
+    connection: Box<Connection>,
+    inTransaction: bool,
+    // Counts the transactions that ended, to tell whether the transaction of a cursor is still open.
+    endedTransactions: u64,
+    savepoints: Vec<String>,
+    transactionOptions: TransactionOptions,
+    pub textFallback: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IsolationLevel {
+    ReadUncommitted,
+    ReadCommitted,
+    RepeatableRead,
+    Serializable,
+}
+
+/// Characteristics of the transactions opened by `startTransaction`.
+#[derive(Clone, Copy, Debug)]
+pub struct TransactionOptions {
+    pub isolationLevel: IsolationLevel,
+    pub readOnly: bool,
+    pub deferrable: bool,
+}
+
+impl Default for TransactionOptions {
+    fn default() -> TransactionOptions {
+        TransactionOptions {
+            isolationLevel: IsolationLevel::ReadCommitted,
+            readOnly: false,
+            deferrable: false,
+        }
+    }
+}
+
+impl TransactionOptions {
+    /// The transaction modes of a `BEGIN` statement.
+    fn modes(&self) -> String {
+        let isolationLevel = match self.isolationLevel {
+            IsolationLevel::ReadUncommitted => "READ UNCOMMITTED",
+            IsolationLevel::ReadCommitted => "READ COMMITTED",
+            IsolationLevel::RepeatableRead => "REPEATABLE READ",
+            IsolationLevel::Serializable => "SERIALIZABLE",
+        };
+
+        format!(
+            "ISOLATION LEVEL {}, {}, {}",
+            isolationLevel,
+            if self.readOnly { "READ ONLY" } else { "READ WRITE" },
+            if self.deferrable { "DEFERRABLE" } else { "NOT DEFERRABLE" }
+        )
+    }
+}
+
+/// Retries of transactions failing with a serialization failure (40001) or a detected deadlock (40P01).
+/// The delay before a retry doubles with every attempt, starting at `backoff`.
+#[derive(Clone, Copy, Debug)]
+pub struct RetryPolicy {
+    pub maxAttempts: u32,
+    pub backoff: Duration,
+}
+
+const RETRYABLE_ERROR_CODES: [&str; 2] = ["40001", "40P01"];
+
+/// Caps the exponential growth of the delay between retries.
+const MAX_BACKOFF_DOUBLINGS: u32 = 10;
+
+/// No single delay is longer, whatever the backoff.
+const MAX_DELAY_SECS: u64 = 60;
+
+/// Bounds of the retry settings handed in by the JVM.
+pub const MAX_ATTEMPTS: i32 = 100;
+pub const MAX_BACKOFF_MS: i64 = 60_000;
+
+impl RetryPolicy {
+    fn delay(&self, attempt: u32) -> Duration {
+        let max = Duration::from_secs(MAX_DELAY_SECS);
+        self.backoff
+            .checked_mul(1 << (attempt - 1).min(MAX_BACKOFF_DOUBLINGS))
+            .map_or(max, |delay| delay.min(max))
+    }
+}
+
+/// Runs `attempt` until it succeeds, fails with an error that isn't retryable or the attempts are used up. Sleeps
+/// between the attempts, so `attempt` has to lock the connection itself instead of holding it across the retries.
+/// Returns the attempts made alongside the result, as the caller reports them for failures as well.
+pub fn withRetries<T, F>(retry: RetryPolicy, mut attempt: F) -> (Result<T>, u32) where F: FnMut() -> Result<T> {
+    let mut attempts = 1;
+    loop {
+        match attempt() {
+            Err(ref e) if attempts < retry.maxAttempts && isRetryable(e) => {
+                debug!("Retrying transaction after attempt {}: {:?}", attempts, e);
+                thread::sleep(retry.delay(attempts));
+                attempts += 1;
+            }
+            res => return (res, attempts),
+        }
+    }
+}
+
+fn isRetryable(e: &DriverError) -> bool {
+    match *e {
+        DriverError::PsqlError(ref e) => match e.as_db() {
+            Some(dbErr) => RETRYABLE_ERROR_CODES.iter().any(|code| *code == dbErr.code.code()),
+            None => false,
+        },
+        _ => false,
+    }
+}
+
+/// Isolation levels are passed with the values of the JDBC constants (`java.sql.Connection.TRANSACTION_*`).
+pub fn isolationLevel(level: i32) -> Result<IsolationLevel> {
+    match level {
+        1 => Ok(IsolationLevel::ReadUncommitted),
+        2 => Ok(IsolationLevel::ReadCommitted),
+        4 => Ok(IsolationLevel::RepeatableRead),
+        8 => Ok(IsolationLevel::Serializable),
+        x => Err(DriverError::GenericError(format!("Invalid transaction isolation level: {}", x))),
+    }
 }
 
 #[repr(C)]
 #[no_mangle]
 #[allow(non_snake_case)]
-pub struct PsqlPreparedStatement<'a> {
-    statement: Statement<'a>,
-}
-
-impl<'a> PsqlPreparedStatement<'a> {
-    pub fn execute(&self, params: Vec<Vec<&jdbc_params::JdbcParameter>>) -> Result<Vec<i32>> {
-        let paramLength = params.len();
-        let mut counts = Vec::new();
-
-        for param in params {
-            let res = self.statement.execute(&jdbc_params::JdbcParameter::paramsToSql(param)[..]).map_err(DriverError::from);
-            match res {
-                Ok(count) => counts.push(count as i32),
-                Err(ref e) if paramLength > 1 => {
-                    debug!("Error during prep exec: {:?}", e);
-                    counts.push(-3)
-                },
-                Err(_) => return res.map(|v| vec!(v as i32))
+/// A prepared statement. Like cursors, it doesn't borrow the connection it was prepared on: the statement is
+/// owned by the connection and looked up by its id for every execution, until `closeStatement` deallocates it.
+pub struct PsqlPreparedStatement {
+    id: usize,
+    query: String,
+    pub textFallback: bool,
+}
+
+static STATEMENT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl PsqlPreparedStatement {
+    /// Batches of plain inserts are executed as multi-row inserts, see `batch`. Failed rows of a batch are
+    /// reported in the result according to the policy, a single row returns its error.
+    pub fn execute(
+        &self,
+        conn: &mut PsqlConnection,
+        params: Vec<Vec<&jdbc_params::JdbcParameter>>,
+        policy: BatchErrorPolicy,
+    ) -> Result<BatchResult> {
+        match policy {
+            BatchErrorPolicy::Continue => self.executeRows(conn, &params, false),
+            BatchErrorPolicy::StopAtFirstError => self.executeRows(conn, &params, true),
+            BatchErrorPolicy::RollBack if params.len() > 1 => self.executeAtomically(conn, &params),
+            BatchErrorPolicy::RollBack => self.executeRows(conn, &params, true),
+        }
+    }
+
+    pub fn query(&self, conn: &PsqlConnection, params: Vec<&jdbc_params::JdbcParameter>) -> Result<Rows> {
+        conn.statement(self)?.query(&jdbc_params::JdbcParameter::paramsToSql(params)[..]).map_err(DriverError::from)
+    }
+
+    fn executeRows(&self, conn: &PsqlConnection, params: &[Vec<&jdbc_params::JdbcParameter>], stop: bool) -> Result<BatchResult> {
+        let statement = conn.statement(self)?;
+        let isBatch = params.len() > 1;
+        let mut result = BatchResult::new();
+
+        match batch::MultiRowInsert::parse(&self.query) {
+            Some(ref insert) if isBatch => executeMultiRow(conn, statement, insert, params, stop, &mut result)?,
+            _ => executeEach(conn, statement, params, isBatch, stop, &mut result)?,
+        }
+
+        Ok(result)
+    }
+
+    /// Executes the rows until the first failure, which rolls back all of them. Outside of a transaction, the batch
+    /// runs in a transaction of its own.
+    fn executeAtomically(&self, conn: &mut PsqlConnection, params: &[Vec<&jdbc_params::JdbcParameter>]) -> Result<BatchResult> {
+        let ownsTransaction = !conn.inTransaction;
+        let savepoint = batchSavepoint();
+        if ownsTransaction {
+            conn.startTransaction()?;
+        } else {
+            conn.connection.batch_execute(&format!("SAVEPOINT {}", savepoint))?;
+        }
+
+        let executed = self.executeRows(conn, params, true);
+        let failed = executed.as_ref().map(|r| !r.errors.is_empty()).unwrap_or(true);
+
+        match (ownsTransaction, failed) {
+            (true, false) => conn.commitTransaction()?,
+            (true, true) => conn.rollbackTransaction()?,
+            (false, false) => conn.connection.batch_execute(&format!("RELEASE SAVEPOINT {}", savepoint))?,
+            (false, true) => conn.connection.batch_execute(&format!(
+                "ROLLBACK TO SAVEPOINT {0}; RELEASE SAVEPOINT {0}",
+                savepoint
+            ))?,
+        }
+
+        executed.map(|mut result| {
+            if failed {
+                result.counts = vec![-3; params.len()];
+            }
+
+            result
+        })
+    }
+}
+
+/// What a batch does after one of its rows failed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BatchErrorPolicy {
+    /// Executes the remaining rows. Within a transaction, every row runs in a savepoint, so that a failed row
+    /// doesn't abort the transaction.
+    Continue,
+    /// Skips the remaining rows, the counts end with the failed row.
+    StopAtFirstError,
+    /// Rolls back the rows executed before the failure, all rows are counted as failed.
+    RollBack,
+}
+
+pub fn batchErrorPolicy(policy: i32) -> Result<BatchErrorPolicy> {
+    match policy {
+        0 => Ok(BatchErrorPolicy::Continue),
+        1 => Ok(BatchErrorPolicy::StopAtFirstError),
+        2 => Ok(BatchErrorPolicy::RollBack),
+        x => Err(DriverError::GenericError(format!("Invalid batch error policy: {}", x))),
+    }
+}
+
+/// Failed rows are counted as -3 (`Statement.EXECUTE_FAILED`) and reported in `errors` by their index in the batch.
+pub struct BatchResult {
+    pub counts: Vec<i32>,
+    pub errors: Vec<BatchError>,
+}
+
+impl BatchResult {
+    fn new() -> BatchResult {
+        BatchResult {
+            counts: Vec::new(),
+            errors: Vec::new(),
+        }
+    }
+
+    fn failed(&mut self, e: DriverError) {
+        debug!("Error during prep exec: {:?}", e);
+        self.errors.push(BatchError::new(self.counts.len(), e));
+        self.counts.push(-3);
+    }
+}
+
+/// Errors that don't come from the database have the codes of `errorToCallResult`. `constraint` is set for
+/// violations of a named constraint.
+#[derive(Serialize)]
+pub struct BatchError {
+    index: usize,
+    code: String,
+    message: String,
+    constraint: Option<String>,
+}
+
+impl BatchError {
+    fn new(index: usize, e: DriverError) -> BatchError {
+        if let DriverError::PsqlError(ref e) = e {
+            if let Some(dbErr) = e.as_db() {
+                return BatchError {
+                    index: index,
+                    code: String::from(dbErr.code.code()),
+                    message: dbErr.message.clone(),
+                    constraint: dbErr.constraint.clone(),
+                };
             }
         }
 
-        Ok(counts)
+        BatchError {
+            index: index,
+            code: String::from(if let DriverError::PsqlError(_) = e { "-1" } else { "-2" }),
+            message: format!("[Rust] {:?}", e),
+            constraint: None,
+        }
+    }
+}
+
+fn executeEach(
+    conn: &PsqlConnection,
+    statement: &Statement,
+    params: &[Vec<&jdbc_params::JdbcParameter>],
+    isBatch: bool,
+    stop: bool,
+    result: &mut BatchResult,
+) -> Result<()> {
+    for param in params {
+        let execute = || statement.execute(&jdbc_params::JdbcParameter::paramsToSql(param.clone())[..]).map_err(DriverError::from);
+        let executed = if isBatch && !stop { conn.savepointed(execute) } else { execute() };
+
+        match executed {
+            Ok(count) => result.counts.push(count as i32),
+            Err(e) => {
+                if !isBatch {
+                    return Err(e);
+                }
+
+                result.failed(e);
+                if stop {
+                    break;
+                }
+            }
+        }
     }
 
-    pub fn query(&self, params: Vec<&jdbc_params::JdbcParameter>) -> Result<Rows> {
-        self.statement.query(&jdbc_params::JdbcParameter::paramsToSql(params)[..]).map_err(DriverError::from)
+    Ok(())
+}
+
+/// A chunk that fails is executed again row by row, so that the result shows which of its rows failed. Rows of
+/// chunks that inserted a different number of rows, e.g. because a trigger skipped some, are counted as -2
+/// (`Statement.SUCCESS_NO_INFO`).
+fn executeMultiRow(
+    conn: &PsqlConnection,
+    single: &Statement,
+    insert: &batch::MultiRowInsert,
+    params: &[Vec<&jdbc_params::JdbcParameter>],
+    stop: bool,
+    result: &mut BatchResult,
+) -> Result<()> {
+    let types = single.param_types();
+    let chunkSize = batch::chunkSize(types.len());
+
+    for chunk in params.chunks(chunkSize) {
+        if stop && !result.errors.is_empty() {
+            break;
+        }
+
+        let inserted = conn.savepointed(|| {
+            // Not cached, the statement is deallocated again once the chunk is inserted. Preparing it is cheap
+            // compared to inserting thousands of rows.
+            let query = insert.statement(chunk.len(), types.len());
+            let multi = conn.connection.prepare(&query)?;
+
+            // Parameters used in expressions could be inferred to other types than in the single row statement.
+            let sameTypes = multi.param_types().len() == types.len() * chunk.len()
+                && (types.is_empty() || multi.param_types().chunks(types.len()).all(|t| t == types));
+            if !sameTypes {
+                return Ok(None);
+            }
+
+            let chunkParams: Vec<&jdbc_params::JdbcParameter> = chunk.iter().flat_map(|row| row.iter().cloned()).collect();
+            Ok(Some(multi.execute(&jdbc_params::JdbcParameter::paramsToSql(chunkParams)[..])?))
+        });
+
+        match inserted {
+            Ok(Some(count)) if count as usize == chunk.len() => result.counts.extend(vec![1; chunk.len()]),
+            Ok(Some(_)) => result.counts.extend(vec![-2; chunk.len()]),
+            Ok(None) => executeEach(conn, single, chunk, true, stop, result)?,
+            Err(e) => {
+                debug!("Multi-row insert failed, executing the rows one by one: {:?}", e);
+                executeEach(conn, single, chunk, true, stop, result)?
+            }
+        }
     }
+
+    Ok(())
 }
 
-impl<'a> Drop for PsqlPreparedStatement<'a> {
+/// A server side cursor. It doesn't borrow the connection it was opened on, every fetch has to pass that connection.
+/// The cursor ends with the transaction it was opened in, `transaction` identifies that transaction.
+#[no_mangle]
+#[allow(non_snake_case)]
+pub struct PsqlCursor {
+    name: String,
+    transaction: u64,
+    pub textFallback: bool,
+}
+
+static CURSOR_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+impl Drop for PsqlPreparedStatement {
     fn drop(&mut self) {
         trace!("Dropping prepared statement");
     }
@@ -99,19 +453,41 @@ pub enum DriverError {
     JsonError(serde_json::Error),
     PsqlError(postgres::Error),
     GenericError(String),
+    InvalidHandle(String),
+    ReentrantUse(String),
 }
 
 pub type Result<T> = result::Result<T, DriverError>;
 
-pub fn connect<'a>(url: String) -> PsqlConnection<'a> {
-    let conn = Connection::connect(url, TlsMode::None).unwrap();
-    return PsqlConnection {
-        connection: conn,
-        transaction: RefCell::new(None),
+/// Besides the TLS parameters, the URL may contain `textFallback=true` to return columns of unhandled types
+/// the text fallback can decode (see `text_fallback::isSupported`) as text instead of failing the query.
+pub fn connect(url: String) -> Result<PsqlConnection> {
+    let (url, parameters) = url_params::takeParameters(&url, &["textFallback"])?;
+    let textFallback = match parameters.last().map(|p| p.1.as_str()) {
+        None | Some("false") => false,
+        Some("true") => true,
+        Some(x) => return Err(DriverError::GenericError(format!("Invalid textFallback: {}", x))),
     };
+
+    let tlsConfig = tls::TlsConfig::from_url(&url)?;
+    let handshake = tlsConfig.handshake()?;
+    let conn = Connection::connect(tlsConfig.url.as_str(), tlsConfig.tls_mode(&handshake))?;
+
+    Ok(PsqlConnection {
+        statements: HashMap::new(),
+        connection: Box::new(conn),
+        inTransaction: false,
+        endedTransactions: 0,
+        savepoints: Vec::new(),
+        transactionOptions: TransactionOptions::default(),
+        textFallback: textFallback,
+    })
 }
 
-impl<'a> Drop for PsqlConnection<'a> {
+// The connection isn't Sync, the statements borrowing it are only used by whoever holds the `PsqlConnection`.
+unsafe impl Send for PsqlConnection {}
+
+impl Drop for PsqlConnection {
     fn drop(&mut self) {
         trace!("Dropping psql connection");
     }
@@ -123,20 +499,38 @@ impl From<postgres::Error> for DriverError {
     }
 }
 
+impl From<Box<StdErr + Sync + Send>> for DriverError {
+    fn from(e: Box<StdErr + Sync + Send>) -> Self {
+        DriverError::GenericError(e.to_string())
+    }
+}
+
+impl From<io::Error> for DriverError {
+    fn from(e: io::Error) -> Self {
+        DriverError::GenericError(e.to_string())
+    }
+}
+
 impl From<serde_json::Error> for DriverError {
     fn from(e: serde_json::Error) -> Self {
         DriverError::JsonError(e)
     }
 }
 
-impl From<cell::BorrowMutError> for DriverError {
-    fn from(e: cell::BorrowMutError) -> Self {
+impl From<format::ParseError> for DriverError {
+    fn from(e: format::ParseError) -> Self {
         DriverError::GenericError(e.to_string())
     }
 }
 
-impl From<format::ParseError> for DriverError {
-    fn from(e: format::ParseError) -> Self {
+impl From<openssl::error::ErrorStack> for DriverError {
+    fn from(e: openssl::error::ErrorStack) -> Self {
+        DriverError::GenericError(e.to_string())
+    }
+}
+
+impl From<base64::DecodeError> for DriverError {
+    fn from(e: base64::DecodeError) -> Self {
         DriverError::GenericError(e.to_string())
     }
 }
@@ -147,20 +541,38 @@ impl From<uuid::ParseError> for DriverError {
     }
 }
 
-impl<'a> PsqlConnection<'a> {
-    pub fn prepareStatement(&self, query: String) -> Result<PsqlPreparedStatement> {
-        let stmt = self.connection.prepare(query.as_str())?;
-        Ok(PsqlPreparedStatement{ statement: stmt })
+/// Transactions are tracked by the connection and controlled with plain SQL. A postgres `Transaction` borrows its
+/// connection, so it can't be stored in the connection that is handed across the FFI boundary.
+impl PsqlConnection {
+    pub fn prepareStatement(&mut self, query: String) -> Result<PsqlPreparedStatement> {
+        let statement = self.connection.prepare(query.as_str())?;
+        let id = STATEMENT_COUNTER.fetch_add(1, Ordering::SeqCst);
+
+        // Safe as long as the statement doesn't outlive the connection, see `statements`. It is only handed out
+        // with the lifetime of a borrow of `self`.
+        let statement = unsafe { mem::transmute::<Statement, Statement<'static>>(statement) };
+        self.statements.insert(id, statement);
+
+        Ok(PsqlPreparedStatement {
+            id: id,
+            query: query,
+            textFallback: self.textFallback,
+        })
+    }
+
+    fn statement(&self, statement: &PsqlPreparedStatement) -> Result<&Statement> {
+        self.statements.get(&statement.id).ok_or_else(|| DriverError::GenericError(format!("Statement {} was closed", statement.query)))
+    }
+
+    /// Deallocates the statement on the server.
+    pub fn closeStatement(&mut self, statement: &PsqlPreparedStatement) {
+        self.statements.remove(&statement.id);
     }
 
     pub fn query(&self, query: String, params: Vec<&jdbc_params::JdbcParameter>) -> Result<Rows> {
         trace!("Querying {} with params: {:?}", query, params);
 
-        let mutRef = self.transaction.try_borrow_mut()?;
-        let rows = match *mutRef {
-            Some(ref t) => t.query(&*query, &jdbc_params::JdbcParameter::paramsToSql(params)[..])?,
-            None => self.connection.query(&*query, &jdbc_params::JdbcParameter::paramsToSql(params)[..])?,
-        };
+        let rows = self.connection.query(&*query, &jdbc_params::JdbcParameter::paramsToSql(params)[..])?;
 
         return Ok(rows);
     }
@@ -168,39 +580,284 @@ impl<'a> PsqlConnection<'a> {
     pub fn execute(&self, query: String, params: Vec<&jdbc_params::JdbcParameter>) -> Result<u64> {
         trace!("Executing {} with params: {:?}", query, params);
 
-        let mutRef = self.transaction.try_borrow_mut()?;
-        let result = match *mutRef {
-            Some(ref t) => {
-                t.execute(&*query, &jdbc_params::JdbcParameter::paramsToSql(params)[..])?
-            }
-            None => self.connection.execute(&*query, &jdbc_params::JdbcParameter::paramsToSql(params)[..])?,
-        };
+        let result = self.connection.execute(&*query, &jdbc_params::JdbcParameter::paramsToSql(params)[..])?;
 
         return Ok(result);
     }
 
-    pub fn close(self) {}
+    /// Only queries DECLARE accepts (SELECT, VALUES and WITH) can be opened as cursor, and only in a transaction:
+    /// the rows are produced as they are fetched. A holdable cursor would compute all of them up front, which defeats
+    /// the purpose, and a transaction opened for the cursor would swallow the statements run while it is open.
+    pub fn openCursor(&self, query: String, params: Vec<&jdbc_params::JdbcParameter>) -> Result<PsqlCursor> {
+        if !self.inTransaction {
+            return Err(DriverError::GenericError(String::from("Cursors can only be opened in a transaction")));
+        }
+
+        let name = format!("prisma_cursor_{}", CURSOR_COUNTER.fetch_add(1, Ordering::SeqCst));
+        self.execute(format!("DECLARE {} NO SCROLL CURSOR FOR {}", name, query), params)?;
+
+        Ok(PsqlCursor {
+            name: name,
+            transaction: self.endedTransactions,
+            textFallback: self.textFallback,
+        })
+    }
+
+    /// Fetches up to `count` rows. Fewer rows than requested means the cursor is exhausted.
+    pub fn fetchCursor(&self, cursor: &PsqlCursor, count: u32) -> Result<Rows> {
+        if count == 0 {
+            return Err(DriverError::GenericError(String::from("Cursor fetch size must be positive")));
+        }
+
+        if !self.isOpen(cursor) {
+            return Err(DriverError::GenericError(format!("Cursor {} was closed by the end of its transaction", cursor.name)));
+        }
+
+        self.query(format!("FETCH FORWARD {} FROM {}", count, cursor.name), Vec::new())
+    }
+
+    /// Closing a cursor that ended with its transaction is a no-op.
+    pub fn closeCursor(&self, cursor: &PsqlCursor) -> Result<()> {
+        if self.isOpen(cursor) {
+            self.execute(format!("CLOSE {}", cursor.name), Vec::new())?;
+        }
+
+        Ok(())
+    }
+
+    fn isOpen(&self, cursor: &PsqlCursor) -> bool {
+        self.inTransaction && cursor.transaction == self.endedTransactions
+    }
+
+    /// Cheap liveness check, used by the pool before handing out an idle connection.
+    pub fn isValid(&self) -> bool {
+        !self.connection.is_desynchronized() && self.connection.batch_execute("SELECT 1").is_ok()
+    }
+
+    /// Rolls back an open transaction and resets all session state, so that the connection can be reused.
+    pub fn reset(&mut self) -> Result<()> {
+        self.rollbackTransaction()?;
+        self.statements.clear();
+
+        // DISCARD ALL without DEALLOCATE ALL, which would invalidate the statement cache of the connection.
+        self.connection.batch_execute(
+            "CLOSE ALL; SET SESSION AUTHORIZATION DEFAULT; RESET ALL; UNLISTEN *; \
+             SELECT pg_advisory_unlock_all(); DISCARD PLANS; DISCARD TEMP; DISCARD SEQUENCES",
+        )?;
+        self.transactionOptions = TransactionOptions::default();
+
+        Ok(())
+    }
+
+    pub fn startTransaction(&mut self) -> Result<()> {
+        let options = self.transactionOptions;
+        self.startTransactionWith(options)
+    }
+
+    pub fn startTransactionWith(&mut self, options: TransactionOptions) -> Result<()> {
+        if self.inTransaction {
+            return Err(DriverError::GenericError(String::from("A transaction is already open on this connection")));
+        }
 
-    pub fn startTransaction(&'a mut self) -> Result<()> {
-        let ta = self.connection.transaction()?;
-        self.transaction.replace(Some(ta));
+        self.connection.batch_execute(&format!("BEGIN {}", options.modes()))?;
+        self.inTransaction = true;
 
         return Ok(());
     }
 
-    pub fn commitTransaction(&self) -> Result<()> {
-        let taOpt = self.transaction.replace(None);
-        match taOpt {
-            Some(ta) => Ok(ta.commit()?),
-            None     => Ok(()),
+    /// Runs the statements in a transaction of their own, with the characteristics set on the connection.
+    /// Retrying the transaction is up to the caller, see `withRetries`.
+    pub fn executeTransaction(&mut self, statements: &[jdbc_params::BatchStatement]) -> Result<Vec<i32>> {
+        self.ensureNoTransaction("run a retried transaction")?;
+
+        let options = self.transactionOptions;
+        self.startTransactionWith(options)?;
+
+        let counts: Result<Vec<i32>> = statements.iter().map(|statement| {
+            self.execute(statement.query.clone(), statement.params.iter().collect()).map(|count| count as i32)
+        }).collect();
+
+        match counts {
+            Ok(counts) => self.commitTransaction().map(|_| counts),
+            Err(e) => {
+                // The failure of the statement is the one to report, a failing rollback ends the transaction as well.
+                if let Err(rollbackErr) = self.rollbackTransaction() {
+                    debug!("Rollback after failed statement failed: {:?}", rollbackErr);
+                }
+
+                Err(e)
+            }
+        }
+    }
+
+    /// Applies to transactions started afterwards, like the JDBC setters do.
+    pub fn setTransactionIsolation(&mut self, level: IsolationLevel) -> Result<()> {
+        self.ensureNoTransaction("change the transaction isolation level")?;
+        self.transactionOptions.isolationLevel = level;
+
+        Ok(())
+    }
+
+    pub fn setReadOnly(&mut self, readOnly: bool) -> Result<()> {
+        self.ensureNoTransaction("change the read-only mode")?;
+        self.transactionOptions.readOnly = readOnly;
+
+        Ok(())
+    }
+
+    fn ensureNoTransaction(&self, action: &str) -> Result<()> {
+        if self.inTransaction {
+            return Err(DriverError::GenericError(format!("Cannot {} in the middle of a transaction", action)));
+        }
+
+        Ok(())
+    }
+
+    /// Without an open transaction, committing is a no-op.
+    pub fn commitTransaction(&mut self) -> Result<()> {
+        self.endTransaction("COMMIT")
+    }
+
+    /// Without an open transaction, rolling back is a no-op.
+    pub fn rollbackTransaction(&mut self) -> Result<()> {
+        self.endTransaction("ROLLBACK")
+    }
+
+    /// The transaction is over even if the statement fails, Postgres rolls back a transaction it can't commit.
+    fn endTransaction(&mut self, statement: &str) -> Result<()> {
+        if !self.inTransaction {
+            return Ok(());
+        }
+
+        self.inTransaction = false;
+        self.endedTransactions += 1;
+        self.savepoints.clear();
+        self.connection.batch_execute(statement)?;
+
+        Ok(())
+    }
+
+    /// Loads the data with a single `COPY ... FROM STDIN` and returns the number of rows written.
+    pub fn copyIn(&self, target: &copy::CopyTarget, data: copy::CopyData) -> Result<u64> {
+        let rows = match data {
+            copy::CopyData::Csv(csv) => {
+                let stmt = self.connection.prepare(&target.copyStatement("csv"))?;
+                stmt.copy_in(&[], &mut csv.as_bytes())?
+            }
+            copy::CopyData::Rows(rows) => {
+                let types = self.connection.prepare(&target.typesStatement())?.columns().iter().map(|c| c.type_().clone()).collect();
+                let stmt = self.connection.prepare(&target.copyStatement("binary"))?;
+                stmt.copy_in(&[], &mut copy::BinaryRows::new(types, rows))?
+            }
+        };
+
+        Ok(rows)
+    }
+
+    /// Writes the output of a `COPY ... TO STDOUT` statement, see `copy::ExportFormat`. The statement is cached,
+    /// `openExport` prepares it up front to report errors in the query right away.
+    /// Fails like executing the statement would if it is invalid. The statement is deallocated again right away.
+    pub fn checkStatement(&self, statement: &str) -> Result<()> {
+        self.connection.prepare(statement)?;
+        Ok(())
+    }
+
+    pub fn copyOut<W: io::Write>(&self, statement: &str, out: &mut W) -> Result<u64> {
+        let stmt = self.connection.prepare(statement)?;
+        Ok(stmt.copy_out(&[], out)?)
+    }
+
+    /// Runs `f` within a savepoint if a transaction is open, so that a failure of `f` doesn't abort the transaction.
+    /// The savepoint isn't tracked in `savepoints`, it is gone again once `f` returns.
+    fn savepointed<T, F>(&self, f: F) -> Result<T> where F: FnOnce() -> Result<T> {
+        if !self.inTransaction {
+            return f();
+        }
+
+        let savepoint = batchSavepoint();
+        self.connection.batch_execute(&format!("SAVEPOINT {}", savepoint))?;
+        match f() {
+            Ok(v) => {
+                self.connection.batch_execute(&format!("RELEASE SAVEPOINT {}", savepoint))?;
+                Ok(v)
+            }
+            Err(e) => {
+                self.connection.batch_execute(&format!("ROLLBACK TO SAVEPOINT {0}; RELEASE SAVEPOINT {0}", savepoint))?;
+                Err(e)
+            }
         }
     }
 
-    pub fn rollbackTransaction(&self) -> Result<()> {
-        let taOpt = self.transaction.replace(None);
-        match taOpt {
-            Some(ta) => Ok(ta.set_rollback()),
-            None     => Ok(()),
+    /// Savepoints are issued as SQL on the open transaction instead of through `Transaction::savepoint`,
+    /// as nested transactions borrow their parent and can't be handed across the FFI boundary.
+    pub fn createSavepoint(&mut self, name: String) -> Result<()> {
+        if !self.inTransaction {
+            return Err(DriverError::GenericError(format!("Savepoint {} requires an open transaction", name)));
         }
+
+        self.execute(format!("SAVEPOINT {}", quoteIdentifier(&name)), Vec::new())?;
+        self.savepoints.push(name);
+
+        Ok(())
+    }
+
+    /// Only the innermost savepoint can be released.
+    pub fn releaseSavepoint(&mut self, name: String) -> Result<()> {
+        match self.savepoints.last() {
+            Some(innermost) if *innermost == name => (),
+            Some(innermost) => return Err(DriverError::GenericError(format!(
+                "Can't release savepoint {}, the innermost savepoint is {}",
+                name, innermost
+            ))),
+            None => return Err(DriverError::GenericError(format!("Can't release savepoint {}, no savepoint is active", name))),
+        }
+
+        self.execute(format!("RELEASE SAVEPOINT {}", quoteIdentifier(&name)), Vec::new())?;
+        self.savepoints.pop();
+
+        Ok(())
+    }
+
+    /// Rolling back to a savepoint discards all savepoints nested in it, the savepoint itself stays active.
+    pub fn rollbackToSavepoint(&mut self, name: String) -> Result<()> {
+        let position = self.savepoints.iter().rposition(|s| *s == name);
+        let position = match position {
+            Some(p) => p,
+            None => return Err(DriverError::GenericError(format!("Can't roll back to savepoint {}, it is not active", name))),
+        };
+
+        self.execute(format!("ROLLBACK TO SAVEPOINT {}", quoteIdentifier(&name)), Vec::new())?;
+        self.savepoints.truncate(position + 1);
+
+        Ok(())
+    }
+}
+
+/// Savepoints of batches are numbered, so that they can't be confused with each other or with those of the caller.
+fn batchSavepoint() -> String {
+    format!("jdbc_native_batch_{}", SAVEPOINT_COUNTER.fetch_add(1, Ordering::SeqCst))
+}
+
+static SAVEPOINT_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+pub fn quoteIdentifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_doubles_up_to_the_maximum() {
+        let retry = RetryPolicy { maxAttempts: 100, backoff: Duration::from_millis(10) };
+        assert_eq!(retry.delay(1), Duration::from_millis(10));
+        assert_eq!(retry.delay(3), Duration::from_millis(40));
+        assert_eq!(retry.delay(11), Duration::from_millis(10240));
+        assert_eq!(retry.delay(99), Duration::from_millis(10240));
+
+        let retry = RetryPolicy { maxAttempts: 100, backoff: Duration::from_secs(u64::max_value()) };
+        assert_eq!(retry.delay(1), Duration::from_secs(MAX_DELAY_SECS));
+        assert_eq!(retry.delay(99), Duration::from_secs(MAX_DELAY_SECS));
     }
 }
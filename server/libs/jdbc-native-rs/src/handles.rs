@@ -0,0 +1,352 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, MutexGuard};
+
+use copy::Export;
+use driver::{DriverError, PsqlConnection, PsqlCursor, PsqlPreparedStatement, Result};
+use pool::PsqlPool;
+
+/// Opaque reference to a native object held by the JVM. Handles are never reused, so a stale handle can't
+/// resolve to an object opened later. 0 is never issued and stands for "no object" in `HandleAndError`.
+pub type Handle = i64;
+
+/// Send + Sync, so that the JVM can call into a connection from any thread. Concurrent calls wait for each other.
+pub type SharedConnection = Arc<Mutex<PsqlConnection>>;
+
+/// All objects handed to the JVM live here instead of behind raw pointers, so that double closes and
+/// use after close are reported as errors. Statements and cursors belong to the connection they were
+/// created on and are invalidated when it is closed or returned to its pool.
+/// Connections checked out of a pool are tagged with the pool, see `insertPooledConnection`.
+struct Registry {
+    nextHandle: Handle,
+    connections: HashMap<Handle, SharedConnection>,
+    statements: HashMap<Handle, Child<PsqlPreparedStatement>>,
+    cursors: HashMap<Handle, Child<PsqlCursor>>,
+    exports: HashMap<Handle, Child<Mutex<Export>>>,
+    pools: HashMap<Handle, Arc<PsqlPool>>,
+    pooled: HashMap<Handle, Handle>,
+}
+
+struct Child<T> {
+    connection: Handle,
+    value: Arc<T>,
+}
+
+// Connections locked by the current thread. Locking one of them again would deadlock.
+thread_local!(static HELD: RefCell<Vec<Handle>> = RefCell::new(Vec::new()));
+
+lazy_static! {
+    static ref REGISTRY: Mutex<Registry> = Mutex::new(Registry {
+        nextHandle: 1,
+        connections: HashMap::new(),
+        statements: HashMap::new(),
+        cursors: HashMap::new(),
+        exports: HashMap::new(),
+        pools: HashMap::new(),
+        pooled: HashMap::new(),
+    });
+}
+
+impl Registry {
+    fn issue(&mut self) -> Handle {
+        let handle = self.nextHandle;
+        self.nextHandle += 1;
+        handle
+    }
+
+    fn connection(&self, handle: Handle) -> Result<SharedConnection> {
+        self.connections.get(&handle).cloned().ok_or_else(|| invalid("connection", handle))
+    }
+
+    /// An export holds the connection until all of its data has been read, calls waiting for it could deadlock
+    /// the thread that is supposed to read the data. The connection can be used again once the export is closed.
+    fn usableConnection(&self, handle: Handle) -> Result<SharedConnection> {
+        if self.exports.values().any(|e| e.connection == handle) {
+            return Err(DriverError::GenericError(format!(
+                "Connection {} is busy with an export, close it first",
+                handle
+            )));
+        }
+
+        self.connection(handle)
+    }
+
+    /// Drops the children of the connection. Cursors still hold server side state, which ends with the
+    /// session or is cleaned up when a pooled connection is reset. Dropped exports are cancelled.
+    fn removeChildren(&mut self, connection: Handle) {
+        self.statements.retain(|_, s| s.connection != connection);
+        self.cursors.retain(|_, c| c.connection != connection);
+        self.exports.retain(|_, e| e.connection != connection);
+    }
+}
+
+pub fn insertConnection(connection: PsqlConnection) -> Result<Handle> {
+    let mut registry = lock()?;
+    let handle = registry.issue();
+    registry.connections.insert(handle, Arc::new(Mutex::new(connection)));
+
+    Ok(handle)
+}
+
+/// A connection checked out of `pool`, it can only be checked in to the same pool. The pool can't be closed while
+/// the connection counts as checked out, see `PsqlPool::close`.
+pub fn insertPooledConnection(pool: Handle, connection: PsqlConnection) -> Result<Handle> {
+    let mut registry = lock()?;
+    let handle = registry.issue();
+    registry.connections.insert(handle, Arc::new(Mutex::new(connection)));
+    registry.pooled.insert(handle, pool);
+
+    Ok(handle)
+}
+
+/// The connection is closed once calls that are still running on it have finished. A pooled connection is closed
+/// as well instead of being reused, its pool can open a new one in its place.
+pub fn removeConnection(handle: Handle) -> Result<()> {
+    let pool = {
+        let mut registry = lock()?;
+        registry.connections.remove(&handle).ok_or_else(|| invalid("connection", handle))?;
+        registry.removeChildren(handle);
+
+        match registry.pooled.remove(&handle) {
+            Some(pool) => registry.pools.get(&pool).cloned(),
+            None => None,
+        }
+    };
+
+    match pool {
+        Some(pool) => {
+            pool.release()?;
+            PsqlPool::refill(&pool);
+            Ok(())
+        }
+        None => Ok(()),
+    }
+}
+
+/// Removes the connection to hand it back to `pool`. Fails if the connection wasn't checked out of that pool
+/// or if calls are still running on it.
+pub fn takePooledConnection(pool: Handle, handle: Handle) -> Result<PsqlConnection> {
+    let mut registry = lock()?;
+    let shared = registry.connection(handle)?;
+
+    if registry.pooled.get(&handle) != Some(&pool) {
+        return Err(DriverError::GenericError(format!("Connection {} wasn't checked out of pool {}", handle, pool)));
+    }
+
+    // New references are only created under the registry lock, ours and the registry's have to be the last ones.
+    if Arc::strong_count(&shared) > 2 {
+        return Err(DriverError::GenericError(format!("Connection {} is still in use", handle)));
+    }
+
+    registry.connections.remove(&handle);
+    registry.pooled.remove(&handle);
+    registry.removeChildren(handle);
+
+    match Arc::try_unwrap(shared) {
+        Ok(connection) => connection.into_inner().map_err(|e| DriverError::GenericError(format!("Connection lock poisoned: {}", e))),
+        Err(_) => Err(DriverError::GenericError(format!("Connection {} is still in use", handle))),
+    }
+}
+
+pub fn withConnection<T, F>(handle: Handle, f: F) -> Result<T> where F: FnOnce(&mut PsqlConnection) -> Result<T> {
+    let shared = lock()?.usableConnection(handle)?;
+    let _held = Held::acquire(handle)?;
+    let mut connection = lockConnection(&shared)?;
+
+    f(&mut connection)
+}
+
+pub fn insertStatement(connection: Handle, statement: PsqlPreparedStatement) -> Result<Handle> {
+    let mut registry = lock()?;
+    registry.connection(connection)?;
+
+    let handle = registry.issue();
+    registry.statements.insert(handle, Child {
+        connection: connection,
+        value: Arc::new(statement),
+    });
+
+    Ok(handle)
+}
+
+/// Deallocates the statement on its connection, which has to be usable for that. The statements of a closed
+/// connection are already gone with it.
+pub fn removeStatement(handle: Handle) -> Result<()> {
+    let (parent, shared, statement) = {
+        let mut registry = lock()?;
+        let parent = registry.statements.get(&handle).ok_or_else(|| invalid("statement", handle))?.connection;
+        let shared = registry.usableConnection(parent)?;
+        (parent, shared, registry.statements.remove(&handle).unwrap().value)
+    };
+
+    let _held = Held::acquire(parent)?;
+    lockConnection(&shared)?.closeStatement(&statement);
+    Ok(())
+}
+
+pub fn withStatement<T, F>(handle: Handle, f: F) -> Result<T> where F: FnOnce(&mut PsqlConnection, &PsqlPreparedStatement) -> Result<T> {
+    let (parent, shared, statement) = {
+        let registry = lock()?;
+        let statement = registry.statements.get(&handle).ok_or_else(|| invalid("statement", handle))?;
+        (statement.connection, registry.usableConnection(statement.connection)?, statement.value.clone())
+    };
+
+    let _held = Held::acquire(parent)?;
+    let mut connection = lockConnection(&shared)?;
+    f(&mut connection, &statement)
+}
+
+pub fn insertCursor(connection: Handle, cursor: PsqlCursor) -> Result<Handle> {
+    let mut registry = lock()?;
+    registry.connection(connection)?;
+
+    let handle = registry.issue();
+    registry.cursors.insert(handle, Child {
+        connection: connection,
+        value: Arc::new(cursor),
+    });
+
+    Ok(handle)
+}
+
+/// The cursor is removed even if the callback fails, closing it is not retried.
+pub fn removeCursor<T, F>(connection: Handle, handle: Handle, f: F) -> Result<T> where F: FnOnce(&PsqlConnection, &PsqlCursor) -> Result<T> {
+    let (shared, cursor) = {
+        let mut registry = lock()?;
+        let cursor = ownCursor(&registry, connection, handle)?;
+        registry.cursors.remove(&handle);
+
+        (registry.usableConnection(connection)?, cursor)
+    };
+
+    let _held = Held::acquire(connection)?;
+    let connection = lockConnection(&shared)?;
+    f(&connection, &cursor)
+}
+
+pub fn withCursor<T, F>(connection: Handle, handle: Handle, f: F) -> Result<T> where F: FnOnce(&PsqlConnection, &PsqlCursor) -> Result<T> {
+    let (shared, cursor) = {
+        let registry = lock()?;
+        (registry.usableConnection(connection)?, ownCursor(&registry, connection, handle)?)
+    };
+
+    let _held = Held::acquire(connection)?;
+    let connection = lockConnection(&shared)?;
+    f(&connection, &cursor)
+}
+
+fn ownCursor(registry: &Registry, connection: Handle, handle: Handle) -> Result<Arc<PsqlCursor>> {
+    match registry.cursors.get(&handle) {
+        Some(ref c) if c.connection == connection => Ok(c.value.clone()),
+        Some(_) => Err(DriverError::GenericError(format!("Cursor {} wasn't opened on connection {}", handle, connection))),
+        None => Err(invalid("cursor", handle)),
+    }
+}
+
+/// The export is started right away, the connection is busy until it is closed. The export thread is spawned
+/// after the registry is released, its handle isn't handed out before it has started.
+pub fn insertExport<F>(connection: Handle, start: F) -> Result<Handle> where F: FnOnce(SharedConnection) -> Export {
+    let (handle, shared, export) = {
+        let mut registry = lock()?;
+        let shared = registry.usableConnection(connection)?;
+
+        let handle = registry.issue();
+        let export = Arc::new(Mutex::new(Export::pending()));
+        registry.exports.insert(handle, Child {
+            connection: connection,
+            value: export.clone(),
+        });
+
+        (handle, shared, export)
+    };
+
+    *export.lock().map_err(|e| DriverError::GenericError(format!("Export lock poisoned: {}", e)))? = start(shared);
+    Ok(handle)
+}
+
+pub fn withExport<T, F>(handle: Handle, f: F) -> Result<T> where F: FnOnce(&mut Export) -> Result<T> {
+    let export = lock()?.exports.get(&handle).map(|e| e.value.clone()).ok_or_else(|| invalid("export", handle))?;
+    let mut export = export.lock().map_err(|e| DriverError::GenericError(format!("Export lock poisoned: {}", e)))?;
+
+    f(&mut export)
+}
+
+pub fn removeExport<T, F>(handle: Handle, f: F) -> Result<T> where F: FnOnce(&mut Export) -> Result<T> {
+    let export = lock()?.exports.remove(&handle).map(|e| e.value).ok_or_else(|| invalid("export", handle))?;
+    let mut export = export.lock().map_err(|e| DriverError::GenericError(format!("Export lock poisoned: {}", e)))?;
+
+    f(&mut export)
+}
+
+pub fn insertPool(pool: PsqlPool) -> Result<Handle> {
+    let mut registry = lock()?;
+    let handle = registry.issue();
+    registry.pools.insert(handle, Arc::new(pool));
+
+    Ok(handle)
+}
+
+pub fn pool(handle: Handle) -> Result<Arc<PsqlPool>> {
+    lock()?.pools.get(&handle).cloned().ok_or_else(|| invalid("pool", handle))
+}
+
+/// The pool can only be closed once all connections have been returned, see `PsqlPool::close`. Calls that got hold of
+/// the pool before can't check out connections anymore.
+pub fn removePool(handle: Handle) -> Result<()> {
+    let mut registry = lock()?;
+    registry.pools.get(&handle).ok_or_else(|| invalid("pool", handle))?.close()?;
+    registry.pools.remove(&handle);
+
+    Ok(())
+}
+
+/// Marks a connection as locked by the current thread until dropped. Has to be acquired before the lock
+/// and dropped after it.
+struct Held(Handle);
+
+impl Held {
+    fn acquire(handle: Handle) -> Result<Held> {
+        HELD.with(|held| {
+            let mut held = held.borrow_mut();
+            if held.contains(&handle) {
+                return Err(DriverError::ReentrantUse(format!(
+                    "Connection {} is already in use by a call further up the stack of this thread",
+                    handle
+                )));
+            }
+
+            held.push(handle);
+            Ok(Held(handle))
+        })
+    }
+}
+
+impl Drop for Held {
+    fn drop(&mut self) {
+        let handle = self.0;
+        HELD.with(|held| held.borrow_mut().retain(|h| *h != handle));
+    }
+}
+
+fn invalid(kind: &str, handle: Handle) -> DriverError {
+    DriverError::InvalidHandle(format!("Invalid or closed {} handle: {}", kind, handle))
+}
+
+fn lock() -> Result<MutexGuard<'static, Registry>> {
+    REGISTRY
+        .lock()
+        .map_err(|e| DriverError::GenericError(format!("Handle registry lock poisoned: {}", e)))
+}
+
+/// A connection is only poisoned if a call panicked while using it, its session state is unknown afterwards.
+fn lockConnection(connection: &SharedConnection) -> Result<MutexGuard<PsqlConnection>> {
+    connection
+        .lock()
+        .map_err(|e| DriverError::GenericError(format!("Connection lock poisoned: {}", e)))
+}
+
+#[allow(dead_code)]
+fn assertSendSync() {
+    fn sendSync<T: Send + Sync>() {}
+    sendSync::<SharedConnection>();
+}
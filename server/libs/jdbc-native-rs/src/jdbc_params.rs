@@ -7,12 +7,13 @@ use num_traits::cast::FromPrimitive;
 use rust_decimal::Decimal;
 use serde_json;
 use uuid::Uuid;
+use base64;
 
 use postgres;
 use postgres::rows::{Row, Rows};
 use postgres::stmt::Statement;
 use postgres::transaction::Transaction;
-use postgres::types::{IsNull, ToSql, Type};
+use postgres::types::{IsNull, Kind, ToSql, Type};
 use postgres::{Connection, Result as PsqlResult, TlsMode};
 
 use std::boxed::Box;
@@ -23,6 +24,9 @@ use std::result;
 
 use driver::DriverError;
 use driver::Result;
+use numeric;
+use array;
+use temporal;
 
 #[derive(Debug, PartialEq)]
 pub enum JdbcParameter {
@@ -32,11 +36,28 @@ pub enum JdbcParameter {
     Null,
     Double(MagicFloat),
     DateTime(DateTime<Utc>),
+    DateTimeTz(DateTime<FixedOffset>),
+    Date(NaiveDate),
+    Time(NaiveTime),
+    TimeTz(NaiveTime, i32),
+    Interval(temporal::Interval),
+    Infinity(bool),
     Long(i64),
     UUID(Uuid),
+    Decimal(String),
+    Json(serde_json::Value),
+    Bytes(Vec<u8>),
+    Array(ArrayParameter),
 }
 
-#[derive(Serialize, Debug)]
+/// Elements are stored flattened in row-major order, with NULL elements as `JdbcParameter::Null`.
+#[derive(Debug, PartialEq)]
+pub struct ArrayParameter {
+    dimensions: Vec<usize>,
+    elements: Vec<JdbcParameter>,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq)]
 pub enum JdbcParameterType {
     Int,
     String,
@@ -46,7 +67,19 @@ pub enum JdbcParameterType {
     DateTime,
     Long,
     UUID,
-    VOID
+    VOID,
+    Decimal,
+    Json,
+    Bytes,
+    Array,
+    DateTimeTz,
+    Date,
+    Time,
+    TimeTz,
+    Interval,
+    Composite,
+    Range,
+    Unknown,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -58,6 +91,8 @@ pub struct MagicDateTime {
     pub minute: u32,
     pub seconds: u32,
     pub millis: u32,
+    #[serde(default)]
+    pub micros: Option<u32>,
 }
 
 impl JdbcParameter {
@@ -71,10 +106,20 @@ impl JdbcParameter {
             JdbcParameter::Null => param,
             JdbcParameter::Double(_) => param,
             JdbcParameter::DateTime(_) => param,
-            JdbcParameter::String(ref s) => s,
+            JdbcParameter::DateTimeTz(_) => param,
+            JdbcParameter::Date(_) => param,
+            JdbcParameter::Time(_) => param,
+            JdbcParameter::TimeTz(_, _) => param,
+            JdbcParameter::Interval(_) => param,
+            JdbcParameter::Infinity(_) => param,
+            JdbcParameter::String(_) => param,
             JdbcParameter::Boolean(ref b) => b,
-            JdbcParameter::Long(ref d) => d,
+            JdbcParameter::Long(_) => param,
             JdbcParameter::UUID(ref uid) => uid,
+            JdbcParameter::Decimal(_) => param,
+            JdbcParameter::Json(_) => param,
+            JdbcParameter::Bytes(ref bytes) => bytes,
+            JdbcParameter::Array(_) => param,
         }
     }
 }
@@ -114,46 +159,118 @@ impl ToSql for JdbcParameter {
     {
         match self {
             JdbcParameter::Null => Ok(IsNull::Yes),
-            JdbcParameter::DateTime(ref dt) => {
-                dt.to_sql(ty, out)
-            }
 
-            JdbcParameter::Int(ref magic) => {
-                let val = magic.underlying.replace(None);
-                match val {
-                    Some(t) => {
-                        match t {
-                            postgres::types::INT2 => out.write_i16::<BigEndian>(magic.value as i16).unwrap(),
-                            postgres::types::INT4 => out.write_i32::<BigEndian>(magic.value as i32).unwrap(),
-                            postgres::types::INT8 => out.write_i64::<BigEndian>(magic.value).unwrap(),
-                            x => error!("[Rust] Unhandled MagicInt OID: {}", x),
-                        }
-                    }
-                    x => panic!("[Rust] No underlying type present for MagicInt."),
-                }
+            // Enum values are written as their label, Postgres validates it.
+            JdbcParameter::String(ref s) => match *ty.kind() {
+                Kind::Enum(_) => {
+                    out.extend_from_slice(s.as_bytes());
+                    Ok(IsNull::No)
+                },
+                _ => s.to_sql_checked(ty, out),
+            },
+            // TIMESTAMP and TIMESTAMPTZ share the binary format, the UTC wall clock time is written to both.
+            JdbcParameter::DateTime(ref dt) => match *ty {
+                postgres::types::TIMESTAMP | postgres::types::TIMESTAMPTZ => dt.to_sql(ty, out),
+                ref x => Err(format!("DateTime parameter can't be written to a column of type {}", x).into()),
+            },
 
-                Ok(IsNull::No)
-            }
+            JdbcParameter::DateTimeTz(ref dt) => match *ty {
+                postgres::types::TIMESTAMP | postgres::types::TIMESTAMPTZ => dt.with_timezone(&Utc).to_sql(ty, out),
+                ref x => Err(format!("DateTimeTz parameter can't be written to a column of type {}", x).into()),
+            },
+
+            JdbcParameter::Date(ref date) => match *ty {
+                postgres::types::DATE => date.to_sql(ty, out),
+                ref x => Err(format!("Date parameter can't be written to a column of type {}", x).into()),
+            },
+
+            JdbcParameter::Time(ref time) => match *ty {
+                postgres::types::TIME => time.to_sql(ty, out),
+                postgres::types::TIMETZ => {
+                    temporal::writeTimeTz(time, 0, out).map_err(|e| format!("{:?}", e))?;
+                    Ok(IsNull::No)
+                },
+                ref x => Err(format!("Time parameter can't be written to a column of type {}", x).into()),
+            },
+
+            JdbcParameter::TimeTz(ref time, offset) => match *ty {
+                postgres::types::TIMETZ => {
+                    temporal::writeTimeTz(time, *offset, out).map_err(|e| format!("{:?}", e))?;
+                    Ok(IsNull::No)
+                },
+                ref x => Err(format!("TimeTz parameter can't be written to a column of type {}", x).into()),
+            },
+
+            JdbcParameter::Interval(ref interval) => match *ty {
+                postgres::types::INTERVAL => {
+                    temporal::writeInterval(interval, out).map_err(|e| format!("{:?}", e))?;
+                    Ok(IsNull::No)
+                },
+                ref x => Err(format!("Interval parameter can't be written to a column of type {}", x).into()),
+            },
+
+            JdbcParameter::Infinity(positive) => match *ty {
+                postgres::types::TIMESTAMP | postgres::types::TIMESTAMPTZ | postgres::types::DATE => {
+                    temporal::writeInfinity(*positive, *ty == postgres::types::DATE, out).map_err(|e| format!("{:?}", e))?;
+                    Ok(IsNull::No)
+                },
+                ref x => Err(format!("Infinity can't be written to a column of type {}", x).into()),
+            },
+
+            JdbcParameter::Int(ref magic) => match magic.underlying.replace(None) {
+                Some(t) => writeInteger(magic.value, &t, out),
+                None => Err("No underlying type present for MagicInt.".into()),
+            },
+
+            JdbcParameter::Long(value) => writeInteger(*value, ty, out),
+
+            JdbcParameter::Double(ref magic) => match magic.underlying.replace(None) {
+                Some(t) => writeFloat(magic.value, &t, out),
+                None => Err("No underlying type present for MagicFloat.".into()),
+            },
+
+            JdbcParameter::Decimal(ref d) => match *ty {
+                postgres::types::NUMERIC => {
+                    numeric::stringToNumeric(d, out).map_err(|e| format!("{:?}", e))?;
+                    Ok(IsNull::No)
+                },
+                postgres::types::MONEY => {
+                    let value = numeric::stringToMoney(d).map_err(|e| format!("{:?}", e))?;
+                    out.write_i64::<BigEndian>(value)?;
+                    Ok(IsNull::No)
+                },
+                ref x => Err(format!("Decimal parameter can't be written to a column of type {}", x).into()),
+            },
+
+            // Bound as json/jsonb, or as serialized text if the statement expects a string.
+            JdbcParameter::Json(ref json) => match *ty {
+                postgres::types::JSON | postgres::types::JSONB => json.to_sql(ty, out),
+                postgres::types::TEXT | postgres::types::VARCHAR => json.to_string().to_sql(ty, out),
+                ref x => Err(format!("Json parameter can't be written to a column of type {}", x).into()),
+            },
+
+            JdbcParameter::Array(ref array) => {
+                let member = match *ty.kind() {
+                    Kind::Array(ref member) => member,
+                    _ => return Err(format!("Array parameter can't be written to a column of type {}", ty).into()),
+                };
 
-            JdbcParameter::Double(ref magic) => {
-                let val = magic.underlying.replace(None);
-                match val {
-                    Some(t) => {
-                        match t {
-                            postgres::types::FLOAT8 => out.write_f64::<BigEndian>(magic.value).unwrap(), // Float8
-                            postgres::types::NUMERIC => {
-                                Decimal::from_f64(magic.value).unwrap().to_sql(ty, out);
-                            },
-                            x => error!("[Rust] Unhandled MagicFloat OID: {}", x),
-                        }
+                let mut elements = Vec::with_capacity(array.elements.len());
+                for element in &array.elements {
+                    let mut buf = Vec::new();
+                    match JdbcParameter::paramToSql(element).to_sql_checked(member, &mut buf)? {
+                        IsNull::Yes => elements.push(None),
+                        IsNull::No => elements.push(Some(buf)),
                     }
-                    x => panic!("[Rust] No underlying type present for MagicFloat."),
                 }
 
+                array::writeArray(out, member.oid(), &array.dimensions, &elements).map_err(|e| format!("{:?}", e))?;
                 Ok(IsNull::No)
-            }
+            },
 
-            x => panic!("ToSql no match {:?}", x),
+            JdbcParameter::Boolean(ref b) => b.to_sql_checked(ty, out),
+            JdbcParameter::UUID(ref uuid) => uuid.to_sql_checked(ty, out),
+            JdbcParameter::Bytes(ref bytes) => bytes.to_sql_checked(ty, out),
         }
     }
 
@@ -169,6 +286,11 @@ impl ToSql for JdbcParameter {
         ty: &Type,
         out: &mut Vec<u8>,
     ) -> result::Result<IsNull, Box<StdErr + Sync + Send>> {
+        // Domains are transmitted in the format of their base type.
+        if let Kind::Domain(ref base) = *ty.kind() {
+            return self.to_sql_checked(base, out);
+        }
+
         match self {
             // Todo: Cloning is inefficient. Alternative?
             JdbcParameter::Int(ref magic) => magic.setType(ty.clone()),
@@ -180,6 +302,45 @@ impl ToSql for JdbcParameter {
     }
 }
 
+/// Integers are range checked against the target type instead of being truncated.
+fn writeInteger(value: i64, ty: &Type, out: &mut Vec<u8>) -> result::Result<IsNull, Box<StdErr + Sync + Send>> {
+    let outOfRange = || format!("Value {} is out of range for type {}", value, ty);
+
+    match *ty {
+        postgres::types::INT2 if value >= i16::min_value() as i64 && value <= i16::max_value() as i64 => out.write_i16::<BigEndian>(value as i16)?,
+        postgres::types::INT4 if value >= i32::min_value() as i64 && value <= i32::max_value() as i64 => out.write_i32::<BigEndian>(value as i32)?,
+        postgres::types::OID if value >= 0 && value <= u32::max_value() as i64 => out.write_u32::<BigEndian>(value as u32)?,
+        postgres::types::INT2 | postgres::types::INT4 | postgres::types::OID => return Err(outOfRange().into()),
+        postgres::types::INT8 => out.write_i64::<BigEndian>(value)?,
+        postgres::types::FLOAT4 => out.write_f32::<BigEndian>(value as f32)?,
+        postgres::types::FLOAT8 => out.write_f64::<BigEndian>(value as f64)?,
+        postgres::types::NUMERIC => numeric::stringToNumeric(&value.to_string(), out).map_err(|e| format!("{:?}", e))?,
+        postgres::types::MONEY => out.write_i64::<BigEndian>(value.checked_mul(100).ok_or_else(outOfRange)?)?,
+        ref x => return Err(format!("Integer parameter can't be written to a column of type {}", x).into()),
+    };
+
+    Ok(IsNull::No)
+}
+
+fn writeFloat(value: f64, ty: &Type, out: &mut Vec<u8>) -> result::Result<IsNull, Box<StdErr + Sync + Send>> {
+    match *ty {
+        // Compared after rounding, the JVM sends the shortest decimal of a float, which may be just above its value.
+        postgres::types::FLOAT4 if value.is_finite() && (value as f32).is_infinite() => {
+            return Err(format!("Value {} is out of range for type {}", value, ty).into())
+        },
+        postgres::types::FLOAT4 => out.write_f32::<BigEndian>(value as f32)?,
+        postgres::types::FLOAT8 => out.write_f64::<BigEndian>(value)?,
+        // The shortest representation that round trips, Display never uses exponent notation.
+        postgres::types::NUMERIC => numeric::stringToNumeric(&value.to_string(), out).map_err(|e| format!("{:?}", e))?,
+        postgres::types::MONEY if value.is_finite() && (value * 100.0).abs() < ::std::i64::MAX as f64 => {
+            out.write_i64::<BigEndian>((value * 100.0).round() as i64)?
+        },
+        ref x => return Err(format!("Double parameter {} can't be written to a column of type {}", value, x).into()),
+    };
+
+    Ok(IsNull::No)
+}
+
 pub fn toJdbcParameterList(str: &String) -> Result<Vec<Vec<JdbcParameter>>> {
     match serde_json::from_str::<serde_json::Value>(&*str) {
         Ok(serde_json::Value::Array(elements)) => elements.iter().map(toJdbcParametersInner).collect(),
@@ -194,6 +355,35 @@ pub fn toJdbcParameterList(str: &String) -> Result<Vec<Vec<JdbcParameter>>> {
     }
 }
 
+/// A statement of a batch that is executed as a whole.
+#[derive(Debug)]
+pub struct BatchStatement {
+    pub query: String,
+    pub params: Vec<JdbcParameter>,
+}
+
+/// Parses statements in the form `[{"query": "...", "params": [...]}, ...]`.
+pub fn toBatchStatements(str: &String) -> Result<Vec<BatchStatement>> {
+    let json = serde_json::from_str::<serde_json::Value>(&*str)?;
+    let elements = match json {
+        serde_json::Value::Array(elements) => elements,
+        json => return Err(DriverError::GenericError(format!("provided json was not an array of statements: {}", json))),
+    };
+
+    elements.iter().map(|element| {
+        let query = element.get("query").and_then(|q| q.as_str());
+        let params = element.get("params");
+
+        match (query, params) {
+            (Some(query), Some(params)) => Ok(BatchStatement {
+                query: String::from(query),
+                params: toJdbcParametersInner(params)?,
+            }),
+            _ => Err(DriverError::GenericError(format!("{} is not a valid statement", element))),
+        }
+    }).collect()
+}
+
 pub fn toJdbcParameters(str: &String) -> Result<Vec<JdbcParameter>> {
     let json = serde_json::from_str::<serde_json::Value>(&*str)?;
     toJdbcParametersInner(&json)
@@ -233,34 +423,166 @@ fn parseDiscriminator(d: &str) -> Result<JdbcParameterType> {
         "DateTime" => Ok(JdbcParameterType::DateTime),
         "Long" => Ok(JdbcParameterType::Long),
         "UUID" => Ok(JdbcParameterType::UUID),
+        "Decimal" => Ok(JdbcParameterType::Decimal),
+        "Json" => Ok(JdbcParameterType::Json),
+        "Bytes" => Ok(JdbcParameterType::Bytes),
+        "Array" => Ok(JdbcParameterType::Array),
+        "DateTimeTz" => Ok(JdbcParameterType::DateTimeTz),
+        "Date" => Ok(JdbcParameterType::Date),
+        "Time" => Ok(JdbcParameterType::Time),
+        "TimeTz" => Ok(JdbcParameterType::TimeTz),
+        "Interval" => Ok(JdbcParameterType::Interval),
         x => Err(DriverError::GenericError(format!("discriminator {} is unhandled",d)))
     }
 }
 
 fn jsonObjectToJdbcParameter(map: &serde_json::Map<String, serde_json::Value>) -> Result<JdbcParameter> {
-    let discriminator = parseDiscriminator(map.get("discriminator").unwrap().as_str().unwrap())?;
-    let value = map.get("value").unwrap();
+    let discriminator = match map.get("discriminator").and_then(|d| d.as_str()) {
+        Some(d) => parseDiscriminator(d)?,
+        None => return Err(DriverError::GenericError(format!("Parameter without discriminator: {:?}", map))),
+    };
+
+    let value = match map.get("value") {
+        Some(value) => value,
+        None => return Err(DriverError::GenericError(format!("Parameter without value: {:?}", map))),
+    };
+
+    if discriminator == JdbcParameterType::Array {
+        let elementDiscriminator = match map.get("elementDiscriminator").and_then(|d| d.as_str()) {
+            Some(d) => parseDiscriminator(d)?,
+            None => return Err(DriverError::GenericError(String::from("Array parameter without elementDiscriminator"))),
+        };
 
+        return jsonToArrayParameter(elementDiscriminator, value);
+    }
+
+    jsonValueToJdbcParameter(discriminator, value)
+}
+
+fn jsonValueToJdbcParameter(discriminator: JdbcParameterType, value: &serde_json::Value) -> Result<JdbcParameter> {
     match (discriminator, value) {
         (JdbcParameterType::Int, &serde_json::Value::Number(ref n)) => Ok(JdbcParameter::Int(MagicInt {
-            value: n.as_i64().unwrap(),
+            value: integerValue(n)?,
             underlying: RefCell::new(None),
         })),
         (JdbcParameterType::String, &serde_json::Value::String(ref s)) => Ok(JdbcParameter::String(s.to_string())),
         (JdbcParameterType::Boolean, &serde_json::Value::Bool(b)) => Ok(JdbcParameter::Boolean(b)),
         (JdbcParameterType::Null, &serde_json::Value::Null) => Ok(JdbcParameter::Null),
         (JdbcParameterType::Double, &serde_json::Value::Number(ref n)) => Ok(JdbcParameter::Double(MagicFloat {
-            value: n.as_f64().unwrap(),
+            value: n.as_f64().ok_or_else(|| DriverError::GenericError(format!("{} is not a valid Double", n)))?,
             underlying: RefCell::new(None),
         })),
+        // JSON has no numbers for these, they are strings as in results.
+        (JdbcParameterType::Double, &serde_json::Value::String(ref s)) => Ok(JdbcParameter::Double(MagicFloat {
+            value: match s.as_str() {
+                "NaN" => ::std::f64::NAN,
+                "Infinity" => ::std::f64::INFINITY,
+                "-Infinity" => ::std::f64::NEG_INFINITY,
+                _ => return Err(DriverError::GenericError(format!("{} is not a valid Double", s))),
+            },
+            underlying: RefCell::new(None),
+        })),
+        (JdbcParameterType::DateTime, &serde_json::Value::String(ref s))
+        | (JdbcParameterType::DateTimeTz, &serde_json::Value::String(ref s))
+        | (JdbcParameterType::Date, &serde_json::Value::String(ref s)) if isInfinity(s) => {
+            Ok(JdbcParameter::Infinity(s == temporal::POSITIVE_INFINITY))
+        },
         (JdbcParameterType::DateTime, x @ &serde_json::Value::Object(_)) => {
             let date: MagicDateTime = serde_json::from_value(x.clone())?;
-            let dateTime = Utc.ymd(date.year, date.month, date.day).and_hms_milli(date.hour, date.minute, date.seconds, date.millis);
+            let micros = date.micros.unwrap_or(date.millis * 1000);
+            let dateTime = Utc
+                .ymd_opt(date.year, date.month, date.day)
+                .single()
+                .and_then(|d| d.and_hms_micro_opt(date.hour, date.minute, date.seconds, micros))
+                .ok_or_else(|| DriverError::GenericError(format!("Invalid date time: {}", x)))?;
+
             Ok(JdbcParameter::DateTime(dateTime))
         },
-        (JdbcParameterType::Long, &serde_json::Value::Number(ref n)) => Ok(JdbcParameter::Long(n.as_i64().unwrap())),
+        (JdbcParameterType::DateTimeTz, &serde_json::Value::String(ref s)) => Ok(JdbcParameter::DateTimeTz(DateTime::parse_from_rfc3339(s)?)),
+        (JdbcParameterType::Date, &serde_json::Value::String(ref s)) => Ok(JdbcParameter::Date(NaiveDate::parse_from_str(s, "%Y-%m-%d")?)),
+        (JdbcParameterType::Time, &serde_json::Value::String(ref s)) => Ok(JdbcParameter::Time(NaiveTime::parse_from_str(s, "%H:%M:%S%.f")?)),
+        (JdbcParameterType::TimeTz, &serde_json::Value::String(ref s)) => {
+            let (time, offset) = temporal::parseTimeTz(s)?;
+            Ok(JdbcParameter::TimeTz(time, offset))
+        },
+        (JdbcParameterType::Interval, x @ &serde_json::Value::Object(_)) => Ok(JdbcParameter::Interval(serde_json::from_value(x.clone())?)),
+        (JdbcParameterType::Long, &serde_json::Value::Number(ref n)) => Ok(JdbcParameter::Long(integerValue(n)?)),
         (JdbcParameterType::UUID, &serde_json::Value::String(ref uuid)) => Ok(JdbcParameter::UUID(Uuid::parse_str(uuid)?)),
+        (JdbcParameterType::Decimal, &serde_json::Value::String(ref d)) => {
+            // Validate eagerly to report malformed values before the statement is executed.
+            numeric::stringToNumeric(d, &mut Vec::new())?;
+            Ok(JdbcParameter::Decimal(d.to_string()))
+        },
+        (JdbcParameterType::Json, json) => Ok(JdbcParameter::Json(json.clone())),
+        (JdbcParameterType::Bytes, &serde_json::Value::String(ref encoded)) => Ok(JdbcParameter::Bytes(base64::decode(encoded)?)),
         (d, v) => Err(DriverError::GenericError(format!("Invalid combination: {:?} value {}", d, v)))
     }
 }
 
+fn integerValue(n: &serde_json::Number) -> Result<i64> {
+    n.as_i64().ok_or_else(|| DriverError::GenericError(format!("{} is not a valid 64 bit integer", n)))
+}
+
+fn isInfinity(s: &str) -> bool {
+    s == temporal::POSITIVE_INFINITY || s == temporal::NEGATIVE_INFINITY
+}
+
+/// Array values are (nested) JSON arrays of plain values, e.g. `[[1, 2], [3, null]]` for a two dimensional Int array.
+fn jsonToArrayParameter(elementDiscriminator: JdbcParameterType, value: &serde_json::Value) -> Result<JdbcParameter> {
+    if elementDiscriminator == JdbcParameterType::Array {
+        return Err(DriverError::GenericError(String::from("Arrays of arrays are not supported, use a multi-dimensional array instead")));
+    }
+
+    // The dimensions are derived from the first element of every level. Json elements can be arrays themselves,
+    // so Json arrays are always one dimensional.
+    let mut dimensions = Vec::new();
+    let mut current = value;
+    while let &serde_json::Value::Array(ref values) = current {
+        dimensions.push(values.len());
+        match values.first() {
+            Some(first) if elementDiscriminator != JdbcParameterType::Json => current = first,
+            _ => break,
+        }
+    }
+
+    if dimensions.is_empty() {
+        return Err(DriverError::GenericError(format!("Invalid array value: {}", value)));
+    }
+
+    let mut elements = Vec::new();
+    collectArrayElements(elementDiscriminator, value, &dimensions, &mut elements)?;
+
+    Ok(JdbcParameter::Array(ArrayParameter {
+        dimensions: dimensions,
+        elements: elements,
+    }))
+}
+
+fn collectArrayElements(
+    elementDiscriminator: JdbcParameterType,
+    value: &serde_json::Value,
+    dimensions: &[usize],
+    elements: &mut Vec<JdbcParameter>,
+) -> Result<()> {
+    match (dimensions.split_first(), value) {
+        (Some((len, inner)), &serde_json::Value::Array(ref values)) if values.len() == *len => {
+            for v in values {
+                collectArrayElements(elementDiscriminator, v, inner, elements)?;
+            }
+
+            Ok(())
+        }
+        (Some(_), v) => Err(DriverError::GenericError(format!(
+            "Multi-dimensional arrays must be rectangular, got {}",
+            v
+        ))),
+        (None, &serde_json::Value::Null) => {
+            elements.push(JdbcParameter::Null);
+            Ok(())
+        }
+        (None, v) => {
+            elements.push(jsonValueToJdbcParameter(elementDiscriminator, v)?);
+            Ok(())
+        }
+    }
+}
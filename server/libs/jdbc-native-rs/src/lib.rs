@@ -9,6 +9,11 @@ extern crate byteorder;
 extern crate rust_decimal;
 extern crate num_traits;
 extern crate uuid;
+extern crate openssl;
+extern crate base64;
+
+#[macro_use]
+extern crate lazy_static;
 
 #[macro_use]
 extern crate log;
@@ -16,135 +21,215 @@ extern crate log;
 #[macro_use]
 extern crate serde_derive;
 
-use std::ffi::{CStr, CString};
-use std::mem;
+use std::ffi::CStr;
 use std::os::raw::c_char;
 use std::str;
+use std::time::Duration;
 use colored::*;
 use postgres::rows::Rows;
 
+mod batch;
 mod driver;
 mod serialization;
+mod columnar;
+mod copy;
+mod protocol_buffer;
 mod jdbc_params;
+mod numeric;
+mod array;
+mod composite;
+mod range;
+mod temporal;
+mod text_fallback;
 mod logging;
+mod tls;
+mod url_params;
+mod panics;
+mod pool;
+mod handles;
 
 use serialization::ResultSet;
-use driver::PointerAndError;
+use driver::HandleAndError;
+use handles::Handle;
+use protocol_buffer::ProtocolBuffer;
+use pool::PoolStats;
 
 #[no_mangle]
 pub extern "C" fn jdbc_initialize() {
-    logging::init();
+    guardVoid(|| {
+        logging::init();
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn newConnection<'a>(url: *const c_char) -> *mut driver::PsqlConnection<'a> {
-    let mut connection = driver::connect(to_string(url));
-    let ptr = Box::into_raw(Box::new(connection));
-    trace!("New connection - handing out: {:?}", ptr);
-    return ptr;
+pub extern "C" fn newConnection(url: *const c_char) -> *mut HandleAndError {
+    guardHandleAndError(|| {
+        let handleAndError = match to_string(url).and_then(driver::connect).and_then(handles::insertConnection) {
+            Ok(handle) => HandleAndError::new(callResultJson(Ok(CallResult::empty())), handle),
+            Err(e) => HandleAndError::new(callResultJson(Ok(connectionErrorToCallResult(e))), 0),
+        };
+
+        trace!("New connection - handing out: {}", handleAndError.handle);
+        Box::into_raw(Box::new(handleAndError))
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn prepareStatement<'a>(conn: &'a driver::PsqlConnection<'a>, query: *const c_char) -> *mut PointerAndError {
-    trace!("Preparing query: {}", to_string(query));
-
-    let pointerAndError = match conn.prepareStatement(to_string(query)) {
-        Ok(pStmt) => PointerAndError {
-            error: serializeCallResult(Ok(CallResult::empty())),
-            pointer: Box::into_raw(Box::new(pStmt)),
-        },
-
-        Err(e) => PointerAndError {
-            error: serializeCallResult(Ok(errorToCallResult(e))),
-            pointer: std::ptr::null_mut(),
-        }
-    };
-
-    let ptr = Box::into_raw(Box::new(pointerAndError));
-    trace!("Prepare - handing out: {:?}", ptr);
-    ptr
+pub extern "C" fn prepareStatement(conn: Handle, query: *const c_char) -> *mut HandleAndError {
+    guardHandleAndError(|| {
+        let prepared = to_string(query)
+            .and_then(|query| {
+                trace!("Preparing query: {}", query);
+                handles::withConnection(conn, |c| c.prepareStatement(query))
+            })
+            .and_then(|stmt| handles::insertStatement(conn, stmt));
+
+        let handleAndError = match prepared {
+            Ok(handle) => HandleAndError::new(callResultJson(Ok(CallResult::empty())), handle),
+            Err(e) => HandleAndError::new(callResultJson(Ok(errorToCallResult(e))), 0),
+        };
+
+        trace!("Prepare - handing out: {}", handleAndError.handle);
+        Box::into_raw(Box::new(handleAndError))
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn closeStatement(stmt: *mut driver::PsqlPreparedStatement) -> *const c_char  {
-    trace!("Closing statement: {:?}", stmt);
-    let boxedStmt = unsafe { Box::from_raw(stmt) };
-    let ptr = serializeCallResult(Ok(CallResult::empty()));
-    trace!("Close statement - handing out: {:?}", ptr);
-    ptr
+pub extern "C" fn closeStatement(stmt: Handle) -> *mut ProtocolBuffer  {
+    guardCallResult(|| {
+        trace!("Closing statement: {}", stmt);
+        let ptr = serializeCallResult(handles::removeStatement(stmt).map(|_| CallResult::empty()));
+        trace!("Close statement - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 
+/// Executes a batch with the `Continue` error policy, see `executePreparedstatementWith`.
 #[no_mangle]
 pub extern "C" fn executePreparedstatement(
-    stmt: &driver::PsqlPreparedStatement,
+    stmt: Handle,
     params: *const c_char,
-) -> *const c_char {
-    let paramsString = to_string(params);
-    let callResult = jdbc_params::toJdbcParameterList(&paramsString).and_then(|p| {
-        stmt.execute(p.iter().map(|x| x.iter().collect()).collect())
-    }).map(|x: Vec<i32>| {
-        CallResult::count(x)
-    });
+) -> *mut ProtocolBuffer {
+    executePreparedstatementWith(stmt, params, 0)
+}
 
-    let ptr = serializeCallResult(callResult);
-    trace!("Exec prepared result - handing out: {:?}", ptr);
-    ptr
+/// `errorPolicy` is 0 to continue after a failed row, 1 to stop at the first failed row and 2 to roll back the
+/// whole batch. The result holds the errors of the failed rows by their index in the batch.
+#[no_mangle]
+pub extern "C" fn executePreparedstatementWith(
+    stmt: Handle,
+    params: *const c_char,
+    errorPolicy: i32,
+) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let callResult = driver::batchErrorPolicy(errorPolicy).and_then(|policy| {
+            let p = jdbc_params::toJdbcParameterList(&to_string(params)?)?;
+            handles::withStatement(stmt, |conn, stmt| stmt.execute(conn, p.iter().map(|x| x.iter().collect()).collect(), policy))
+        }).map(CallResult::batch);
+
+        let ptr = serializeCallResult(callResult);
+        trace!("Exec prepared result - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn queryPreparedstatement(
-    stmt: &driver::PsqlPreparedStatement,
+    stmt: Handle,
     params: *const c_char,
-) -> *const c_char {
-    let paramsString = to_string(params);
-    let callResult = jdbc_params::toJdbcParameters(&paramsString).and_then(|p| {
-        stmt.query(p.iter().collect())
-    }).and_then(|rows| {
-        CallResult::result_set(rows)
-    });
-
-    let ptr = serializeCallResult(callResult);
-    trace!("Query prepared result - handing out: {:?}", ptr);
-    ptr
+) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let callResult = parameters(params).and_then(|p| {
+            handles::withStatement(stmt, |conn, stmt| {
+                stmt.query(conn, p.iter().collect()).and_then(|rows| CallResult::result_set(rows, stmt.textFallback))
+            })
+        });
+
+        let ptr = serializeCallResult(callResult);
+        trace!("Query prepared result - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn sqlQuery(
-    conn: &driver::PsqlConnection,
+    conn: Handle,
     query: *const c_char,
     params: *const c_char,
-) -> *const c_char {
-    let queryString = to_string(query);
-    let paramsString = to_string(params);
-    let callResult = jdbc_params::toJdbcParameters(&paramsString).and_then(|p| {
-        conn.query(queryString, p.iter().collect())
-    }).and_then(|rows| {
-        CallResult::result_set(rows)
-    });
+) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let callResult = to_string(query).and_then(|queryString| {
+            let p = parameters(params)?;
+            handles::withConnection(conn, |conn| {
+                conn.query(queryString, p.iter().collect()).and_then(|rows| CallResult::result_set(rows, conn.textFallback))
+            })
+        });
+
+        let ptr = serializeCallResult(callResult);
+        trace!("Query result - handing out: {:?}", ptr);
+        ptr
+    })
+}
+
+/// Like `queryPreparedstatement`, with the rows in the binary format of `columnar`.
+#[no_mangle]
+pub extern "C" fn queryPreparedstatementBinary(
+    stmt: Handle,
+    params: *const c_char,
+) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let encoded = parameters(params).and_then(|p| {
+            handles::withStatement(stmt, |conn, stmt| {
+                stmt.query(conn, p.iter().collect()).and_then(|rows| columnar::encodeRows(&rows, stmt.textFallback))
+            })
+        });
+
+        let ptr = serializeProtocolBuffer(encoded);
+        trace!("Binary query prepared result - handing out: {:?}", ptr);
+        ptr
+    })
+}
 
-    let ptr = serializeCallResult(callResult);
-    trace!("Query result - handing out: {:?}", ptr);
-    ptr
+/// Like `sqlQuery`, with the rows in the binary format of `columnar`.
+#[no_mangle]
+pub extern "C" fn sqlQueryBinary(
+    conn: Handle,
+    query: *const c_char,
+    params: *const c_char,
+) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let encoded = to_string(query).and_then(|queryString| {
+            let p = parameters(params)?;
+            handles::withConnection(conn, |conn| {
+                conn.query(queryString, p.iter().collect()).and_then(|rows| columnar::encodeRows(&rows, conn.textFallback))
+            })
+        });
+
+        let ptr = serializeProtocolBuffer(encoded);
+        trace!("Binary query result - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn sqlExecute(
-    conn: &driver::PsqlConnection,
+    conn: Handle,
     query: *const c_char,
     params: *const c_char,
-) -> *const c_char {
-    let queryString = to_string(query);
-    let paramsString = to_string(params);
-    let callResult = jdbc_params::toJdbcParameters(&paramsString).and_then(|p| {
-        conn.execute(queryString, p.iter().collect())
-    }).map(|x| {
-        CallResult::count(vec!(x as i32))
-    });
-
-    let ptr = serializeCallResult(callResult);
-    trace!("Exec result - handing out: {:?}", ptr);
-    ptr
+) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let callResult = to_string(query).and_then(|queryString| {
+            let p = parameters(params)?;
+            handles::withConnection(conn, |conn| conn.execute(queryString, p.iter().collect()))
+        }).map(|x| {
+            CallResult::count(vec!(x as i32))
+        });
+
+        let ptr = serializeCallResult(callResult);
+        trace!("Exec result - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 #[derive(Serialize)]
@@ -153,6 +238,9 @@ struct CallResult {
     rows: Option<ResultSet>,
     error: Option<CallError>,
     counts: Vec<i32>,
+    stats: Option<PoolStats>,
+    attempts: Option<u32>,
+    errors: Option<Vec<driver::BatchError>>,
 }
 
 impl CallResult {
@@ -161,17 +249,35 @@ impl CallResult {
             ty: String::from("COUNT"),
             rows: None,
             error: None,
-            counts: c
+            counts: c,
+            stats: None,
+            attempts: None,
+            errors: None,
         }
     }
 
-    pub fn result_set(rows: Rows) -> driver::Result<CallResult> {
-        let data = ResultSet::create(rows)?;
+    pub fn batch(result: driver::BatchResult) -> CallResult {
+        CallResult {
+            ty: String::from("COUNT"),
+            rows: None,
+            error: None,
+            counts: result.counts,
+            stats: None,
+            attempts: None,
+            errors: Some(result.errors),
+        }
+    }
+
+    pub fn result_set(rows: Rows, textFallback: bool) -> driver::Result<CallResult> {
+        let data = ResultSet::create(rows, textFallback)?;
         Ok(CallResult {
             ty: String::from("RESULT_SET"),
             rows: Some(data),
             error: None,
-            counts: Vec::new()
+            counts: Vec::new(),
+            stats: None,
+            attempts: None,
+            errors: None,
         })
     }
 
@@ -180,7 +286,22 @@ impl CallResult {
             ty: String::from("EMPTY"),
             rows: None,
             error: None,
-            counts: Vec::new()
+            counts: Vec::new(),
+            stats: None,
+            attempts: None,
+            errors: None,
+        }
+    }
+
+    pub fn pool_stats(stats: PoolStats) -> CallResult {
+        CallResult {
+            ty: String::from("POOL_STATS"),
+            rows: None,
+            error: None,
+            counts: Vec::new(),
+            stats: Some(stats),
+            attempts: None,
+            errors: None,
         }
     }
 
@@ -192,7 +313,10 @@ impl CallResult {
                 code: code,
                 message: message,
             }),
-            counts: Vec::new()
+            counts: Vec::new(),
+            stats: None,
+            attempts: None,
+            errors: None,
         }
     }
 }
@@ -203,11 +327,31 @@ struct CallError {
     message: String
 }
 
-fn serializeCallResult(res: driver::Result<CallResult>) -> *const c_char {
+/// Error results are handed out in the error buffer, like failures of functions returning binary data.
+fn serializeCallResult(res: driver::Result<CallResult>) -> *mut ProtocolBuffer {
     let result = handleResult(res);
-    let serialized = serde_json::to_string(&result).unwrap();
+    let isError = result.error.is_some();
+    let serialized = serde_json::to_vec(&result).unwrap();
+
+    let buffer = if isError {
+        ProtocolBuffer::error(serialized)
+    } else {
+        ProtocolBuffer::data(serialized)
+    };
 
-    to_ptr(serialized)
+    buffer.into_boxed_ptr()
+}
+
+fn callResultJson(res: driver::Result<CallResult>) -> Vec<u8> {
+    let result = handleResult(res);
+    serde_json::to_vec(&result).unwrap()
+}
+
+fn serializeProtocolBuffer(res: driver::Result<Vec<u8>>) -> *mut ProtocolBuffer {
+    match res {
+        Ok(data) => ProtocolBuffer::data(data).into_boxed_ptr(),
+        Err(e) => serializeCallResult(Err(e)),
+    }
 }
 
 fn handleResult(result: driver::Result<CallResult>) -> CallResult {
@@ -227,80 +371,483 @@ fn errorToCallResult(e: driver::DriverError) -> CallResult {
             None => CallResult::error(String::from("-1"), err),
         },
 
+        driver::DriverError::InvalidHandle(msg) => CallResult::error(String::from(INVALID_HANDLE_ERROR_CODE), msg),
+        driver::DriverError::ReentrantUse(msg) => CallResult::error(String::from(REENTRANT_USE_ERROR_CODE), msg),
         _ => CallResult::error(String::from("-2"), err),
     }
 }
 
+/// Panics caught at the FFI boundary are reported with this code.
+const PANIC_ERROR_CODE: &str = "-4";
+
+/// Calls with a handle that was never issued or has been closed are reported with this code.
+const INVALID_HANDLE_ERROR_CODE: &str = "-5";
+
+/// Calls on a connection that is already in use further up the stack of the calling thread are reported with this code.
+const REENTRANT_USE_ERROR_CODE: &str = "-6";
+
+fn guardCallResult<F>(f: F) -> *mut ProtocolBuffer where F: FnOnce() -> *mut ProtocolBuffer {
+    panics::catch(f).unwrap_or_else(|msg| {
+        serializeCallResult(Ok(CallResult::error(String::from(PANIC_ERROR_CODE), msg)))
+    })
+}
+
+fn guardHandleAndError<F>(f: F) -> *mut HandleAndError where F: FnOnce() -> *mut HandleAndError {
+    panics::catch(f).unwrap_or_else(|msg| {
+        Box::into_raw(Box::new(HandleAndError::new(
+            callResultJson(Ok(CallResult::error(String::from(PANIC_ERROR_CODE), msg))),
+            0,
+        )))
+    })
+}
+
+/// Functions without a return channel can only log the panic, which the panic hook already does.
+fn guardVoid<F>(f: F) where F: FnOnce() {
+    let _ = panics::catch(f);
+}
+
+/// Errors that don't originate from the database (unreachable host, failed TLS handshake) are reported
+/// with SQLSTATE 08001 (sqlclient_unable_to_establish_sqlconnection), like the Postgres JDBC driver does.
+fn connectionErrorToCallResult(e: driver::DriverError) -> CallResult {
+    match e {
+        driver::DriverError::PsqlError(ref psqlErr) if psqlErr.as_db().is_none() => {
+            CallResult::error(String::from("08001"), format!("[Rust] {:?}", e))
+        },
+
+        e => errorToCallResult(e),
+    }
+}
+
+/// Statements and cursors of the connection can't be used anymore afterwards. Closing a connection checked out of a
+/// pool frees its slot in the pool.
 #[no_mangle]
-pub extern "C" fn closeConnection(conn: *mut driver::PsqlConnection) -> *const c_char  {
-    trace!("Closing connection: {:?}", conn);
-    let connection = unsafe { Box::from_raw(conn) };
-    connection.close();
+pub extern "C" fn closeConnection(conn: Handle) -> *mut ProtocolBuffer  {
+    guardCallResult(|| {
+        trace!("Closing connection: {}", conn);
+        let ptr = serializeCallResult(handles::removeConnection(conn).map(|_| CallResult::empty()));
 
-    let ptr = serializeCallResult(Ok(CallResult::empty()));
-    trace!("Close connection - handing out: {:?}", ptr);
-    ptr
+        trace!("Close connection - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn destroy(pointerAndError: *mut PointerAndError) {
-    trace!("Destroying PointerAndError: {:?}", pointerAndError);
-    unsafe {
-        Box::from_raw(pointerAndError)
-    };
+pub extern "C" fn destroy(handleAndError: *mut HandleAndError) {
+    guardVoid(|| {
+        trace!("Destroying HandleAndError: {:?}", handleAndError);
+        unsafe {
+            Box::from_raw(handleAndError)
+        };
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn destroy_string(s: *mut c_char) {
-    trace!("Dropping string buffer: {:?}", s);
-    unsafe {
-        String::from_utf8(CString::from_raw(s).into_bytes()).unwrap()
-    };
+pub extern "C" fn destroy_buffer(buffer: *mut ProtocolBuffer) {
+    guardVoid(|| {
+        trace!("Destroying ProtocolBuffer: {:?}", buffer);
+        unsafe {
+            Box::from_raw(buffer)
+        };
+    })
 }
 
 #[no_mangle]
-pub extern "C" fn startTransaction<'a>(conn: *mut driver::PsqlConnection) -> *const c_char  {
-    unsafe {
-        let res = (*conn).startTransaction();
+pub extern "C" fn startTransaction(conn: Handle) -> *mut ProtocolBuffer  {
+    guardCallResult(|| {
+        let ptr = serializeCallResult(handles::withConnection(conn, |c| c.startTransaction()).map(|_| { CallResult::empty() }));
+        trace!("Opened transaction on {} - handing out: {:?}", conn, ptr);
+        ptr
+    })
+}
+
+/// Starts a transaction with the given characteristics instead of the ones set on the connection.
+#[no_mangle]
+pub extern "C" fn startTransactionWith(conn: Handle, isolationLevel: i32, readOnly: bool, deferrable: bool) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let res = driver::isolationLevel(isolationLevel).and_then(|level| {
+            handles::withConnection(conn, |c| c.startTransactionWith(driver::TransactionOptions {
+                isolationLevel: level,
+                readOnly: readOnly,
+                deferrable: deferrable,
+            }))
+        });
+
         let ptr = serializeCallResult(res.map(|_| { CallResult::empty() }));
-        trace!("Opened transaction on {:?} - handing out: {:?}", conn, ptr);
+        trace!("Opened transaction with {} on {} - handing out: {:?}", isolationLevel, conn, ptr);
         ptr
-    }
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn setTransactionIsolation(conn: Handle, isolationLevel: i32) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let res = driver::isolationLevel(isolationLevel)
+            .and_then(|level| handles::withConnection(conn, |c| c.setTransactionIsolation(level)));
+        let ptr = serializeCallResult(res.map(|_| CallResult::empty()));
+
+        trace!("Set transaction isolation {} - handing out: {:?}", isolationLevel, ptr);
+        ptr
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn setReadOnly(conn: Handle, readOnly: bool) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let ptr = serializeCallResult(handles::withConnection(conn, |c| c.setReadOnly(readOnly)).map(|_| CallResult::empty()));
+
+        trace!("Set read-only {} - handing out: {:?}", readOnly, ptr);
+        ptr
+    })
+}
+
+/// Executes the statements (`[{"query": "...", "params": [...]}, ...]`) in a transaction of their own and retries
+/// serialization failures and deadlocks. The result reports the number of attempts, also for failures. At most
+/// `MAX_ATTEMPTS` attempts with a backoff of up to `MAX_BACKOFF_MS` are accepted.
+#[no_mangle]
+pub extern "C" fn executeTransaction(
+    conn: Handle,
+    statements: *const c_char,
+    maxAttempts: i32,
+    backoffMs: i64,
+) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let (res, attempts) = if maxAttempts < 1 || maxAttempts > driver::MAX_ATTEMPTS || backoffMs < 0 || backoffMs > driver::MAX_BACKOFF_MS {
+            (Err(driver::DriverError::GenericError(format!(
+                "Invalid retry settings: {} attempts, backoff {}ms",
+                maxAttempts, backoffMs
+            ))), 0)
+        } else {
+            let retry = driver::RetryPolicy {
+                maxAttempts: maxAttempts as u32,
+                backoff: Duration::from_millis(backoffMs as u64),
+            };
+
+            // The connection is locked per attempt, so that other calls can use it while the retry backs off.
+            match to_string(statements).and_then(|s| jdbc_params::toBatchStatements(&s)) {
+                Ok(s) => driver::withRetries(retry, || handles::withConnection(conn, |c| c.executeTransaction(&s))),
+                Err(e) => (Err(e), 0),
+            }
+        };
+
+        let mut callResult = handleResult(res.map(CallResult::count));
+        callResult.attempts = Some(attempts);
+
+        let ptr = serializeCallResult(Ok(callResult));
+        trace!("Transaction finished after {} attempts - handing out: {:?}", attempts, ptr);
+        ptr
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn commitTransaction(conn: Handle) -> *mut ProtocolBuffer  {
+    guardCallResult(|| {
+        let ret = serializeCallResult(handles::withConnection(conn, |c| c.commitTransaction()).map(|_| { CallResult::empty() }));
+
+        trace!("Committed on connection {} - handing out: {:?}", conn, ret);
+        ret
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rollbackTransaction(conn: Handle) -> *mut ProtocolBuffer  {
+    guardCallResult(|| {
+        let ret = serializeCallResult(handles::withConnection(conn, |c| c.rollbackTransaction()).map(|_| { CallResult::empty() }));
+
+        trace!("Rolled back on connection {} - handing out: {:?}", conn, ret);
+        ret
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn createSavepoint(conn: Handle, name: *const c_char) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let res = to_string(name).and_then(|name| {
+            trace!("Creating savepoint: {}", name);
+            handles::withConnection(conn, |c| c.createSavepoint(name))
+        });
+        let ptr = serializeCallResult(res.map(|_| CallResult::empty()));
+
+        trace!("Create savepoint - handing out: {:?}", ptr);
+        ptr
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn releaseSavepoint(conn: Handle, name: *const c_char) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let res = to_string(name).and_then(|name| {
+            trace!("Releasing savepoint: {}", name);
+            handles::withConnection(conn, |c| c.releaseSavepoint(name))
+        });
+        let ptr = serializeCallResult(res.map(|_| CallResult::empty()));
+
+        trace!("Release savepoint - handing out: {:?}", ptr);
+        ptr
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn rollbackToSavepoint(conn: Handle, name: *const c_char) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let res = to_string(name).and_then(|name| {
+            trace!("Rolling back to savepoint: {}", name);
+            handles::withConnection(conn, |c| c.rollbackToSavepoint(name))
+        });
+        let ptr = serializeCallResult(res.map(|_| CallResult::empty()));
+
+        trace!("Rollback to savepoint - handing out: {:?}", ptr);
+        ptr
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn openCursor(
+    conn: Handle,
+    query: *const c_char,
+    params: *const c_char,
+) -> *mut HandleAndError {
+    guardHandleAndError(|| {
+        let opened = to_string(query).and_then(|queryString| {
+            let p = parameters(params)?;
+            handles::withConnection(conn, |c| c.openCursor(queryString, p.iter().collect()))
+        }).and_then(|cursor| handles::insertCursor(conn, cursor));
+
+        let handleAndError = match opened {
+            Ok(handle) => HandleAndError::new(callResultJson(Ok(CallResult::empty())), handle),
+            Err(e) => HandleAndError::new(callResultJson(Ok(errorToCallResult(e))), 0),
+        };
+
+        trace!("Open cursor - handing out: {}", handleAndError.handle);
+        Box::into_raw(Box::new(handleAndError))
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn fetchCursor(conn: Handle, cursor: Handle, count: i32) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let callResult = if count <= 0 {
+            Err(driver::DriverError::GenericError(format!("Invalid cursor fetch size: {}", count)))
+        } else {
+            handles::withCursor(conn, cursor, |conn, cursor| {
+                conn.fetchCursor(cursor, count as u32).and_then(|rows| CallResult::result_set(rows, cursor.textFallback))
+            })
+        };
+
+        let ptr = serializeCallResult(callResult);
+        trace!("Fetch cursor - handing out: {:?}", ptr);
+        ptr
+    })
+}
+
+/// Like `fetchCursor`, with the rows in the binary format of `columnar`.
+#[no_mangle]
+pub extern "C" fn fetchCursorBinary(conn: Handle, cursor: Handle, count: i32) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let encoded = if count <= 0 {
+            Err(driver::DriverError::GenericError(format!("Invalid cursor fetch size: {}", count)))
+        } else {
+            handles::withCursor(conn, cursor, |conn, cursor| {
+                conn.fetchCursor(cursor, count as u32).and_then(|rows| columnar::encodeRows(&rows, cursor.textFallback))
+            })
+        };
+
+        let ptr = serializeProtocolBuffer(encoded);
+        trace!("Binary fetch cursor - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
+/// Frees the cursor, even if closing it on the server fails.
 #[no_mangle]
-pub extern "C" fn commitTransaction(conn: *mut driver::PsqlConnection) -> *const c_char  {
-    let ptr = unsafe { Box::from_raw(conn) };
-    let ret = serializeCallResult(ptr.commitTransaction().map(|_| { CallResult::empty() }));
+pub extern "C" fn closeCursor(conn: Handle, cursor: Handle) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        trace!("Closing cursor: {}", cursor);
+        let res = handles::removeCursor(conn, cursor, |conn, cursor| conn.closeCursor(cursor));
+        let ptr = serializeCallResult(res.map(|_| CallResult::empty()));
 
-    mem::forget(ptr);
-    trace!("Committed on connection {:?} - handing out: {:?}", conn, ret);
-    ret
+        trace!("Close cursor - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
+/// Bulk loads rows with `COPY ... FROM STDIN` and returns the number of rows written. `columns` is a JSON array of
+/// column names. With format `json`, `data` holds the rows in the parameter format of `executePreparedstatement`,
+/// with format `csv` the rows as CSV without a header line.
 #[no_mangle]
-pub extern "C" fn rollbackTransaction(conn: *mut driver::PsqlConnection) -> *const c_char  {
-    let ptr = unsafe { Box::from_raw(conn) };
-    let ret = serializeCallResult(ptr.rollbackTransaction().map(|_| { CallResult::empty() }));
+pub extern "C" fn copyIn(
+    conn: Handle,
+    schema: *const c_char,
+    table: *const c_char,
+    columns: *const c_char,
+    format: *const c_char,
+    data: *const c_char,
+) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let callResult = copyTarget(schema, table, columns).and_then(|target| {
+            let data = copy::CopyData::parse(&to_string(format)?, to_string(data)?)?;
+            handles::withConnection(conn, |c| c.copyIn(&target, data))
+        }).map(|rows| {
+            CallResult::count(vec!(rows as i32))
+        });
+
+        let ptr = serializeCallResult(callResult);
+        trace!("Copy in - handing out: {:?}", ptr);
+        ptr
+    })
+}
 
-    mem::forget(ptr);
-    trace!("Rolled back on connection {:?} - handing out: {:?}", conn, ret);
-    ret
+/// Starts exporting the result of `query` with `COPY ... TO STDOUT`. `format` is `csv` (with a header line) or
+/// `ndjson` (one JSON object per row). The data is read with `fetchExport` in chunks of about `chunkSize` bytes.
+/// The connection can't be used for anything else until the export is closed.
+#[no_mangle]
+pub extern "C" fn openExport(conn: Handle, query: *const c_char, format: *const c_char, chunkSize: i32) -> *mut HandleAndError {
+    guardHandleAndError(|| {
+        let opened = if chunkSize <= 0 {
+            Err(driver::DriverError::GenericError(format!("Invalid export chunk size: {}", chunkSize)))
+        } else {
+            to_string(format).and_then(|format| copy::ExportFormat::parse(&format)).and_then(|format| {
+                // COPY doesn't analyze its query before it is executed, so the query is checked on its own.
+                let query = to_string(query)?;
+                handles::withConnection(conn, |c| c.checkStatement(&query))?;
+                let statement = format.copyStatement(&query);
+                handles::insertExport(conn, |shared| copy::Export::start(shared, statement, chunkSize as usize))
+            })
+        };
+
+        let handleAndError = match opened {
+            Ok(handle) => HandleAndError::new(callResultJson(Ok(CallResult::empty())), handle),
+            Err(e) => HandleAndError::new(callResultJson(Ok(errorToCallResult(e))), 0),
+        };
+
+        trace!("Open export - handing out: {}", handleAndError.handle);
+        Box::into_raw(Box::new(handleAndError))
+    })
+}
+
+/// The next chunk of the export as raw bytes in `data`. Empty data marks the end of the export.
+#[no_mangle]
+pub extern "C" fn fetchExport(export: Handle) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let chunk = handles::withExport(export, |e| e.next()).map(|chunk| chunk.unwrap_or_else(Vec::new));
+
+        let ptr = serializeProtocolBuffer(chunk);
+        trace!("Fetch export - handing out: {:?}", ptr);
+        ptr
+    })
+}
+
+/// Frees the export, the connection can be used again afterwards. Returns the number of rows exported, or no count
+/// if the export was closed before all data was read, which cancels it.
+#[no_mangle]
+pub extern "C" fn closeExport(export: Handle) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        trace!("Closing export: {}", export);
+        let callResult = handles::removeExport(export, |e| e.finish()).map(|rows| match rows {
+            Some(rows) => CallResult::count(vec!(rows as i32)),
+            None => CallResult::empty(),
+        });
+
+        let ptr = serializeCallResult(callResult);
+        trace!("Close export - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 /// Convert a native string to a Rust string
-fn to_string(pointer: *const c_char) -> String {
+fn to_string(pointer: *const c_char) -> driver::Result<String> {
     let slice = unsafe { CStr::from_ptr(pointer).to_bytes() };
-    str::from_utf8(slice).unwrap().to_string()
+    str::from_utf8(slice)
+        .map(|s| s.to_string())
+        .map_err(|e| driver::DriverError::GenericError(format!("String argument is not valid UTF-8: {}", e)))
 }
 
-/// Convert a Rust string to a native string
-fn to_ptr(string: String) -> *const c_char {
-    let cs = CString::new(string.as_bytes()).unwrap();
-    let ptr = cs.as_ptr();
-    // Tell Rust not to clean up the string while we still have a pointer to it.
-    // Otherwise, we'll get a segfault.
+fn parameters(params: *const c_char) -> driver::Result<Vec<jdbc_params::JdbcParameter>> {
+    jdbc_params::toJdbcParameters(&to_string(params)?)
+}
+
+fn copyTarget(schema: *const c_char, table: *const c_char, columns: *const c_char) -> driver::Result<copy::CopyTarget> {
+    copy::CopyTarget::parse(to_string(schema)?, to_string(table)?, &to_string(columns)?)
+}
+
+#[no_mangle]
+pub extern "C" fn newPool(url: *const c_char, minSize: i32, maxSize: i32, idleTimeoutMs: i64) -> *mut HandleAndError {
+    guardHandleAndError(|| {
+        let created = if minSize < 0 || maxSize < 0 || idleTimeoutMs < 0 {
+            Err(driver::DriverError::GenericError(format!(
+                "Invalid pool settings: min {}, max {}, idle timeout {}ms",
+                minSize, maxSize, idleTimeoutMs
+            )))
+        } else {
+            to_string(url).and_then(|url| {
+                pool::PsqlPool::create(url, minSize as usize, maxSize as usize, Duration::from_millis(idleTimeoutMs as u64))
+            })
+        }.and_then(handles::insertPool);
+
+        let handleAndError = match created {
+            Ok(handle) => HandleAndError::new(callResultJson(Ok(CallResult::empty())), handle),
+            Err(e) => HandleAndError::new(callResultJson(Ok(connectionErrorToCallResult(e))), 0),
+        };
+
+        trace!("New pool - handing out: {}", handleAndError.handle);
+        Box::into_raw(Box::new(handleAndError))
+    })
+}
 
-    mem::forget(cs);
-    ptr
+#[no_mangle]
+pub extern "C" fn checkoutConnection(pool: Handle) -> *mut HandleAndError {
+    guardHandleAndError(|| {
+        let checkedOut = handles::pool(pool)
+            .and_then(|p| {
+                // Broken idle connections may have been discarded on the way.
+                let connection = p.checkout();
+                pool::PsqlPool::refill(&p);
+                connection
+            })
+            .and_then(|connection| handles::insertPooledConnection(pool, *connection));
+
+        let handleAndError = match checkedOut {
+            Ok(handle) => HandleAndError::new(callResultJson(Ok(CallResult::empty())), handle),
+            Err(e) => HandleAndError::new(callResultJson(Ok(connectionErrorToCallResult(e))), 0),
+        };
+
+        trace!("Checkout - handing out: {}", handleAndError.handle);
+        Box::into_raw(Box::new(handleAndError))
+    })
+}
+
+/// Returns a connection obtained from `checkoutConnection` to the pool. The connection pointer is invalid afterwards.
+#[no_mangle]
+pub extern "C" fn checkinConnection(pool: Handle, conn: Handle) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        trace!("Checking in connection: {}", conn);
+        let res = handles::pool(pool).and_then(|p| {
+            let connection = handles::takePooledConnection(pool, conn)?;
+            p.checkin(Box::new(connection))?;
+            pool::PsqlPool::refill(&p);
+            Ok(())
+        });
+
+        let ptr = serializeCallResult(res.map(|_| CallResult::empty()));
+        trace!("Checkin - handing out: {:?}", ptr);
+        ptr
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn poolStats(pool: Handle) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        let ptr = serializeCallResult(handles::pool(pool).and_then(|p| p.stats()).map(CallResult::pool_stats));
+        trace!("Pool stats - handing out: {:?}", ptr);
+        ptr
+    })
+}
+
+#[no_mangle]
+pub extern "C" fn closePool(pool: Handle) -> *mut ProtocolBuffer {
+    guardCallResult(|| {
+        trace!("Closing pool: {}", pool);
+        let ptr = serializeCallResult(handles::removePool(pool).map(|_| CallResult::empty()));
+        trace!("Close pool - handing out: {:?}", ptr);
+        ptr
+    })
 }
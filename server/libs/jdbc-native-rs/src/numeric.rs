@@ -0,0 +1,277 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use std::io::Cursor;
+
+use driver::{DriverError, Result};
+
+// Conversion between the binary NUMERIC wire format and exact decimal strings.
+// rust_decimal is limited to 28 significant digits, while NUMERIC allows up to 131072 digits before the decimal point.
+//
+// Wire format: ndigits (i16), weight (i16), sign (u16), dscale (u16), followed by ndigits base 10000 digits (i16).
+// The value is the sum of digit[i] * 10000^(weight - i).
+
+const NUMERIC_POS: u16 = 0x0000;
+const NUMERIC_NEG: u16 = 0x4000;
+const NUMERIC_NAN: u16 = 0xC000;
+const NUMERIC_PINF: u16 = 0xD000;
+const NUMERIC_NINF: u16 = 0xF000;
+
+pub fn numericToString(raw: &[u8]) -> Result<String> {
+    let mut rdr = Cursor::new(raw);
+    let ndigits = rdr.read_i16::<BigEndian>().map_err(invalidNumeric)? as i32;
+    let weight = rdr.read_i16::<BigEndian>().map_err(invalidNumeric)? as i32;
+    let sign = rdr.read_u16::<BigEndian>().map_err(invalidNumeric)?;
+    let dscale = rdr.read_u16::<BigEndian>().map_err(invalidNumeric)? as usize;
+
+    let mut digits = Vec::with_capacity(ndigits as usize);
+    for _ in 0..ndigits {
+        digits.push(rdr.read_i16::<BigEndian>().map_err(invalidNumeric)?);
+    }
+
+    match sign {
+        NUMERIC_NAN => return Ok(String::from("NaN")),
+        NUMERIC_PINF => return Ok(String::from("Infinity")),
+        NUMERIC_NINF => return Ok(String::from("-Infinity")),
+        NUMERIC_POS | NUMERIC_NEG => (),
+        x => return Err(DriverError::GenericError(format!("Invalid NUMERIC sign: {:x}", x))),
+    };
+
+    let digitAt = |i: i32| if i >= 0 && i < ndigits { digits[i as usize] } else { 0 };
+    let mut result = String::new();
+
+    if sign == NUMERIC_NEG && ndigits > 0 {
+        result.push('-');
+    }
+
+    if weight < 0 {
+        result.push('0');
+    } else {
+        result.push_str(&digitAt(0).to_string());
+        for i in 1..(weight + 1) {
+            result.push_str(&format!("{:04}", digitAt(i)));
+        }
+    }
+
+    if dscale > 0 {
+        let mut fraction = String::with_capacity(dscale + 4);
+        let mut i = weight + 1;
+        while fraction.len() < dscale {
+            fraction.push_str(&format!("{:04}", digitAt(i)));
+            i += 1;
+        }
+
+        fraction.truncate(dscale);
+        result.push('.');
+        result.push_str(&fraction);
+    }
+
+    Ok(result)
+}
+
+/// Accepts plain decimal notation as produced by `BigDecimal.toPlainString` on the JVM, as well as NaN.
+pub fn stringToNumeric(s: &str, out: &mut Vec<u8>) -> Result<()> {
+    let invalid = || DriverError::GenericError(format!("Invalid decimal value: {}", s));
+    let trimmed = s.trim();
+
+    if trimmed == "NaN" {
+        return writeNumeric(out, 0, NUMERIC_NAN, 0, &[]);
+    }
+
+    let (negative, unsigned) = if trimmed.starts_with('-') {
+        (true, &trimmed[1..])
+    } else if trimmed.starts_with('+') {
+        (false, &trimmed[1..])
+    } else {
+        (false, trimmed)
+    };
+
+    let (integral, fraction) = match unsigned.find('.') {
+        Some(pos) => (&unsigned[..pos], &unsigned[pos + 1..]),
+        None => (unsigned, ""),
+    };
+
+    let isDigits = |part: &str| part.bytes().all(|b| b >= b'0' && b <= b'9');
+    if (integral.is_empty() && fraction.is_empty()) || !isDigits(integral) || !isDigits(fraction) {
+        return Err(invalid());
+    }
+
+    if fraction.len() > u16::max_value() as usize {
+        return Err(invalid());
+    }
+
+    // Pad both parts to a multiple of 4 digits, so that they split into base 10000 digits.
+    let integralPadding = (4 - integral.len() % 4) % 4;
+    let fractionPadding = (4 - fraction.len() % 4) % 4;
+    let padded = format!(
+        "{}{}{}{}",
+        "0".repeat(integralPadding),
+        integral,
+        fraction,
+        "0".repeat(fractionPadding)
+    );
+
+    let mut digits: Vec<i16> = padded
+        .as_bytes()
+        .chunks(4)
+        .map(|chunk| chunk.iter().fold(0i16, |acc, b| acc * 10 + (b - b'0') as i16))
+        .collect();
+
+    let mut weight = ((integral.len() + integralPadding) / 4) as i32 - 1;
+
+    // Leading and trailing zero digits are not transmitted.
+    let leadingZeros = digits.iter().take_while(|d| **d == 0).count();
+    digits.drain(..leadingZeros);
+    weight -= leadingZeros as i32;
+
+    while digits.last() == Some(&0) {
+        digits.pop();
+    }
+
+    if digits.is_empty() {
+        weight = 0;
+    }
+
+    if digits.len() > i16::max_value() as usize || weight > i16::max_value() as i32 || weight < i16::min_value() as i32 {
+        return Err(invalid());
+    }
+
+    let sign = if negative && !digits.is_empty() { NUMERIC_NEG } else { NUMERIC_POS };
+    writeNumeric(out, weight as i16, sign, fraction.len() as u16, &digits)
+}
+
+fn writeNumeric(out: &mut Vec<u8>, weight: i16, sign: u16, dscale: u16, digits: &[i16]) -> Result<()> {
+    out.write_i16::<BigEndian>(digits.len() as i16).map_err(invalidNumeric)?;
+    out.write_i16::<BigEndian>(weight).map_err(invalidNumeric)?;
+    out.write_u16::<BigEndian>(sign).map_err(invalidNumeric)?;
+    out.write_u16::<BigEndian>(dscale).map_err(invalidNumeric)?;
+
+    for digit in digits {
+        out.write_i16::<BigEndian>(*digit).map_err(invalidNumeric)?;
+    }
+
+    Ok(())
+}
+
+fn invalidNumeric(e: ::std::io::Error) -> DriverError {
+    DriverError::GenericError(format!("Invalid NUMERIC value: {}", e))
+}
+
+// MONEY is transmitted as i64 in the smallest currency unit. The number of fractional digits depends on the
+// lc_monetary setting of the server, we assume the two digits of the common locales.
+const MONEY_SCALE: i64 = 100;
+
+pub fn moneyToString(raw: i64) -> String {
+    let sign = if raw < 0 { "-" } else { "" };
+    let abs = (raw as i128).abs();
+
+    format!("{}{}.{:02}", sign, abs / MONEY_SCALE as i128, abs % MONEY_SCALE as i128)
+}
+
+/// Accepts plain decimal notation with at most two fractional digits.
+pub fn stringToMoney(s: &str) -> Result<i64> {
+    let invalid = || DriverError::GenericError(format!("Invalid money value: {}", s));
+    let trimmed = s.trim();
+    let (negative, unsigned) = if trimmed.starts_with('-') { (true, &trimmed[1..]) } else { (false, trimmed) };
+    let (integral, fraction) = match unsigned.find('.') {
+        Some(pos) => (&unsigned[..pos], &unsigned[pos + 1..]),
+        None => (unsigned, ""),
+    };
+
+    let isDigits = |part: &str| part.bytes().all(|b| b >= b'0' && b <= b'9');
+    if integral.is_empty() || !isDigits(integral) || !isDigits(fraction) || fraction.len() > 2 {
+        return Err(invalid());
+    }
+
+    // Computed as i128, so that the most negative value doesn't overflow before the sign is applied.
+    let integral: i128 = integral.parse().map_err(|_| invalid())?;
+    let fraction: i128 = format!("{:0<2}", fraction).parse().map_err(|_| invalid())?;
+    let value = integral
+        .checked_mul(MONEY_SCALE as i128)
+        .and_then(|v| v.checked_add(fraction))
+        .map(|v| if negative { -v } else { v })
+        .ok_or_else(invalid)?;
+
+    if value < i64::min_value() as i128 || value > i64::max_value() as i128 {
+        return Err(invalid());
+    }
+
+    Ok(value as i64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    // (input, numeric_send of the input, text of the input as numeric), taken from Postgres.
+    const SAMPLES: [(&str, &str, &str); 14] = [
+        ("0", "0000000000000000", "0"),
+        ("0.00", "0000000000000002", "0.00"),
+        ("-0.00", "0000000000000002", "0.00"),
+        ("-1", "00010000400000000001", "-1"),
+        ("10000", "00010001000000000001", "10000"),
+        ("100000000", "00010002000000000001", "100000000"),
+        (
+            "12345678901234567890123456789.123456789",
+            "000b000700000009000109291a85007b11d722c509291a8504d2162e2328",
+            "12345678901234567890123456789.123456789",
+        ),
+        ("0.0001", "0001ffff000000040001", "0.0001"),
+        ("0.00000001", "0001fffe000000080001", "0.00000001"),
+        ("-0.000012", "0001fffe4000000604b0", "-0.000012"),
+        ("1.10", "0002000000000002000103e8", "1.10"),
+        ("123.4500", "0002000000000004007b1194", "123.4500"),
+        ("9999.9999", "0002000000000004270f270f", "9999.9999"),
+        ("NaN", "00000000c0000000", "NaN"),
+    ];
+
+    #[test]
+    fn encodes_like_postgres() {
+        for &(input, raw, _) in SAMPLES.iter() {
+            let mut out = Vec::new();
+            stringToNumeric(input, &mut out).unwrap();
+            assert_eq!(out, hex(raw), "{}", input);
+        }
+    }
+
+    #[test]
+    fn decodes_like_postgres() {
+        for &(_, raw, text) in SAMPLES.iter() {
+            assert_eq!(numericToString(&hex(raw)).unwrap(), text);
+        }
+
+        assert_eq!(numericToString(&hex("00000000d0000000")).unwrap(), "Infinity");
+        assert_eq!(numericToString(&hex("00000000f0000000")).unwrap(), "-Infinity");
+    }
+
+    #[test]
+    fn round_trips_more_digits_than_rust_decimal_holds() {
+        let value = format!("-{}.{}", "9".repeat(100), "0123456789".repeat(10));
+        let mut out = Vec::new();
+        stringToNumeric(&value, &mut out).unwrap();
+        assert_eq!(numericToString(&out).unwrap(), value);
+    }
+
+    #[test]
+    fn rejects_malformed_input() {
+        for input in ["", "-", ".", "1e5", "abc", "1.2.3", "--1", "1,5", "Infinity"].iter() {
+            assert!(stringToNumeric(input, &mut Vec::new()).is_err(), "{}", input);
+        }
+
+        assert!(numericToString(&hex("0001000000000000")).is_err()); // Missing digit
+        assert!(numericToString(&hex("000000")).is_err());
+        assert!(numericToString(&hex("0000000012340000")).is_err()); // Invalid sign
+    }
+
+    #[test]
+    fn converts_money_in_cents() {
+        assert_eq!(moneyToString(-12345), "-123.45");
+        assert_eq!(moneyToString(i64::min_value()), "-92233720368547758.08");
+        assert_eq!(stringToMoney("-92233720368547758.08").unwrap(), i64::min_value());
+        assert_eq!(stringToMoney("1.5").unwrap(), 150);
+        assert!(stringToMoney("1.005").is_err());
+        assert!(stringToMoney("92233720368547758.08").is_err());
+    }
+}
@@ -0,0 +1,51 @@
+// jwt-native keeps a copy of this file, as the crates are built on their own. Keep the copies in sync.
+
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe, PanicInfo};
+use std::sync::{Once, ONCE_INIT};
+
+thread_local!(static LAST_PANIC: RefCell<Option<String>> = RefCell::new(None));
+static INSTALL_HOOK: Once = ONCE_INIT;
+
+/// Chains a hook in front of the default one that records message and location of a panic for the
+/// current thread. The payload handed to `catch_unwind` carries no location, so we need to keep it here.
+pub fn install_hook() {
+    INSTALL_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            let description = describe(info);
+            LAST_PANIC.with(|last| *last.borrow_mut() = Some(description));
+            default_hook(info);
+        }));
+    });
+}
+
+/// Runs the given closure and converts a panic into an error with the panic message and location.
+/// Unwinding across the FFI boundary into the JVM is undefined behaviour, so every exported function goes through here.
+pub fn catch<F, R>(f: F) -> Result<R, String>
+where
+    F: FnOnce() -> R,
+{
+    install_hook();
+    panic::catch_unwind(AssertUnwindSafe(f)).map_err(|_| {
+        LAST_PANIC
+            .with(|last| last.borrow_mut().take())
+            .unwrap_or_else(|| String::from("Panic without further information"))
+    })
+}
+
+fn describe(info: &PanicInfo) -> String {
+    let payload = info.payload();
+    let message = match payload.downcast_ref::<&str>() {
+        Some(s) => *s,
+        None => match payload.downcast_ref::<String>() {
+            Some(s) => s.as_str(),
+            None => "Box<Any>",
+        },
+    };
+
+    match info.location() {
+        Some(location) => format!("Panic at {}:{}: {}", location.file(), location.line(), message),
+        None => format!("Panic: {}", message),
+    }
+}
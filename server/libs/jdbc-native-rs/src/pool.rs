@@ -0,0 +1,297 @@
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use driver;
+use driver::{DriverError, PsqlConnection, Result};
+use panics;
+
+/// How long a checkout waits for a connection to be returned if the pool is exhausted (same default as HikariCP).
+const CHECKOUT_TIMEOUT_MS: u64 = 30000;
+
+/// Pool of connections to a single URL.
+/// Pooled connections are handed out as regular `PsqlConnection`s, but must be returned via `checkin`. Closing one
+/// instead has to `release` its slot. Connections that are discarded are replaced in the background, see `refill`.
+pub struct PsqlPool {
+    url: String,
+    minSize: usize,
+    maxSize: usize,
+    idleTimeout: Option<Duration>,
+    state: Mutex<PoolState>,
+    returned: Condvar,
+}
+
+struct PoolState {
+    idle: Vec<IdleConnection>,
+    checkedOut: usize,
+    opening: usize, // Connections currently being opened outside of the lock for a checkout
+    refilling: usize, // Connections currently being opened by `refill`
+    replaced: u64,
+    closed: bool,
+}
+
+struct IdleConnection {
+    connection: Box<PsqlConnection>,
+    since: Instant,
+}
+
+#[derive(Serialize)]
+pub struct PoolStats {
+    idle: usize,
+    active: usize,
+    total: usize,
+    min: usize,
+    max: usize,
+    replaced: u64,
+}
+
+impl PoolState {
+    fn total(&self) -> usize {
+        self.idle.len() + self.checkedOut + self.opening + self.refilling
+    }
+}
+
+impl PsqlPool {
+    /// Opens `minSize` connections right away. An idle timeout of zero disables idle eviction.
+    pub fn create(url: String, minSize: usize, maxSize: usize, idleTimeout: Duration) -> Result<PsqlPool> {
+        if maxSize == 0 || minSize > maxSize {
+            return Err(DriverError::GenericError(format!(
+                "Invalid pool size: min {}, max {}",
+                minSize, maxSize
+            )));
+        }
+
+        let mut idle = Vec::with_capacity(maxSize);
+        for _ in 0..minSize {
+            idle.push(IdleConnection {
+                connection: Box::new(driver::connect(url.clone())?),
+                since: Instant::now(),
+            });
+        }
+
+        Ok(PsqlPool {
+            url: url,
+            minSize: minSize,
+            maxSize: maxSize,
+            idleTimeout: if idleTimeout == Duration::from_millis(0) { None } else { Some(idleTimeout) },
+            state: Mutex::new(PoolState {
+                idle: idle,
+                checkedOut: 0,
+                opening: 0,
+                refilling: 0,
+                replaced: 0,
+                closed: false,
+            }),
+            returned: Condvar::new(),
+        })
+    }
+
+    /// Hands out the most recently used idle connection that passes validation, opens a new one if the pool
+    /// is below its max size, or waits for a connection to be returned.
+    pub fn checkout(&self) -> Result<Box<PsqlConnection>> {
+        let deadline = Instant::now() + Duration::from_millis(CHECKOUT_TIMEOUT_MS);
+        let mut state = self.lock()?;
+
+        loop {
+            // Whoever got hold of the pool before it was closed mustn't check out a connection that nobody can return.
+            if state.closed {
+                return Err(DriverError::GenericError(String::from("The pool is closed")));
+            }
+
+            self.evictIdle(&mut state);
+
+            if let Some(idle) = state.idle.pop() {
+                // Validation is a round trip to the server, the slot is reserved while the lock is released for it.
+                state.checkedOut += 1;
+                drop(state);
+
+                if idle.connection.isValid() {
+                    return Ok(idle.connection);
+                }
+
+                debug!("Discarding broken pooled connection");
+                state = self.lock()?;
+                state.checkedOut -= 1;
+                state.replaced += 1;
+                continue;
+            }
+
+            if state.total() < self.maxSize {
+                state.opening += 1;
+                drop(state);
+
+                let connection = driver::connect(self.url.clone());
+                let mut state = self.lock()?;
+                state.opening -= 1;
+
+                return match connection {
+                    Ok(c) => {
+                        state.checkedOut += 1;
+                        Ok(Box::new(c))
+                    }
+                    Err(e) => {
+                        // Someone waiting might be able to use the free slot.
+                        self.returned.notify_one();
+                        Err(e)
+                    }
+                };
+            }
+
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(DriverError::GenericError(format!(
+                    "Timed out after {}ms waiting for a pooled connection, all {} connections are in use",
+                    CHECKOUT_TIMEOUT_MS, state.checkedOut
+                )));
+            }
+
+            state = self
+                .returned
+                .wait_timeout(state, deadline - now)
+                .map_err(|e| DriverError::GenericError(format!("Pool lock poisoned: {}", e)))?
+                .0;
+        }
+    }
+
+    /// Resets the session state of the connection and makes it available again.
+    /// Connections that fail to reset are dropped and replaced on a later checkout.
+    pub fn checkin(&self, mut connection: Box<PsqlConnection>) -> Result<()> {
+        let reset = connection.reset();
+        let mut state = self.lock()?;
+        state.checkedOut = state.checkedOut.checked_sub(1).ok_or_else(notCheckedOut)?;
+
+        match reset {
+            Ok(_) => state.idle.push(IdleConnection {
+                connection: connection,
+                since: Instant::now(),
+            }),
+            Err(e) => {
+                debug!("Discarding pooled connection that failed to reset: {:?}", e);
+                state.replaced += 1;
+            }
+        };
+
+        self.returned.notify_one();
+        Ok(())
+    }
+
+    /// Frees the slot of a checked out connection that was closed instead of being checked in.
+    pub fn release(&self) -> Result<()> {
+        let mut state = self.lock()?;
+        state.checkedOut = state.checkedOut.checked_sub(1).ok_or_else(notCheckedOut)?;
+        self.returned.notify_one();
+
+        Ok(())
+    }
+
+    /// Opens connections in a background thread until the pool is back at its min size. Called after connections
+    /// were discarded or closed instead of being checked in, idle eviction never goes below the min size.
+    /// Connections that can't be opened are left to the next checkout.
+    pub fn refill(pool: &Arc<PsqlPool>) {
+        let missing = match pool.lock() {
+            Ok(mut state) => {
+                let missing = if state.closed { 0 } else { pool.minSize.saturating_sub(state.total()) };
+                state.refilling += missing;
+                missing
+            }
+            Err(_) => return,
+        };
+
+        if missing == 0 {
+            return;
+        }
+
+        let pool = pool.clone();
+        thread::spawn(move || {
+            let refilled = panics::catch(move || {
+                for i in 0..missing {
+                    let connection = driver::connect(pool.url.clone());
+                    let mut state = pool.lock()?;
+                    state.refilling -= 1;
+                    pool.returned.notify_one();
+
+                    match connection {
+                        Ok(c) if !state.closed => state.idle.push(IdleConnection {
+                            connection: Box::new(c),
+                            since: Instant::now(),
+                        }),
+                        result => {
+                            state.refilling -= missing - i - 1;
+                            return result.map(|_| ());
+                        }
+                    }
+                }
+
+                Ok(())
+            });
+
+            match refilled {
+                Ok(Ok(_)) => (),
+                Ok(Err(e)) => debug!("Failed to refill the pool: {:?}", e),
+                Err(msg) => debug!("Failed to refill the pool: {}", msg),
+            }
+        });
+    }
+
+    pub fn stats(&self) -> Result<PoolStats> {
+        let mut state = self.lock()?;
+        self.evictIdle(&mut state);
+
+        Ok(PoolStats {
+            idle: state.idle.len(),
+            active: state.checkedOut,
+            total: state.total(),
+            min: self.minSize,
+            max: self.maxSize,
+            replaced: state.replaced,
+        })
+    }
+
+    /// The pool can only be closed once all connections have been returned, as they would dangle otherwise.
+    /// Closes the idle connections, checkouts waiting for a connection fail.
+    pub fn close(&self) -> Result<()> {
+        let mut state = self.lock()?;
+        if state.checkedOut > 0 || state.opening > 0 {
+            return Err(DriverError::GenericError(format!(
+                "Can't close pool, {} connections are still checked out",
+                state.checkedOut + state.opening
+            )));
+        }
+
+        state.closed = true;
+        state.idle.clear();
+        self.returned.notify_all();
+
+        Ok(())
+    }
+
+    /// Drops connections that have been idle for longer than the idle timeout, oldest first, without going below the min size.
+    fn evictIdle(&self, state: &mut PoolState) {
+        let timeout = match self.idleTimeout {
+            Some(t) => t,
+            None => return,
+        };
+
+        let now = Instant::now();
+        while state.total() > self.minSize && !state.idle.is_empty() && now.duration_since(state.idle[0].since) > timeout {
+            trace!("Evicting idle pooled connection");
+            state.idle.remove(0);
+        }
+    }
+
+    fn lock(&self) -> Result<MutexGuard<PoolState>> {
+        self.state
+            .lock()
+            .map_err(|e| DriverError::GenericError(format!("Pool lock poisoned: {}", e)))
+    }
+}
+
+fn notCheckedOut() -> DriverError {
+    DriverError::GenericError(String::from("More connections were returned to the pool than checked out"))
+}
+
+impl Drop for PsqlPool {
+    fn drop(&mut self) {
+        trace!("Dropping connection pool");
+    }
+}
@@ -0,0 +1,60 @@
+use std::slice;
+
+/// Envelope for all results handed to the JVM. Both buffers are length delimited instead of NUL terminated,
+/// so they may contain arbitrary bytes. On failure, `error` holds a serialized call result and `data` is null.
+#[repr(C)]
+#[no_mangle]
+pub struct ProtocolBuffer {
+    pub error: *mut u8,   // Always a raw pointer to a Box<[u8]>
+    pub error_len: usize,
+    pub data: *mut u8,    // Always a raw pointer to a Box<[u8]>
+    pub data_len: usize,
+}
+
+impl ProtocolBuffer {
+    pub fn data(data: Vec<u8>) -> ProtocolBuffer {
+        let (ptr, len) = into_raw_bytes(data);
+
+        ProtocolBuffer {
+            error: ::std::ptr::null_mut(),
+            error_len: 0,
+            data: ptr,
+            data_len: len,
+        }
+    }
+
+    pub fn error(error: Vec<u8>) -> ProtocolBuffer {
+        let (ptr, len) = into_raw_bytes(error);
+
+        ProtocolBuffer {
+            error: ptr,
+            error_len: len,
+            data: ::std::ptr::null_mut(),
+            data_len: 0,
+        }
+    }
+
+    pub fn into_boxed_ptr(self) -> *mut ProtocolBuffer {
+        Box::into_raw(Box::new(self))
+    }
+}
+
+impl Drop for ProtocolBuffer {
+    fn drop(&mut self) {
+        trace!("Dropping ProtocolBuffer");
+        drop_raw_bytes(self.error, self.error_len);
+        drop_raw_bytes(self.data, self.data_len);
+    }
+}
+
+/// Hands out the bytes as pointer and length, they have to be freed with `drop_raw_bytes`.
+pub fn into_raw_bytes(bytes: Vec<u8>) -> (*mut u8, usize) {
+    let len = bytes.len();
+    (Box::into_raw(bytes.into_boxed_slice()) as *mut u8, len)
+}
+
+pub fn drop_raw_bytes(ptr: *mut u8, len: usize) {
+    if !ptr.is_null() {
+        drop(unsafe { Box::from_raw(slice::from_raw_parts_mut(ptr, len)) });
+    }
+}
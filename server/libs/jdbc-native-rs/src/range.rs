@@ -0,0 +1,51 @@
+use byteorder::{BigEndian, ReadBytesExt};
+use std::io::Cursor;
+
+use driver::{DriverError, Result};
+
+// Binary range wire format: flags (u8), followed by the lower and upper bound as length (i32) and value bytes,
+// each only present if the range isn't empty and the bound isn't infinite.
+
+const RANGE_EMPTY: u8 = 0x01;
+const RANGE_LB_INC: u8 = 0x02;
+const RANGE_UB_INC: u8 = 0x04;
+const RANGE_LB_INF: u8 = 0x08;
+const RANGE_UB_INF: u8 = 0x10;
+
+pub struct RawRange<'a> {
+    pub empty: bool,
+    pub lower: Option<&'a [u8]>,
+    pub upper: Option<&'a [u8]>,
+    pub lowerInclusive: bool,
+    pub upperInclusive: bool,
+}
+
+pub fn readRange(raw: &[u8]) -> Result<RawRange> {
+    let mut rdr = Cursor::new(raw);
+    let flags = rdr.read_u8()?;
+    let empty = flags & RANGE_EMPTY != 0;
+
+    let lower = if empty || flags & RANGE_LB_INF != 0 { None } else { Some(readBound(raw, &mut rdr)?) };
+    let upper = if empty || flags & RANGE_UB_INF != 0 { None } else { Some(readBound(raw, &mut rdr)?) };
+
+    Ok(RawRange {
+        empty: empty,
+        lower: lower,
+        upper: upper,
+        lowerInclusive: flags & RANGE_LB_INC != 0,
+        upperInclusive: flags & RANGE_UB_INC != 0,
+    })
+}
+
+fn readBound<'a>(raw: &'a [u8], rdr: &mut Cursor<&'a [u8]>) -> Result<&'a [u8]> {
+    let len = rdr.read_i32::<BigEndian>()?;
+    let start = rdr.position() as usize;
+    let end = start + len.max(0) as usize;
+
+    if len < 0 || end > raw.len() {
+        return Err(DriverError::GenericError(String::from("Range bound exceeds the received data")));
+    }
+
+    rdr.set_position(end as u64);
+    Ok(&raw[start..end])
+}
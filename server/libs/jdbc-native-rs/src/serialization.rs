@@ -2,13 +2,21 @@ use serde_json;
 use postgres;
 use postgres::rows::{Row, Rows};
 use postgres::stmt::Column;
+use postgres::types::{Field, FromSql, Kind, Type};
 use driver::Result;
 use rust_decimal::Decimal;
 use chrono::prelude::*;
 use driver::DriverError;
 use num_traits::ToPrimitive;
-use jdbc_params::{JdbcParameterType, MagicDateTime};
+use jdbc_params::JdbcParameterType;
 use uuid::Uuid;
+use numeric;
+use array;
+use composite;
+use range;
+use temporal;
+use text_fallback;
+use base64;
 
 #[derive(Serialize)]
 pub struct ResultSet {
@@ -17,38 +25,84 @@ pub struct ResultSet {
 }
 #[derive(Serialize)]
 pub struct ResultColumn {
-    name: String,
-    discriminator: JdbcParameterType
+    pub name: String,
+    pub discriminator: JdbcParameterType,
+    pub elementDiscriminator: Option<JdbcParameterType>,
+    pub typeName: Option<String>,
 }
 
-fn mapColumn(col: &Column) -> Result<ResultColumn> {
-    let discriminator = match col.type_() {
+pub fn mapColumn(col: &Column, textFallback: bool) -> Result<ResultColumn> {
+    let (discriminator, (elementDiscriminator, typeName)) = match col.type_().kind() {
+        &Kind::Array(ref member) => (JdbcParameterType::Array, mapTypeOrFallback(member, textFallback).map(|(d, n)| (Some(d), n))?),
+        &Kind::Range(ref subtype) => (JdbcParameterType::Range, mapTypeOrFallback(subtype, textFallback).map(|(d, n)| (Some(d), n))?),
+        _ => {
+            let (d, n) = mapTypeOrFallback(col.type_(), textFallback)?;
+            (d, (None, n))
+        }
+    };
+
+    Ok(ResultColumn {
+        name: String::from(col.name()),
+        discriminator: discriminator,
+        elementDiscriminator: elementDiscriminator,
+        typeName: typeName,
+    })
+}
+
+/// With the text fallback enabled, unhandled types the fallback can decode are reported as Unknown together with their type name.
+fn mapTypeOrFallback(ty: &Type, textFallback: bool) -> Result<(JdbcParameterType, Option<String>)> {
+    match mapType(ty) {
+        Ok(discriminator) => Ok((discriminator, None)),
+        Err(_) if textFallback && text_fallback::isSupported(ty.name()) => Ok((JdbcParameterType::Unknown, Some(String::from(ty.name())))),
+        Err(_) if textFallback => Err(text_fallback::unsupported(ty.name())),
+        Err(e) => Err(e),
+    }
+}
+
+/// Non-builtin types (enums, domains, composites, ranges) are resolved by the postgres crate through `pg_type`
+/// when a statement is prepared and cached per connection, so they can be told apart by their kind.
+fn mapType(ty: &Type) -> Result<JdbcParameterType> {
+    match ty {
         &postgres::types::BOOL => Ok(JdbcParameterType::Boolean),
+        &postgres::types::INT2 => Ok(JdbcParameterType::Int),
         &postgres::types::INT4 => Ok(JdbcParameterType::Int),
         &postgres::types::INT8 => Ok(JdbcParameterType::Long),
-        &postgres::types::NUMERIC => Ok(JdbcParameterType::Double),
+        &postgres::types::OID => Ok(JdbcParameterType::Long),
+        &postgres::types::FLOAT4 => Ok(JdbcParameterType::Double),
+        &postgres::types::FLOAT8 => Ok(JdbcParameterType::Double),
+        &postgres::types::NUMERIC => Ok(JdbcParameterType::Decimal),
+        &postgres::types::MONEY => Ok(JdbcParameterType::Decimal),
         &postgres::types::VARCHAR => Ok(JdbcParameterType::String),
         &postgres::types::TEXT => Ok(JdbcParameterType::String),
         &postgres::types::BPCHAR => Ok(JdbcParameterType::String),
         &postgres::types::TIMESTAMP => Ok(JdbcParameterType::DateTime),
+        &postgres::types::TIMESTAMPTZ => Ok(JdbcParameterType::DateTimeTz),
+        &postgres::types::DATE => Ok(JdbcParameterType::Date),
+        &postgres::types::TIME => Ok(JdbcParameterType::Time),
+        &postgres::types::TIMETZ => Ok(JdbcParameterType::TimeTz),
+        &postgres::types::INTERVAL => Ok(JdbcParameterType::Interval),
         &postgres::types::UUID => Ok(JdbcParameterType::UUID),
+        &postgres::types::JSON => Ok(JdbcParameterType::Json),
+        &postgres::types::JSONB => Ok(JdbcParameterType::Json),
+        &postgres::types::BYTEA => Ok(JdbcParameterType::Bytes),
         &postgres::types::VOID => Ok(JdbcParameterType::VOID),
-        x =>  Err(DriverError::GenericError(format!(
-            "Unhandled type in map column: {}",
-            x
-        )))
-    }?;
-
-    Ok(ResultColumn {
-        name: String::from(col.name()),
-        discriminator: discriminator,
-    })
+        x => match x.kind() {
+            &Kind::Enum(_) => Ok(JdbcParameterType::String),
+            &Kind::Domain(ref base) => mapType(base),
+            &Kind::Composite(_) => Ok(JdbcParameterType::Composite),
+            &Kind::Range(_) => Ok(JdbcParameterType::Range),
+            _ => Err(DriverError::GenericError(format!(
+                "Unhandled type in map column: {}",
+                x
+            ))),
+        }
+    }
 }
 
 impl ResultSet {
-    pub fn create(rows: Rows) -> Result<ResultSet> {
-        let data: Result<Vec<serde_json::Value>> = rows.iter().map(|r| ResultSet::serializeToJson(r)).collect();
-        let columns: Result<Vec<ResultColumn>> = rows.columns().iter().map(mapColumn).collect();
+    pub fn create(rows: Rows, textFallback: bool) -> Result<ResultSet> {
+        let data: Result<Vec<serde_json::Value>> = rows.iter().map(|r| ResultSet::serializeToJson(r, textFallback)).collect();
+        let columns: Result<Vec<ResultColumn>> = rows.columns().iter().map(|c| mapColumn(c, textFallback)).collect();
 
         Ok(ResultSet {
             columns: columns?,
@@ -56,67 +110,154 @@ impl ResultSet {
         })
     }
 
-    fn serializeToJson(row: Row) -> Result<serde_json::Value> {
+    fn serializeToJson(row: Row, textFallback: bool) -> Result<serde_json::Value> {
         let mut vec = Vec::new();
         for (i, column) in row.columns().iter().enumerate() {
-            let isNull = row.get_bytes(i).is_none();
-            if !isNull {
-                let json_value: serde_json::Value = match column.type_() {
-                    &postgres::types::BOOL => serde_json::Value::Bool(row.get(i)),
-                    &postgres::types::INT4 => {
-                        let value: i32 = row.get(i);
-                        let number = serde_json::Number::from_f64(value as f64).unwrap();
-                        serde_json::Value::Number(number)
-                    }
-                    &postgres::types::INT8 => {
-                        let value: i64 = row.get(i);
-                        let number = serde_json::Number::from_f64(value as f64).unwrap();
-                        serde_json::Value::Number(number)
-                    }
-                    &postgres::types::VARCHAR => serde_json::Value::String(row.get(i)),
-                    &postgres::types::TEXT => serde_json::Value::String(row.get(i)),
-                    &postgres::types::NUMERIC => {
-                        let value: Decimal = row.get(i);
-                        let number = serde_json::Number::from_f64(value.to_f64().unwrap()).unwrap();
-                        serde_json::Value::Number(number)
-                    }
-                    &postgres::types::TIMESTAMP => {
-                        let value: NaiveDateTime = row.get(i);
-                        let date: DateTime<Utc> = DateTime::from_utc(value, Utc);
-                        let mdt = MagicDateTime {
-                            year: date.year(),
-                            month: date.month(),
-                            day: date.day(),
-                            hour: date.hour(),
-                            minute: date.minute(),
-                            seconds: date.second(),
-                            millis: date.timestamp_subsec_millis(),
-                        };
-
-                        serde_json::to_value(mdt)?
-                    }
-                    &postgres::types::UUID => {
-                        let uuid: Uuid = row.get(i);
-                        serde_json::Value::String(uuid.to_string())
-                    },
-                    &postgres::types::BPCHAR => serde_json::Value::String(row.get(i)),
-                    &postgres::types::VOID => {
-                        serde_json::Value::Null
-                    },
-                    x => {
-                        return Err(DriverError::GenericError(format!(
-                            "Unhandled type in json serialize: {}",
-                            x
-                        )))
-                    }
-                };
-
-                vec.push(json_value);
-            } else {
-                vec.push(serde_json::Value::Null);
+            match row.get_bytes(i) {
+                Some(raw) => vec.push(valueToJson(column.type_(), raw, textFallback)?),
+                None => vec.push(serde_json::Value::Null),
             }
         }
 
         return Ok(serde_json::Value::Array(vec));
     }
-}
\ No newline at end of file
+}
+
+/// Converts a single non-null value in binary format.
+pub fn valueToJson(ty: &Type, raw: &[u8], textFallback: bool) -> Result<serde_json::Value> {
+    let json_value: serde_json::Value = match ty {
+        &postgres::types::BOOL => serde_json::Value::Bool(bool::from_sql(ty, raw)?),
+        &postgres::types::INT2 => {
+            let value = i16::from_sql(ty, raw)?;
+            serde_json::Value::Number(serde_json::Number::from(value))
+        }
+        &postgres::types::INT4 => {
+            let value = i32::from_sql(ty, raw)?;
+            serde_json::Value::Number(serde_json::Number::from(value))
+        }
+        &postgres::types::INT8 => {
+            let value = i64::from_sql(ty, raw)?;
+            serde_json::Value::Number(serde_json::Number::from(value))
+        }
+        &postgres::types::OID => {
+            let value = u32::from_sql(ty, raw)?;
+            serde_json::Value::Number(serde_json::Number::from(value))
+        }
+        &postgres::types::FLOAT4 => floatToJson(f32::from_sql(ty, raw)? as f64),
+        &postgres::types::FLOAT8 => floatToJson(f64::from_sql(ty, raw)?),
+        &postgres::types::MONEY => serde_json::Value::String(numeric::moneyToString(i64::from_sql(ty, raw)?)),
+        &postgres::types::VARCHAR => serde_json::Value::String(String::from_sql(ty, raw)?),
+        &postgres::types::TEXT => serde_json::Value::String(String::from_sql(ty, raw)?),
+        &postgres::types::NUMERIC => {
+            // Emitted as string, as JSON numbers are parsed into doubles by most consumers.
+            serde_json::Value::String(numeric::numericToString(raw)?)
+        }
+        &postgres::types::TIMESTAMP => temporal::timestampToJson(raw)?,
+        &postgres::types::TIMESTAMPTZ => temporal::timestampTzToJson(raw)?,
+        &postgres::types::DATE => temporal::dateToJson(raw)?,
+        &postgres::types::TIME => temporal::timeToJson(raw)?,
+        &postgres::types::TIMETZ => temporal::timeTzToJson(raw)?,
+        &postgres::types::INTERVAL => temporal::intervalToJson(raw)?,
+        &postgres::types::UUID => {
+            let uuid = Uuid::from_sql(ty, raw)?;
+            serde_json::Value::String(uuid.to_string())
+        },
+        &postgres::types::BPCHAR => serde_json::Value::String(String::from_sql(ty, raw)?),
+        &postgres::types::JSON | &postgres::types::JSONB => serde_json::Value::from_sql(ty, raw)?,
+        &postgres::types::BYTEA => serde_json::Value::String(base64::encode(raw)),
+        &postgres::types::VOID => {
+            serde_json::Value::Null
+        },
+        x => match x.kind() {
+            &Kind::Array(ref member) => arrayToJson(member, raw, textFallback)?,
+            // Enum values are transmitted as their label.
+            &Kind::Enum(_) => serde_json::Value::String(String::from_sql(&postgres::types::TEXT, raw)?),
+            &Kind::Domain(ref base) => valueToJson(base, raw, textFallback)?,
+            &Kind::Composite(ref fields) => compositeToJson(fields, raw, textFallback)?,
+            &Kind::Range(ref subtype) => rangeToJson(subtype, raw, textFallback)?,
+            _ if textFallback => serde_json::Value::String(text_fallback::binaryToText(x.name(), raw)?),
+            _ => {
+                return Err(DriverError::GenericError(format!(
+                    "Unhandled type in json serialize: {}",
+                    x
+                )))
+            }
+        }
+    };
+
+    Ok(json_value)
+}
+
+/// JSON has no representation for NaN and infinity, those are emitted as the strings the JVM parses them from.
+fn floatToJson(value: f64) -> serde_json::Value {
+    match serde_json::Number::from_f64(value) {
+        Some(n) => serde_json::Value::Number(n),
+        None if value.is_nan() => serde_json::Value::String(String::from("NaN")),
+        None if value > 0.0 => serde_json::Value::String(String::from("Infinity")),
+        None => serde_json::Value::String(String::from("-Infinity")),
+    }
+}
+
+/// Arrays are emitted as nested JSON arrays, one level per dimension.
+fn arrayToJson(member: &Type, raw: &[u8], textFallback: bool) -> Result<serde_json::Value> {
+    let array = array::readArray(raw)?;
+    if array.dimensions.is_empty() {
+        return Ok(serde_json::Value::Array(Vec::new()));
+    }
+
+    let elements: Result<Vec<serde_json::Value>> = array.elements.iter().map(|element| match *element {
+        Some(bytes) => valueToJson(member, bytes, textFallback),
+        None => Ok(serde_json::Value::Null),
+    }).collect();
+
+    Ok(nestElements(&array.dimensions, &mut elements?.into_iter()))
+}
+
+/// Composites are emitted as JSON objects keyed by field name.
+fn compositeToJson(fields: &[Field], raw: &[u8], textFallback: bool) -> Result<serde_json::Value> {
+    let values = composite::readComposite(raw)?;
+    if values.len() != fields.len() {
+        return Err(DriverError::GenericError(format!(
+            "Composite value has {} fields, expected {}",
+            values.len(),
+            fields.len()
+        )));
+    }
+
+    let mut map = serde_json::Map::new();
+    for (field, value) in fields.iter().zip(values.iter()) {
+        let json = match value.value {
+            Some(bytes) => valueToJson(field.type_(), bytes, textFallback)?,
+            None => serde_json::Value::Null,
+        };
+
+        map.insert(String::from(field.name()), json);
+    }
+
+    Ok(serde_json::Value::Object(map))
+}
+
+/// Ranges are emitted as objects, infinite bounds are null.
+fn rangeToJson(subtype: &Type, raw: &[u8], textFallback: bool) -> Result<serde_json::Value> {
+    let range = range::readRange(raw)?;
+    let bound = |b: Option<&[u8]>| match b {
+        Some(bytes) => valueToJson(subtype, bytes, textFallback),
+        None => Ok(serde_json::Value::Null),
+    };
+
+    let mut map = serde_json::Map::new();
+    map.insert(String::from("empty"), serde_json::Value::Bool(range.empty));
+    map.insert(String::from("lower"), bound(range.lower)?);
+    map.insert(String::from("upper"), bound(range.upper)?);
+    map.insert(String::from("lowerInclusive"), serde_json::Value::Bool(range.lowerInclusive));
+    map.insert(String::from("upperInclusive"), serde_json::Value::Bool(range.upperInclusive));
+
+    Ok(serde_json::Value::Object(map))
+}
+
+fn nestElements<I>(dimensions: &[usize], elements: &mut I) -> serde_json::Value where I: Iterator<Item = serde_json::Value> {
+    match dimensions.split_first() {
+        Some((len, inner)) => serde_json::Value::Array((0..*len).map(|_| nestElements(inner, &mut *elements)).collect()),
+        None => elements.next().unwrap_or(serde_json::Value::Null),
+    }
+}
@@ -0,0 +1,283 @@
+use byteorder::{BigEndian, ReadBytesExt, WriteBytesExt};
+use chrono::prelude::*;
+use chrono::Duration;
+use serde_json;
+use std::io::Cursor;
+
+use driver::{DriverError, Result};
+use jdbc_params::MagicDateTime;
+
+// Temporal values are decoded from the binary wire format directly, as chrono can't represent Postgres' infinity values.
+// Timestamps are microseconds since 2000-01-01 (i64), dates are days since 2000-01-01 (i32),
+// times are microseconds since midnight (i64). Infinity is encoded as the max / min value of the respective integer.
+
+pub const POSITIVE_INFINITY: &str = "infinity";
+pub const NEGATIVE_INFINITY: &str = "-infinity";
+
+const MICROS_PER_DAY: i64 = 86400000000;
+
+#[derive(Serialize, Deserialize, Debug, PartialEq)]
+pub struct Interval {
+    pub months: i32,
+    pub days: i32,
+    pub microseconds: i64,
+}
+
+fn epoch() -> NaiveDateTime {
+    NaiveDate::from_ymd(2000, 1, 1).and_hms(0, 0, 0)
+}
+
+fn infinityToJson(positive: bool) -> serde_json::Value {
+    serde_json::Value::String(String::from(if positive { POSITIVE_INFINITY } else { NEGATIVE_INFINITY }))
+}
+
+fn readTimestamp(raw: &[u8]) -> Result<::std::result::Result<NaiveDateTime, bool>> {
+    let micros = Cursor::new(raw).read_i64::<BigEndian>()?;
+    match micros {
+        ::std::i64::MAX => Ok(Err(true)),
+        ::std::i64::MIN => Ok(Err(false)),
+        m => epoch()
+            .checked_add_signed(Duration::microseconds(m))
+            .map(Ok)
+            .ok_or_else(|| DriverError::GenericError(format!("Timestamp out of range: {}", m))),
+    }
+}
+
+/// TIMESTAMP values keep the MagicDateTime representation, extended by the microseconds of the second.
+pub fn timestampToJson(raw: &[u8]) -> Result<serde_json::Value> {
+    match readTimestamp(raw)? {
+        Ok(ts) => {
+            let micros = ts.nanosecond() / 1000;
+            let mdt = MagicDateTime {
+                year: ts.year(),
+                month: ts.month(),
+                day: ts.day(),
+                hour: ts.hour(),
+                minute: ts.minute(),
+                seconds: ts.second(),
+                millis: micros / 1000,
+                micros: Some(micros),
+            };
+
+            Ok(serde_json::to_value(mdt)?)
+        }
+        Err(positive) => Ok(infinityToJson(positive)),
+    }
+}
+
+/// TIMESTAMPTZ values are points in time, Postgres doesn't store the offset they were written with.
+/// They are emitted as RFC 3339 strings in UTC with an explicit offset, whatever the TimeZone of the session: the
+/// instant is exact, but unlike psql's output, the text doesn't show the local time of the session.
+pub fn timestampTzToJson(raw: &[u8]) -> Result<serde_json::Value> {
+    match readTimestamp(raw)? {
+        Ok(ts) => Ok(serde_json::Value::String(ts.format("%Y-%m-%dT%H:%M:%S%.6f+00:00").to_string())),
+        Err(positive) => Ok(infinityToJson(positive)),
+    }
+}
+
+pub fn dateToJson(raw: &[u8]) -> Result<serde_json::Value> {
+    let days = Cursor::new(raw).read_i32::<BigEndian>()?;
+    match days {
+        ::std::i32::MAX => Ok(infinityToJson(true)),
+        ::std::i32::MIN => Ok(infinityToJson(false)),
+        d => epoch()
+            .date()
+            .checked_add_signed(Duration::days(d as i64))
+            .map(|date| serde_json::Value::String(date.format("%Y-%m-%d").to_string()))
+            .ok_or_else(|| DriverError::GenericError(format!("Date out of range: {}", d))),
+    }
+}
+
+pub fn timeToJson(raw: &[u8]) -> Result<serde_json::Value> {
+    let micros = Cursor::new(raw).read_i64::<BigEndian>()?;
+    Ok(serde_json::Value::String(formatTime(micros)?))
+}
+
+/// The zone of a TIMETZ is transmitted in seconds west of UTC, we emit it the ISO way (east of UTC).
+pub fn timeTzToJson(raw: &[u8]) -> Result<serde_json::Value> {
+    let mut rdr = Cursor::new(raw);
+    let micros = rdr.read_i64::<BigEndian>()?;
+    let offsetEast = -rdr.read_i32::<BigEndian>()?;
+
+    Ok(serde_json::Value::String(format!("{}{}", formatTime(micros)?, formatOffset(offsetEast))))
+}
+
+pub fn intervalToJson(raw: &[u8]) -> Result<serde_json::Value> {
+    let mut rdr = Cursor::new(raw);
+    let interval = Interval {
+        microseconds: rdr.read_i64::<BigEndian>()?,
+        days: rdr.read_i32::<BigEndian>()?,
+        months: rdr.read_i32::<BigEndian>()?,
+    };
+
+    Ok(serde_json::to_value(interval)?)
+}
+
+fn formatTime(micros: i64) -> Result<String> {
+    // 24:00:00 is a valid time in Postgres, but not in chrono.
+    if micros == MICROS_PER_DAY {
+        return Ok(String::from("24:00:00.000000"));
+    }
+
+    let inRange = micros >= 0 && micros < MICROS_PER_DAY;
+    let time = if inRange {
+        NaiveTime::from_num_seconds_from_midnight_opt((micros / 1000000) as u32, (micros % 1000000) as u32 * 1000)
+    } else {
+        None
+    };
+
+    time.map(|t| t.format("%H:%M:%S%.6f").to_string())
+        .ok_or_else(|| DriverError::GenericError(format!("Time out of range: {}", micros)))
+}
+
+fn formatOffset(offsetEast: i32) -> String {
+    let sign = if offsetEast < 0 { '-' } else { '+' };
+    let abs = offsetEast.abs();
+    let (hours, minutes, seconds) = (abs / 3600, (abs % 3600) / 60, abs % 60);
+
+    if seconds == 0 {
+        format!("{}{:02}:{:02}", sign, hours, minutes)
+    } else {
+        format!("{}{:02}:{:02}:{:02}", sign, hours, minutes, seconds)
+    }
+}
+
+/// Writes infinity for TIMESTAMP, TIMESTAMPTZ and DATE targets.
+pub fn writeInfinity(positive: bool, isDate: bool, out: &mut Vec<u8>) -> Result<()> {
+    if isDate {
+        out.write_i32::<BigEndian>(if positive { ::std::i32::MAX } else { ::std::i32::MIN })?;
+    } else {
+        out.write_i64::<BigEndian>(if positive { ::std::i64::MAX } else { ::std::i64::MIN })?;
+    }
+
+    Ok(())
+}
+
+pub fn writeTimeTz(time: &NaiveTime, offsetEast: i32, out: &mut Vec<u8>) -> Result<()> {
+    let micros = time.num_seconds_from_midnight() as i64 * 1000000 + (time.nanosecond() / 1000) as i64;
+    out.write_i64::<BigEndian>(micros)?;
+    out.write_i32::<BigEndian>(-offsetEast)?;
+
+    Ok(())
+}
+
+pub fn writeInterval(interval: &Interval, out: &mut Vec<u8>) -> Result<()> {
+    out.write_i64::<BigEndian>(interval.microseconds)?;
+    out.write_i32::<BigEndian>(interval.days)?;
+    out.write_i32::<BigEndian>(interval.months)?;
+
+    Ok(())
+}
+
+/// Parses `HH:MM:SS[.ffffff]` followed by an offset like `+02`, `+02:00`, `-05:30` or `Z`.
+pub fn parseTimeTz(s: &str) -> Result<(NaiveTime, i32)> {
+    let invalid = || DriverError::GenericError(format!("Invalid time with time zone: {}", s));
+    let pos = s.rfind(|c| c == '+' || c == '-' || c == 'Z').ok_or_else(invalid)?;
+    let (time, offset) = s.split_at(pos);
+    let time = NaiveTime::parse_from_str(time, "%H:%M:%S%.f").map_err(|_| invalid())?;
+
+    if offset == "Z" {
+        return Ok((time, 0));
+    }
+
+    let sign = if offset.starts_with('-') { -1 } else { 1 };
+    let parts: Vec<&str> = offset[1..].split(':').collect();
+    if parts.len() > 3 {
+        return Err(invalid());
+    }
+
+    // Postgres accepts offsets up to 15:59:59.
+    let mut seconds = 0;
+    for (i, part) in parts.iter().enumerate() {
+        if part.is_empty() || part.len() > 2 || !part.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(invalid());
+        }
+
+        let value: i32 = part.parse().map_err(|_| invalid())?;
+        if value > [15, 59, 59][i] {
+            return Err(DriverError::GenericError(format!("Time zone offset out of range: {}", s)));
+        }
+
+        seconds += value * [3600, 60, 1][i];
+    }
+
+    Ok((time, sign * seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn string(value: serde_json::Value) -> String {
+        value.as_str().unwrap().to_string()
+    }
+
+    // The wire values are taken from Postgres' send functions.
+    #[test]
+    fn reads_infinity() {
+        assert_eq!(string(timestampTzToJson(&hex("7fffffffffffffff")).unwrap()), POSITIVE_INFINITY);
+        assert_eq!(string(timestampToJson(&hex("8000000000000000")).unwrap()), NEGATIVE_INFINITY);
+        assert_eq!(string(dateToJson(&hex("7fffffff")).unwrap()), POSITIVE_INFINITY);
+        assert_eq!(string(dateToJson(&hex("ffffffff")).unwrap()), "1999-12-31");
+    }
+
+    #[test]
+    fn writes_infinity() {
+        let mut out = Vec::new();
+        writeInfinity(true, false, &mut out).unwrap();
+        writeInfinity(false, true, &mut out).unwrap();
+        assert_eq!(out, hex("7fffffffffffffff80000000"));
+    }
+
+    #[test]
+    fn reads_timestamptz_in_utc() {
+        assert_eq!(string(timestampTzToJson(&hex("0002263c7b99edc8")).unwrap()), "2019-03-04T05:06:07.000008+00:00");
+    }
+
+    #[test]
+    fn reads_the_end_of_the_day() {
+        assert_eq!(string(timeToJson(&hex("000000141dd76000")).unwrap()), "24:00:00.000000");
+        assert!(timeToJson(&hex("000000141dd76001")).is_err());
+        assert!(timeToJson(&hex("ffffffffffffffff")).is_err());
+    }
+
+    #[test]
+    fn round_trips_timetz() {
+        assert_eq!(string(timeTzToJson(&hex("0000000a8be62608ffffb2a8")).unwrap()), "12:34:56.789000+05:30");
+        assert_eq!(string(timeTzToJson(&hex("00000000000000000000e0ff")).unwrap()), "00:00:00.000000-15:59:59");
+
+        let (time, offset) = parseTimeTz("12:34:56.789+05:30").unwrap();
+        let mut out = Vec::new();
+        writeTimeTz(&time, offset, &mut out).unwrap();
+        assert_eq!(out, hex("0000000a8be62608ffffb2a8"));
+
+        assert_eq!(parseTimeTz("00:00:00-15:59:59").unwrap().1, -57599);
+        assert_eq!(parseTimeTz("10:00:00Z").unwrap().1, 0);
+        assert_eq!(parseTimeTz("10:00:00+02").unwrap().1, 7200);
+    }
+
+    #[test]
+    fn rejects_invalid_time_zones() {
+        for input in ["10:00:00+16", "10:00:00+02:60", "10:00:00+02:00:60", "10:00:00+99999999", "10:00:00+", "10:00:00+1:2:3:4", "10:00:00++2", "10:00"].iter() {
+            assert!(parseTimeTz(input).is_err(), "{}", input);
+        }
+    }
+
+    #[test]
+    fn round_trips_intervals() {
+        let raw = hex("000000036c8bc087000000030000000e"); // 1 year 2 mons 3 days 04:05:06.000007
+        let interval = Interval { months: 14, days: 3, microseconds: 14706000007 };
+        assert_eq!(intervalToJson(&raw).unwrap(), serde_json::to_value(&interval).unwrap());
+
+        let mut out = Vec::new();
+        writeInterval(&interval, &mut out).unwrap();
+        assert_eq!(out, raw);
+
+        let negative = Interval { months: -1, days: 0, microseconds: -1000000 };
+        assert_eq!(intervalToJson(&hex("fffffffffff0bdc000000000ffffffff")).unwrap(), serde_json::to_value(&negative).unwrap());
+    }
+}
@@ -0,0 +1,210 @@
+use byteorder::{BigEndian, ReadBytesExt};
+use std::io::{Cursor, Read};
+use std::net::{Ipv4Addr, Ipv6Addr};
+
+use driver::{DriverError, Result};
+
+// Results are always requested in binary format, so the text representation of types the driver doesn't handle
+// has to be reconstructed. Only types whose binary format is known are decoded into the text Postgres would produce,
+// other types are reported as unsupported and have to be cast to text in the query.
+
+pub fn isSupported(typeName: &str) -> bool {
+    match typeName {
+        "inet" | "cidr" | "macaddr" | "macaddr8" | "bit" | "varbit" | "point" | "lseg" | "box" | "line" | "circle" | "ltree"
+        | "lquery" | "ltxtquery" | "hstore" | "xml" | "citext" => true,
+        _ => false,
+    }
+}
+
+pub fn unsupported(typeName: &str) -> DriverError {
+    DriverError::GenericError(format!("Columns of type {} can't be read, cast them to text in the query", typeName))
+}
+
+pub fn binaryToText(typeName: &str, raw: &[u8]) -> Result<String> {
+    match typeName {
+        "inet" | "cidr" => inetToText(raw, typeName == "cidr"),
+        "macaddr" | "macaddr8" => Ok(raw.iter().map(|b| format!("{:02x}", b)).collect::<Vec<String>>().join(":")),
+        "bit" | "varbit" => bitsToText(raw),
+        "point" => readFloats(raw, 2).map(|f| formatPoint(f[0], f[1])),
+        "lseg" => readFloats(raw, 4).map(|f| format!("[{},{}]", formatPoint(f[0], f[1]), formatPoint(f[2], f[3]))),
+        "box" => readFloats(raw, 4).map(|f| format!("{},{}", formatPoint(f[0], f[1]), formatPoint(f[2], f[3]))),
+        "line" => readFloats(raw, 3).map(|f| format!("{{{},{},{}}}", f[0], f[1], f[2])),
+        "circle" => readFloats(raw, 3).map(|f| format!("<{},{}>", formatPoint(f[0], f[1]), f[2])),
+        "ltree" | "lquery" | "ltxtquery" => versionedText(raw),
+        "hstore" => hstoreToText(raw),
+        // The binary format of these types is their text.
+        "xml" | "citext" => ::std::str::from_utf8(raw).map(String::from).map_err(|e| DriverError::GenericError(e.to_string())),
+        _ => Err(unsupported(typeName)),
+    }
+}
+
+/// family (u8, 2 = IPv4, 3 = IPv6), netmask bits (u8), is_cidr (u8), address length (u8), address bytes.
+fn inetToText(raw: &[u8], isCidr: bool) -> Result<String> {
+    let mut rdr = Cursor::new(raw);
+    let family = rdr.read_u8()?;
+    let bits = rdr.read_u8()?;
+    let _isCidr = rdr.read_u8()?;
+    let len = rdr.read_u8()? as usize;
+    let mut addr = vec![0u8; len];
+    rdr.read_exact(&mut addr)?;
+
+    let (text, maxBits) = match (family, len) {
+        (2, 4) => (Ipv4Addr::new(addr[0], addr[1], addr[2], addr[3]).to_string(), 32),
+        (3, 16) => {
+            let mut octets = [0u8; 16];
+            octets.copy_from_slice(&addr);
+            (Ipv6Addr::from(octets).to_string(), 128)
+        }
+        _ => return Err(DriverError::GenericError(format!("Invalid inet family: {}", family))),
+    };
+
+    // inet omits the netmask for single hosts, cidr always prints it.
+    if isCidr || bits != maxBits {
+        Ok(format!("{}/{}", text, bits))
+    } else {
+        Ok(text)
+    }
+}
+
+/// bit length (i32), followed by the bits packed into bytes, most significant bit first.
+fn bitsToText(raw: &[u8]) -> Result<String> {
+    let mut rdr = Cursor::new(raw);
+    let len = rdr.read_i32::<BigEndian>()?;
+    let bytes = &raw[4..];
+
+    if len < 0 || (len as usize + 7) / 8 > bytes.len() {
+        return Err(DriverError::GenericError(format!("Invalid bit string length: {}", len)));
+    }
+
+    Ok((0..len as usize).map(|i| if bytes[i / 8] & (0x80 >> (i % 8)) != 0 { '1' } else { '0' }).collect())
+}
+
+fn readFloats(raw: &[u8], count: usize) -> Result<Vec<f64>> {
+    let mut rdr = Cursor::new(raw);
+    (0..count).map(|_| rdr.read_f64::<BigEndian>().map_err(DriverError::from)).collect()
+}
+
+fn formatPoint(x: f64, y: f64) -> String {
+    format!("({},{})", x, y)
+}
+
+/// The ltree types prefix their text with a version byte.
+fn versionedText(raw: &[u8]) -> Result<String> {
+    match raw.split_first() {
+        Some((&1, text)) => ::std::str::from_utf8(text).map(String::from).map_err(|e| DriverError::GenericError(e.to_string())),
+        _ => Err(DriverError::GenericError(String::from("Unknown ltree version"))),
+    }
+}
+
+/// pair count (i32), followed by key and value as length (i32, -1 for a NULL value) and bytes.
+fn hstoreToText(raw: &[u8]) -> Result<String> {
+    let mut rdr = Cursor::new(raw);
+    let count = rdr.read_i32::<BigEndian>()?;
+    let mut pairs = Vec::new();
+
+    for _ in 0..count.max(0) {
+        let key = readHstoreString(&mut rdr)?.unwrap_or_default();
+        let value = match readHstoreString(&mut rdr)? {
+            Some(v) => quoteHstore(&v),
+            None => String::from("NULL"),
+        };
+
+        pairs.push(format!("{}=>{}", quoteHstore(&key), value));
+    }
+
+    Ok(pairs.join(", "))
+}
+
+fn readHstoreString(rdr: &mut Cursor<&[u8]>) -> Result<Option<String>> {
+    let len = rdr.read_i32::<BigEndian>()?;
+    if len < 0 {
+        return Ok(None);
+    }
+
+    if len as u64 > rdr.get_ref().len() as u64 - rdr.position() {
+        return Err(DriverError::GenericError(String::from("hstore entry exceeds the received data")));
+    }
+
+    let mut bytes = vec![0u8; len as usize];
+    rdr.read_exact(&mut bytes)?;
+    String::from_utf8(bytes).map(Some).map_err(|e| DriverError::GenericError(e.to_string()))
+}
+
+fn quoteHstore(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(s: &str) -> Vec<u8> {
+        (0..s.len()).step_by(2).map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap()).collect()
+    }
+
+    fn text(typeName: &str, raw: &str) -> String {
+        binaryToText(typeName, &hex(raw)).unwrap()
+    }
+
+    // The wire values are taken from Postgres' send functions, which always send is_cidr as false.
+    #[test]
+    fn prints_the_netmask_of_inet_only_if_it_is_not_a_single_host() {
+        assert_eq!(text("inet", "022000040a000001"), "10.0.0.1");
+        assert_eq!(text("inet", "021000040a010000"), "10.1.0.0/16");
+        assert_eq!(text("inet", "0380001020010db8000000000000000000000001"), "2001:db8::1");
+    }
+
+    #[test]
+    fn always_prints_the_netmask_of_cidr() {
+        assert_eq!(text("cidr", "022000040a000001"), "10.0.0.1/32");
+        assert_eq!(text("cidr", "0320001020010db8000000000000000000000000"), "2001:db8::/32");
+    }
+
+    #[test]
+    fn reads_bit_strings_of_any_length() {
+        assert_eq!(text("varbit", "0000000aacc0"), "1010110011");
+        assert_eq!(text("bit", "00000003a0"), "101");
+        assert_eq!(text("varbit", "00000000"), "");
+    }
+
+    #[test]
+    fn reads_hstore_with_null_values() {
+        assert_eq!(text("hstore", "00000002000000016100000001310000000162ffffffff"), r#""a"=>"1", "b"=>NULL"#);
+        assert_eq!(text("hstore", "000000010000000122000000015c"), r#""\""=>"\\""#);
+        assert_eq!(text("hstore", "00000000"), "");
+    }
+
+    #[test]
+    fn reads_geometric_types() {
+        assert_eq!(text("point", "3ff80000000000004000000000000000"), "(1.5,2)");
+        assert_eq!(text("circle", "000000000000000000000000000000003ff0000000000000"), "<(0,0),1>");
+    }
+
+    #[test]
+    fn rejects_truncated_values() {
+        let truncated = [
+            ("inet", "022000040a00"),
+            ("inet", "0220"),
+            ("inet", "021000100a010000"),
+            ("cidr", "0320001020010db8"),
+            ("varbit", "0000000aac"),
+            ("varbit", "0000"),
+            ("bit", "ffffffff"),
+            ("hstore", "000000020000000161000000013100"),
+            ("hstore", "000000010000000561"),
+            ("hstore", "0000"),
+            ("point", "3ff8000000000000"),
+            ("ltree", ""),
+        ];
+
+        for &(typeName, raw) in truncated.iter() {
+            assert!(binaryToText(typeName, &hex(raw)).is_err(), "{} {}", typeName, raw);
+        }
+    }
+
+    #[test]
+    fn reports_unsupported_types() {
+        assert!(!isSupported("tsvector"));
+        assert!(binaryToText("tsvector", &[]).is_err());
+    }
+}
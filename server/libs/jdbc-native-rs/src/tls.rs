@@ -0,0 +1,204 @@
+use openssl::ssl::{SslConnectorBuilder, SslMethod, SSL_VERIFY_NONE};
+use openssl::x509::X509_FILETYPE_PEM;
+use postgres::TlsMode;
+use postgres::tls::openssl::OpenSsl;
+
+use driver::{DriverError, Result};
+use url_params;
+
+/// Mirrors the libpq `sslmode` connection parameter.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SslMode {
+    Disable,
+    Prefer,
+    Require,
+    VerifyCa,
+    VerifyFull,
+}
+
+/// TLS settings extracted from a connection URL. The ssl parameters are stripped from the URL.
+#[derive(Debug)]
+pub struct TlsConfig {
+    pub url: String,
+    pub mode: SslMode,
+    root_cert: Option<String>,
+    client_cert: Option<String>,
+    client_key: Option<String>,
+}
+
+impl SslMode {
+    fn parse(s: &str) -> Result<SslMode> {
+        match s {
+            "disable" => Ok(SslMode::Disable),
+            "prefer" => Ok(SslMode::Prefer),
+            "require" => Ok(SslMode::Require),
+            "verify-ca" => Ok(SslMode::VerifyCa),
+            "verify-full" => Ok(SslMode::VerifyFull),
+            x => Err(DriverError::GenericError(format!("Invalid sslmode: {}", x))),
+        }
+    }
+}
+
+impl TlsConfig {
+    /// Parses `sslmode`, `sslrootcert`, `sslcert` and `sslkey` from the query string of the URL.
+    /// Without an explicit `sslmode`, TLS stays disabled, which matches the previous behaviour of the driver.
+    pub fn from_url(url: &str) -> Result<TlsConfig> {
+        let (url, parameters) = url_params::takeParameters(url, &["sslmode", "sslrootcert", "sslcert", "sslkey"])?;
+        let mut config = TlsConfig {
+            url: url,
+            mode: SslMode::Disable,
+            root_cert: None,
+            client_cert: None,
+            client_key: None,
+        };
+
+        for (key, value) in parameters {
+            match key.as_str() {
+                "sslmode" => config.mode = SslMode::parse(&value)?,
+                "sslrootcert" => config.root_cert = Some(value),
+                "sslcert" => config.client_cert = Some(value),
+                _ => config.client_key = Some(value),
+            }
+        }
+
+        Ok(config)
+    }
+
+    /// Builds the handshake for the configured mode. Returns None if TLS is disabled.
+    pub fn handshake(&self) -> Result<Option<OpenSsl>> {
+        if self.mode == SslMode::Disable {
+            return Ok(None);
+        }
+
+        let mut builder = SslConnectorBuilder::new(SslMethod::tls())?;
+        match self.root_cert {
+            Some(ref path) => builder.set_ca_file(path)?,
+            None if self.verifies_chain() => {
+                return Err(DriverError::GenericError(format!(
+                    "sslmode {:?} requires sslrootcert to be set",
+                    self.mode
+                )))
+            }
+            None => (),
+        };
+
+        match (&self.client_cert, &self.client_key) {
+            (&Some(ref cert), &Some(ref key)) => {
+                builder.set_certificate_file(cert, X509_FILETYPE_PEM)?;
+                builder.set_private_key_file(key, X509_FILETYPE_PEM)?;
+                builder.check_private_key()?;
+            }
+            (&None, &None) => (),
+            _ => return Err(DriverError::GenericError(String::from("sslcert and sslkey must be set together"))),
+        };
+
+        if !self.verifies_chain() {
+            builder.set_verify(SSL_VERIFY_NONE);
+        }
+
+        let mut handshake = OpenSsl::from(builder.build());
+        handshake.danger_disable_hostname_verification(self.mode != SslMode::VerifyFull);
+
+        Ok(Some(handshake))
+    }
+
+    pub fn tls_mode<'a>(&self, handshake: &'a Option<OpenSsl>) -> TlsMode<'a> {
+        match (self.mode, handshake) {
+            (_, &None) => TlsMode::None,
+            (SslMode::Prefer, &Some(ref h)) => TlsMode::Prefer(h),
+            (_, &Some(ref h)) => TlsMode::Require(h),
+        }
+    }
+
+    /// Like libpq, `require` verifies the server certificate against the root certificate if one is given.
+    fn verifies_chain(&self) -> bool {
+        match self.mode {
+            SslMode::VerifyCa | SslMode::VerifyFull => true,
+            SslMode::Require => self.root_cert.is_some(),
+            _ => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(query: &str) -> Result<TlsConfig> {
+        TlsConfig::from_url(&format!("postgres://u:p@localhost:5432/db?{}", query))
+    }
+
+    #[test]
+    fn parses_every_sslmode() {
+        let modes = [
+            ("disable", SslMode::Disable),
+            ("prefer", SslMode::Prefer),
+            ("require", SslMode::Require),
+            ("verify-ca", SslMode::VerifyCa),
+            ("verify-full", SslMode::VerifyFull),
+        ];
+
+        for &(name, mode) in modes.iter() {
+            let config = config(&format!("sslmode={}", name)).unwrap();
+            assert_eq!(config.mode, mode);
+            assert_eq!(config.url, "postgres://u:p@localhost:5432/db");
+        }
+    }
+
+    #[test]
+    fn defaults_to_disable() {
+        let config = TlsConfig::from_url("postgres://u:p@localhost:5432/db").unwrap();
+
+        assert_eq!(config.mode, SslMode::Disable);
+        assert_eq!(config.root_cert, None);
+    }
+
+    #[test]
+    fn rejects_invalid_modes() {
+        for mode in &["", "required", "VERIFY-FULL", "allow-ish"] {
+            assert!(config(&format!("sslmode={}", mode)).is_err(), "sslmode={} was accepted", mode);
+        }
+    }
+
+    #[test]
+    fn decodes_certificate_paths() {
+        let config = config("sslmode=verify-full&sslrootcert=%2Fcerts%2Froot.crt&sslcert=%2Fcerts%2Fmy+client.crt&sslkey=%2Fcerts%2Fclient.key&search_path=x").unwrap();
+
+        assert_eq!(config.url, "postgres://u:p@localhost:5432/db?search_path=x");
+        assert_eq!(config.root_cert, Some(String::from("/certs/root.crt")));
+        assert_eq!(config.client_cert, Some(String::from("/certs/my client.crt")));
+        assert_eq!(config.client_key, Some(String::from("/certs/client.key")));
+    }
+
+    #[test]
+    fn rejects_malformed_certificate_paths() {
+        assert!(config("sslmode=require&sslrootcert=%2").is_err());
+        assert!(config("sslmode=require&sslkey=%zz").is_err());
+    }
+
+    #[test]
+    fn verifies_the_chain_like_libpq() {
+        assert!(!config("sslmode=prefer").unwrap().verifies_chain());
+        assert!(!config("sslmode=require").unwrap().verifies_chain());
+        assert!(config("sslmode=require&sslrootcert=root.crt").unwrap().verifies_chain());
+        assert!(config("sslmode=verify-ca").unwrap().verifies_chain());
+        assert!(config("sslmode=verify-full").unwrap().verifies_chain());
+    }
+
+    #[test]
+    fn disable_needs_no_handshake() {
+        assert!(config("sslmode=disable").unwrap().handshake().unwrap().is_none());
+    }
+
+    #[test]
+    fn verifying_modes_require_a_root_certificate() {
+        assert!(config("sslmode=verify-ca").unwrap().handshake().is_err());
+        assert!(config("sslmode=verify-full").unwrap().handshake().is_err());
+    }
+
+    #[test]
+    fn client_certificate_and_key_go_together() {
+        assert!(config("sslmode=require&sslcert=client.crt").unwrap().handshake().is_err());
+        assert!(config("sslmode=require&sslkey=client.key").unwrap().handshake().is_err());
+    }
+}
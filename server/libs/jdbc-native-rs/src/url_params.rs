@@ -0,0 +1,112 @@
+use driver::{DriverError, Result};
+
+/// Removes the given driver-level parameters from the query string of a connection URL, returning the remaining URL
+/// and the decoded values. The postgres crate would otherwise forward them to the server as runtime parameters.
+pub fn takeParameters(url: &str, keys: &[&str]) -> Result<(String, Vec<(String, String)>)> {
+    let (base, query) = match url.find('?') {
+        Some(pos) => (&url[..pos], &url[pos + 1..]),
+        None => return Ok((String::from(url), Vec::new())),
+    };
+
+    let mut taken = Vec::new();
+    let mut remaining = Vec::new();
+    for pair in query.split('&').filter(|p| !p.is_empty()) {
+        let (key, value) = match pair.find('=') {
+            Some(pos) => (&pair[..pos], &pair[pos + 1..]),
+            None => (pair, ""),
+        };
+
+        if keys.contains(&key) {
+            taken.push((String::from(key), percent_decode(value)?));
+        } else {
+            remaining.push(pair);
+        }
+    }
+
+    let url = if remaining.is_empty() {
+        String::from(base)
+    } else {
+        format!("{}?{}", base, remaining.join("&"))
+    };
+
+    Ok((url, taken))
+}
+
+fn percent_decode(s: &str) -> Result<String> {
+    let bytes = s.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                // from_str_radix would accept a sign, so the digits are checked first.
+                let hex = bytes.get(i + 1..i + 3).filter(|h| h.iter().all(|b| b.is_ascii_hexdigit()));
+                match hex.and_then(|h| u8::from_str_radix(::std::str::from_utf8(h).ok()?, 16).ok()) {
+                    Some(b) => decoded.push(b),
+                    None => return Err(DriverError::GenericError(format!("Invalid percent encoding in: {}", s))),
+                }
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+
+    String::from_utf8(decoded).map_err(|e| DriverError::GenericError(e.to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn takes_only_the_given_parameters() {
+        let (url, taken) = takeParameters("postgres://u@host/db?sslmode=require&search_path=x&sslkey=k", &["sslmode", "sslkey"]).unwrap();
+
+        assert_eq!(url, "postgres://u@host/db?search_path=x");
+        assert_eq!(taken, vec![(String::from("sslmode"), String::from("require")), (String::from("sslkey"), String::from("k"))]);
+    }
+
+    #[test]
+    fn drops_the_query_string_if_nothing_remains() {
+        let (url, taken) = takeParameters("postgres://u@host/db?sslmode=disable", &["sslmode"]).unwrap();
+
+        assert_eq!(url, "postgres://u@host/db");
+        assert_eq!(taken.len(), 1);
+    }
+
+    #[test]
+    fn leaves_urls_without_query_string_alone() {
+        let (url, taken) = takeParameters("postgres://u@host/db", &["sslmode"]).unwrap();
+
+        assert_eq!(url, "postgres://u@host/db");
+        assert!(taken.is_empty());
+    }
+
+    #[test]
+    fn decodes_percent_encoded_values() {
+        assert_eq!(percent_decode("%2Fetc%2Fssl%2Froot.crt").unwrap(), "/etc/ssl/root.crt");
+        assert_eq!(percent_decode("C%3A%5Ccerts%5Cmy+cert.pem").unwrap(), "C:\\certs\\my cert.pem");
+        assert_eq!(percent_decode("%c3%a9").unwrap(), "é");
+        assert_eq!(percent_decode("plain").unwrap(), "plain");
+    }
+
+    #[test]
+    fn rejects_malformed_escapes() {
+        for value in &["%", "%4", "abc%", "%zz", "%+1", "%-1", "%4g"] {
+            assert!(percent_decode(value).is_err(), "{} was decoded", value);
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_utf8() {
+        assert!(percent_decode("%ff").is_err());
+    }
+}
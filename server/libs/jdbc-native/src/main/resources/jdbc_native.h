@@ -1,36 +1,90 @@
+#include <stdbool.h>
 #include <stdint.h>
 #include <stdlib.h>
 
-typedef struct PsqlConnection PsqlConnection;
-typedef struct PsqlPreparedStatement PsqlPreparedStatement;
+typedef int64_t Handle;
 
-typedef struct PointerAndError {
-  const char *error;
-  const char *pointer;
-} PointerAndError;
+typedef struct HandleAndError {
+  uint8_t *error;
+  uintptr_t error_len;
+  Handle handle;
+} HandleAndError;
 
-PsqlConnection *newConnection(const char *url);
+typedef struct ProtocolBuffer {
+  uint8_t *error;
+  uintptr_t error_len;
+  uint8_t *data;
+  uintptr_t data_len;
+} ProtocolBuffer;
 
-PointerAndError *prepareStatement(const PsqlConnection *conn, const char *query);
+HandleAndError *newConnection(const char *url);
 
-const char *closeConnection(PsqlConnection *conn);
+HandleAndError *prepareStatement(Handle conn, const char *query);
 
-const char *closeStatement(PsqlPreparedStatement *stmt);
+ProtocolBuffer *closeConnection(Handle conn);
 
-const char *commitTransaction(PsqlConnection *conn);
+ProtocolBuffer *closeStatement(Handle stmt);
 
-const char *executePreparedstatement(const PsqlPreparedStatement *stmt, const char *params);
+ProtocolBuffer *executeTransaction(Handle conn, const char *statements, int32_t maxAttempts, int64_t backoffMs);
 
-const char *queryPreparedstatement(const PsqlPreparedStatement *stmt, const char *params);
+ProtocolBuffer *commitTransaction(Handle conn);
 
-const char *rollbackTransaction(PsqlConnection *conn);
+ProtocolBuffer *executePreparedstatement(Handle stmt, const char *params);
 
-const char *sqlExecute(const PsqlConnection *conn, const char *query, const char *params);
+ProtocolBuffer *executePreparedstatementWith(Handle stmt, const char *params, int32_t errorPolicy);
 
-const char *sqlQuery(const PsqlConnection *conn, const char *query, const char *params);
+ProtocolBuffer *queryPreparedstatement(Handle stmt, const char *params);
 
-const char *startTransaction(PsqlConnection *conn);
+ProtocolBuffer *queryPreparedstatementBinary(Handle stmt, const char *params);
 
-void destroy(PointerAndError *pointerAndError);
+ProtocolBuffer *rollbackTransaction(Handle conn);
 
-void destroy_string(const char *s);
\ No newline at end of file
+ProtocolBuffer *sqlExecute(Handle conn, const char *query, const char *params);
+
+ProtocolBuffer *sqlQuery(Handle conn, const char *query, const char *params);
+
+ProtocolBuffer *sqlQueryBinary(Handle conn, const char *query, const char *params);
+
+ProtocolBuffer *startTransaction(Handle conn);
+
+ProtocolBuffer *startTransactionWith(Handle conn, int32_t isolationLevel, bool readOnly, bool deferrable);
+
+ProtocolBuffer *setTransactionIsolation(Handle conn, int32_t isolationLevel);
+
+ProtocolBuffer *setReadOnly(Handle conn, bool readOnly);
+
+HandleAndError *newPool(const char *url, int32_t minSize, int32_t maxSize, int64_t idleTimeoutMs);
+
+HandleAndError *checkoutConnection(Handle pool);
+
+ProtocolBuffer *checkinConnection(Handle pool, Handle conn);
+
+ProtocolBuffer *poolStats(Handle pool);
+
+ProtocolBuffer *closePool(Handle pool);
+
+ProtocolBuffer *createSavepoint(Handle conn, const char *name);
+
+ProtocolBuffer *releaseSavepoint(Handle conn, const char *name);
+
+ProtocolBuffer *rollbackToSavepoint(Handle conn, const char *name);
+
+HandleAndError *openCursor(Handle conn, const char *query, const char *params);
+
+ProtocolBuffer *fetchCursor(Handle conn, Handle cursor, int32_t count);
+
+ProtocolBuffer *fetchCursorBinary(Handle conn, Handle cursor, int32_t count);
+
+ProtocolBuffer *closeCursor(Handle conn, Handle cursor);
+
+ProtocolBuffer *copyIn(Handle conn, const char *schema, const char *table, const char *columns, const char *format, const char *data);
+
+HandleAndError *openExport(Handle conn, const char *query, const char *format, int32_t chunkSize);
+
+ProtocolBuffer *fetchExport(Handle export);
+
+ProtocolBuffer *closeExport(Handle export);
+
+void destroy(HandleAndError *handleAndError);
+
+void destroy_buffer(ProtocolBuffer *buffer);
\ No newline at end of file
@@ -0,0 +1,88 @@
+package com.prisma.native_jdbc
+
+import java.nio.ByteBuffer
+import java.nio.charset.StandardCharsets
+import java.util.Base64
+
+import play.api.libs.json._
+
+// Decodes results in the binary format (see columnar.rs) into the representation the JSON format is parsed into,
+// so that both formats are read by the same result set implementation.
+object BinaryResultSet {
+  val FormatVersion = 1
+  val NullLength    = -1
+
+  // Values of these discriminators are transmitted as JSON text.
+  val jsonEncoded = Set("DateTime", "Json", "Array", "Composite", "Range", "Interval")
+
+  // Values of these discriminators have a fixed width. Any other length means the result can't be read safely.
+  val fixedWidths = Map("Boolean" -> 1, "Int" -> 4, "Long" -> 8, "Double" -> 8)
+
+  def decode(buffer: ByteBuffer): RustResultSet = {
+    val version = buffer.get()
+    if (version != FormatVersion) {
+      sys.error(s"Unsupported binary result format version: $version")
+    }
+
+    val columnCount = buffer.getInt
+    val rowCount    = buffer.getInt
+    val columns = Vector.fill(columnCount) {
+      val name                 = readString(buffer)
+      val discriminator        = readString(buffer)
+      val elementDiscriminator = Some(readString(buffer)).filter(_.nonEmpty)
+      val typeName             = Some(readString(buffer)).filter(_.nonEmpty)
+
+      ResultColumn(name, discriminator, elementDiscriminator, typeName)
+    }
+
+    val rows = Array.ofDim[JsValue](rowCount, columnCount)
+    for (col <- 0 until columnCount; row <- 0 until rowCount) {
+      rows(row)(col) = readValue(buffer, columns(col).discriminator)
+    }
+
+    RustResultSet(columns, rows.map(row => JsArray(row.toVector)).toVector)
+  }
+
+  private def readString(buffer: ByteBuffer): String = {
+    val bytes = new Array[Byte](buffer.getInt)
+    buffer.get(bytes)
+    new String(bytes, StandardCharsets.UTF_8)
+  }
+
+  private def readValue(buffer: ByteBuffer, discriminator: String): JsValue = {
+    val length = buffer.getInt
+    if (length == NullLength) {
+      return JsNull
+    }
+
+    if (length < 0 || length > buffer.remaining) {
+      sys.error(s"Invalid length of a $discriminator value: $length bytes, ${buffer.remaining} bytes left")
+    }
+
+    fixedWidths.get(discriminator).filter(_ != length).foreach { width =>
+      sys.error(s"Invalid length of a $discriminator value: $length bytes instead of $width")
+    }
+
+    discriminator match {
+      case "Boolean" => JsBoolean(buffer.get() != 0)
+      case "Int"     => JsNumber(buffer.getInt)
+      case "Long"    => JsNumber(buffer.getLong)
+      case "Double"  => doubleToJson(buffer.getDouble)
+      case _ =>
+        val bytes = new Array[Byte](length)
+        buffer.get(bytes)
+
+        discriminator match {
+          case "Bytes"                      => JsString(Base64.getEncoder.encodeToString(bytes))
+          case "VOID"                       => JsNull
+          case d if jsonEncoded.contains(d) => Json.parse(bytes)
+          case _                            => JsString(new String(bytes, StandardCharsets.UTF_8))
+        }
+    }
+  }
+
+  // Same representation of NaN and infinity as in the JSON format.
+  private def doubleToJson(value: Double): JsValue = {
+    if (value.isNaN || value.isInfinite) JsString(value.toString) else JsNumber(value)
+  }
+}
@@ -1,33 +1,63 @@
 package com.prisma.native_jdbc
 
-import java.sql.{Connection, PreparedStatement, Savepoint, Statement}
+import java.sql.{Connection, PreparedStatement, SQLException, Savepoint, Statement}
 import java.util.Properties
 import java.util.concurrent.Executor
 
 import org.slf4j.LoggerFactory
 
-class CustomJdbcConnection(url: String, outerBinding: RustBinding) extends Connection {
+// Unnamed savepoints are identified by an id and get a generated name, like in the Postgres JDBC driver.
+case class RustSavepoint(id: Option[Int], name: String) extends Savepoint {
+  override def getSavepointId   = id.getOrElse(throw new SQLException("Cannot retrieve the id of a named savepoint."))
+  override def getSavepointName = if (id.isDefined) throw new SQLException("Cannot retrieve the name of an unnamed savepoint.") else name
+}
+
+// With a pool, the connection is checked out of the pool instead of being opened for the url.
+class CustomJdbcConnection(url: String,
+                           outerBinding: RustBinding,
+                           binaryResults: Boolean = false,
+                           batchErrorPolicy: Int = BatchErrorPolicy.Continue,
+                           pool: Option[RustConnectionPool] = None)
+    extends Connection {
   var closed = false
   val logger = LoggerFactory.getLogger("prisma")
-  val bindingAndConnection = new BindingAndConnection {
-    override val binding: RustBinding = outerBinding
-    override val connection: binding.Conn = {
-      val conn = binding.newConnection(url)
-      logger.trace(s"New connection with hash ${conn.hashCode()}")
-      conn
-    }
+  val bindingAndConnection: BindingAndConnection = pool match {
+    case Some(p) =>
+      val pooled = p.checkout()
+      logger.trace(s"Checked out connection with hash ${pooled.connection.hashCode()}")
+      pooled
+
+    case None =>
+      new BindingAndConnection {
+        override val binding: RustBinding = outerBinding
+        override val connection: binding.Conn = {
+          val conn = binding.newConnection(url)
+          logger.trace(s"New connection with hash ${conn.hashCode()}")
+          conn
+        }
+      }
   }
 
   import bindingAndConnection._
-  var autoCommit = true
+  var autoCommit           = true
+  var savepointCounter     = 0
+  var transactionIsolation = Connection.TRANSACTION_READ_COMMITTED
+  var readOnly             = false
 
   override def prepareStatement(sql: String, autoGeneratedKeys: Int) = {
     val returnGenKeys = autoGeneratedKeys != Statement.RETURN_GENERATED_KEYS
-    new CustomPreparedStatement(sql, bindingAndConnection, returnGenKeys)
+    val stmt          = new CustomPreparedStatement(sql, bindingAndConnection, returnGenKeys)
+    stmt.binaryResults = binaryResults
+    stmt.batchErrorPolicy = batchErrorPolicy
+    stmt.inTransaction = () => !autoCommit
+    stmt
   }
 
   override def prepareStatement(sql: String): PreparedStatement = {
     val stmt = new CustomPreparedStatement(sql, bindingAndConnection, returnAutoGeneratedKeys = false)
+    stmt.binaryResults = binaryResults
+    stmt.batchErrorPolicy = batchErrorPolicy
+    stmt.inTransaction = () => !autoCommit
     logger.trace(s"New prepared statement with hash ${stmt.hashCode()}")
     stmt
   }
@@ -88,7 +118,10 @@ class CustomJdbcConnection(url: String, outerBinding: RustBinding) extends Conne
 
   override def getMetaData = ???
 
-  override def setReadOnly(readOnly: Boolean) = ???
+  override def setReadOnly(readOnly: Boolean) = {
+    binding.setReadOnly(connection, readOnly)
+    this.readOnly = readOnly
+  }
 
   override def prepareCall(sql: String) = ???
 
@@ -96,27 +129,44 @@ class CustomJdbcConnection(url: String, outerBinding: RustBinding) extends Conne
 
   override def prepareCall(sql: String, resultSetType: Int, resultSetConcurrency: Int, resultSetHoldability: Int) = ???
 
-  override def setTransactionIsolation(level: Int) = ???
+  override def setTransactionIsolation(level: Int) = {
+    binding.setTransactionIsolation(connection, level)
+    transactionIsolation = level
+  }
 
   override def getWarnings = ???
 
-  override def releaseSavepoint(savepoint: Savepoint) = ???
+  override def releaseSavepoint(savepoint: Savepoint) = binding.releaseSavepoint(connection, savepointName(savepoint))
 
   override def nativeSQL(sql: String) = ???
 
-  override def isReadOnly = ???
+  override def isReadOnly = readOnly
+
+  override def createArrayOf(typeName: String, elements: Array[AnyRef]) = RustArray(RustArray.discriminatorFor(typeName), elements)
+
+  override def setSavepoint() = {
+    savepointCounter += 1
+    val savepoint = RustSavepoint(Some(savepointCounter), s"prisma_savepoint_$savepointCounter")
 
-  override def createArrayOf(typeName: String, elements: Array[AnyRef]) = ???
+    binding.createSavepoint(connection, savepoint.name)
+    savepoint
+  }
 
-  override def setSavepoint() = ???
+  override def setSavepoint(name: String) = {
+    binding.createSavepoint(connection, name)
+    RustSavepoint(None, name)
+  }
 
-  override def setSavepoint(name: String) = ???
+  private def savepointName(savepoint: Savepoint) = savepoint match {
+    case s: RustSavepoint => s.name
+    case s                => s.getSavepointName
+  }
 
   override def close() = {
     logger.trace(s"Closing connection with hash ${this.hashCode()}")
     this.synchronized {
       if (!closed) {
-        binding.closeConnection(connection)
+        bindingAndConnection.release()
         closed = true
       } else {
         logger.trace(s"Called close on already closed connection with hash ${this.hashCode()}")
@@ -131,7 +181,7 @@ class CustomJdbcConnection(url: String, outerBinding: RustBinding) extends Conne
 
   }
 
-  override def rollback(savepoint: Savepoint) = ???
+  override def rollback(savepoint: Savepoint) = binding.rollbackToSavepoint(connection, savepointName(savepoint))
 
   override def setNetworkTimeout(executor: Executor, milliseconds: Int) = ???
 
@@ -149,7 +199,7 @@ class CustomJdbcConnection(url: String, outerBinding: RustBinding) extends Conne
 
   override def isClosed = ???
 
-  override def getTransactionIsolation = ???
+  override def getTransactionIsolation = transactionIsolation
 
   override def createStruct(typeName: String, attributes: Array[AnyRef]) = ???
 
@@ -1,16 +1,37 @@
 package com.prisma.native_jdbc
 
-import java.net.URLDecoder
-import java.sql.{Driver, DriverManager}
+import java.net.{URLDecoder, URLEncoder}
+import java.sql.{Driver, DriverAction, DriverManager}
 import java.util.Properties
 
 import com.prisma.native_jdbc.graalvm.RustBindingGraal
 import com.prisma.native_jdbc.jna.RustBindingJna
 
+import scala.collection.mutable
+import scala.util.{Failure, Success, Try}
+
 object CustomJdbcDriver {
   lazy val jna   = driverWithBinding(RustBindingJna)
   lazy val graal = driverWithBinding(RustBindingGraal.initialize())
 
+  // Passed on to the native driver. The ssl properties are interpreted like libpq does,
+  // textFallback=true returns columns of common unhandled types like inet or hstore as text instead of failing the query.
+  val nativeProperties = Vector("sslmode", "sslrootcert", "sslcert", "sslkey", "textFallback")
+
+  // binaryResults=true makes statements of the connection transfer their rows in the binary format instead of JSON.
+  val binaryResultsProperty = "binaryResults"
+
+  // batchErrorPolicy=continue|stop|rollback decides what executeBatch does after a row failed, see BatchErrorPolicy.
+  val batchErrorPolicyProperty = "batchErrorPolicy"
+
+  // nativePool=true checks connections out of a native pool per url and pool settings instead of opening a new one for
+  // every connect, closing the connection returns it to the pool. The pool is sized with the nativePool* properties below.
+  // The pools are closed when the driver is deregistered or the JVM shuts down.
+  val nativePoolProperty              = "nativePool"
+  val nativePoolMinSizeProperty       = "nativePoolMinSize"
+  val nativePoolMaxSizeProperty       = "nativePoolMaxSize"
+  val nativePoolIdleTimeoutMsProperty = "nativePoolIdleTimeoutMs"
+
   def driverWithBinding(binding: RustBinding): CustomJdbcDriver = {
     val driver = CustomJdbcDriver(binding)
     driver.register()
@@ -20,6 +41,8 @@ object CustomJdbcDriver {
 
 case class PgUrl(var dbName: String, var port: String, var host: String)
 
+case class PoolSettings(url: String, minSize: Int, maxSize: Int, idleTimeoutMs: Long)
+
 case class CustomJdbcDriver(binding: RustBinding) extends Driver {
   override def getParentLogger                                = ???
   override def getMajorVersion                                = 1
@@ -28,6 +51,8 @@ case class CustomJdbcDriver(binding: RustBinding) extends Driver {
   override def acceptsURL(url: String)                        = true
   override def getPropertyInfo(url: String, info: Properties) = Array.empty
 
+  private val pools = mutable.Map.empty[PoolSettings, RustConnectionPool]
+
   override def connect(url: String, info: Properties) = {
     val props  = parseURL(url, new Properties())
     val dbName = props.getProperty("PGDBNAME")
@@ -37,11 +62,55 @@ case class CustomJdbcDriver(binding: RustBinding) extends Driver {
     val user   = info.getProperty("user")
     val pass   = info.getProperty("password")
 
-    new CustomJdbcConnection(s"postgres://$user:$pass@$host:$port/$dbName?search_path=$schema", binding)
+    val nativeParams = CustomJdbcDriver.nativeProperties.flatMap { key =>
+      Option(props.getProperty(key)).orElse(Option(info.getProperty(key))).map(value => s"&$key=${URLEncoder.encode(value, "UTF-8")}")
+    }
+
+    def property(key: String) = Option(props.getProperty(key)).orElse(Option(info.getProperty(key)))
+
+    val binaryResults    = property(CustomJdbcDriver.binaryResultsProperty).contains("true")
+    val batchErrorPolicy = property(CustomJdbcDriver.batchErrorPolicyProperty).map(BatchErrorPolicy.parse).getOrElse(BatchErrorPolicy.Continue)
+    val nativeUrl        = s"postgres://$user:$pass@$host:$port/$dbName?search_path=$schema${nativeParams.mkString}"
+
+    val pool = if (property(CustomJdbcDriver.nativePoolProperty).contains("true")) {
+      Some(poolFor(nativeUrl, property))
+    } else {
+      None
+    }
+
+    new CustomJdbcConnection(nativeUrl, binding, binaryResults, batchErrorPolicy, pool)
+  }
+
+  // Connects to the same url with different pool settings get a pool of their own.
+  private def poolFor(url: String, property: String => Option[String]): RustConnectionPool = pools.synchronized {
+    val settings = PoolSettings(
+      url,
+      property(CustomJdbcDriver.nativePoolMinSizeProperty).map(_.toInt).getOrElse(0),
+      property(CustomJdbcDriver.nativePoolMaxSizeProperty).map(_.toInt).getOrElse(10),
+      property(CustomJdbcDriver.nativePoolIdleTimeoutMsProperty).map(_.toLong).getOrElse(600000L)
+    )
+
+    pools.getOrElseUpdate(settings, new RustConnectionPool(binding, settings.url, settings.minSize, settings.maxSize, settings.idleTimeoutMs))
+  }
+
+  // Pools with connections that are still checked out stay open, see closePool. The first failure is thrown once all pools were tried.
+  def closePools(): Unit = pools.synchronized {
+    val failures = pools.toVector.flatMap {
+      case (settings, pool) =>
+        Try(pool.close()) match {
+          case Success(_) => pools.remove(settings); None
+          case Failure(e) => Some(e)
+        }
+    }
+
+    failures.headOption.foreach(throw _)
   }
 
   def register(): Unit = {
-    DriverManager.registerDriver(this)
+    DriverManager.registerDriver(this, new DriverAction {
+      override def deregister(): Unit = closePools()
+    })
+    sys.addShutdownHook(Try(closePools()))
   }
 
   // todo find better solution, this is copied from the postgres driver
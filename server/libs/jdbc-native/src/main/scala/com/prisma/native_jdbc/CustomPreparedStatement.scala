@@ -4,14 +4,17 @@ import java.io.{InputStream, Reader}
 import java.net.URL
 import java.sql
 import java.sql.{Blob, Clob, Date, NClob, PreparedStatement, Ref, ResultSet, RowId, SQLException, SQLXML, Time, Timestamp}
+import java.time.format.DateTimeFormatter
+import java.time.{LocalDate, LocalTime, OffsetDateTime, OffsetTime}
 import java.util.regex.Pattern
-import java.util.{Calendar, UUID}
+import java.util.{Base64, Calendar, UUID}
 
 import org.joda.time.DateTime
 import org.slf4j.LoggerFactory
 
 import scala.annotation.tailrec
-import play.api.libs.json.{JsArray, JsNull, JsValue, Json}
+import scala.util.Try
+import play.api.libs.json.{JsArray, JsNull, JsString, JsValue, Json}
 
 import scala.collection.mutable
 
@@ -19,14 +22,52 @@ object CustomPreparedStatement {
   type Params = mutable.HashMap[Int, JsValue]
 
   implicit val magicDateTimeFormat = Json.format[MagicDateTime]
-  case class MagicDateTime(year: Int, month: Int, day: Int, hour: Int, minute: Int, seconds: Int, millis: Int)
+  case class MagicDateTime(year: Int, month: Int, day: Int, hour: Int, minute: Int, seconds: Int, millis: Int, micros: Option[Int] = None)
+
+  def magicDateTime(x: Timestamp): MagicDateTime = {
+    val isoDate = new DateTime(x.getTime)
+    MagicDateTime(
+      isoDate.year().get(),
+      isoDate.monthOfYear().get(),
+      isoDate.dayOfMonth().get(),
+      isoDate.hourOfDay().get(),
+      isoDate.minuteOfHour().get(),
+      isoDate.secondOfMinute().get(),
+      isoDate.millisOfSecond().get(),
+      Some(x.getNanos / 1000)
+    )
+  }
 
   val logger = LoggerFactory.getLogger("prisma")
+
+  // JSON has no numbers for NaN and infinity, they are sent as the strings results represent them with.
+  def doubleToJson(x: Double): JsValue = if (x.isNaN || x.isInfinite) JsString(x.toString) else Json.toJson(x)
+  def floatToJson(x: Float): JsValue   = if (x.isNaN || x.isInfinite) JsString(x.toString) else Json.toJson(x)
+
+  private val cursorKeywords = Set("select", "values", "with", "table")
+
+  // Whether DECLARE accepts the query, judged by its first keyword after whitespace, comments and parentheses.
+  def isCursorQuery(sql: String): Boolean = {
+    @tailrec
+    def skip(i: Int): Int = {
+      if (i >= sql.length) i
+      else if (sql(i).isWhitespace || sql(i) == '(') skip(i + 1)
+      else if (sql.startsWith("--", i)) sql.indexOf('\n', i) match { case -1 => sql.length; case end => skip(end + 1) }
+      else if (sql.startsWith("/*", i)) sql.indexOf("*/", i + 2) match { case -1 => sql.length; case end => skip(end + 2) }
+      else i
+    }
+
+    val start = skip(0)
+    cursorKeywords.contains(sql.substring(start).takeWhile(_.isLetter).toLowerCase)
+  }
 }
 
 abstract class BindingAndConnection {
   val binding: RustBinding
   val connection: binding.Conn
+
+  // Closes the connection. Pooled connections are checked in instead, see RustConnectionPool.
+  def release(): RustCallResult = binding.closeConnection(connection)
 }
 
 class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAndConnection, returnAutoGeneratedKeys: Boolean) extends PreparedStatement {
@@ -44,6 +85,10 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
   val paramList                      = mutable.ArrayBuffer.empty[Params]
   var lastCallResult: RustCallResult = null
   var closed                         = false
+  var fetchSize                      = 0
+  var binaryResults                  = false // Rows are transferred in the binary format instead of JSON
+  var batchErrorPolicy               = BatchErrorPolicy.Continue
+  var inTransaction                  = () => false // Cursors are only used in a transaction, see executeQuery
 
   val returnsRows = rawSqlString.toLowerCase().startsWith("with ") || rawSqlString.toLowerCase().startsWith("select ") || rawSqlString
     .toLowerCase()
@@ -102,10 +147,19 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
     val params = renderParams(asArray = false)
     clearParams()
 
-    val result = binding.queryPreparedstatement(
-      stmt,
-      params
-    )
+    // Like pgjdbc, the whole result is read at once in autocommit mode, a cursor ends with its transaction.
+    if (fetchSize > 0 && inTransaction() && isCursorQuery(rawSqlString)) {
+      return executeQueryWithCursor(params)
+    }
+
+    val result = if (binaryResults) {
+      RustCallResult("RESULT_SET", Vector.empty, Some(binding.queryPreparedstatementBinary(stmt, params)), None, None)
+    } else {
+      binding.queryPreparedstatement(
+        stmt,
+        params
+      )
+    }
 
     if (!result.isResultSet) {
       throw new SQLException("No results were returned by the query.", "02000")
@@ -115,6 +169,33 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
     result.toResultSet
   }
 
+  // Rows are fetched in chunks of the fetch size, instead of materializing the whole result at once.
+  private def executeQueryWithCursor(params: String): ResultSet = {
+    val cursor    = binding.openCursor(connection, rawSqlString, params)
+    val chunkSize = fetchSize
+    val binary    = binaryResults
+    val chunks = new ResultChunks {
+      val fetchSize = chunkSize
+      def fetchNext() = {
+        if (binary) binding.fetchCursorBinary(connection, cursor, chunkSize) else binding.fetchCursor(connection, cursor, chunkSize).rows.get
+      }
+      def close() = binding.closeCursor(connection, cursor)
+    }
+
+    val firstChunk = Try { chunks.fetchNext() }.recover {
+      case e =>
+        Try { chunks.close() }
+        throw e
+    }.get
+
+    if (firstChunk.data.size < chunkSize) {
+      chunks.close()
+      JsonResultSet(firstChunk)
+    } else {
+      JsonResultSet(firstChunk, Some(chunks))
+    }
+  }
+
   override def executeUpdate() = {
     val params = renderParams(asArray = true)
     clearParams()
@@ -140,9 +221,10 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
     val params = renderParams(asArray = true)
     clearParams()
 
-    val result = binding.executePreparedstatement(
+    val result = binding.executePreparedstatementWith(
       stmt,
-      params
+      params,
+      batchErrorPolicy
     )
 
     if (!result.isCount) {
@@ -171,21 +253,30 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
 
   override def getGeneratedKeys: ResultSet = lastCallResult.toResultSet
 
-  override def setShort(parameterIndex: Int, x: Short) = ???
+  override def setShort(parameterIndex: Int, x: Short) = {
+    currentParams.put(parameterIndex, Json.obj("discriminator" -> "Int", "value" -> x))
+  }
 
   override def setObject(parameterIndex: Int, x: scala.Any, targetSqlType: Int) = ???
 
   override def setObject(parameterIndex: Int, x: scala.Any) = {
     x match {
-      case uuid: UUID => currentParams.put(parameterIndex, Json.obj("discriminator" -> "UUID", "value" -> uuid.toString))
-      case _          => sys.error(s"SetObject not implemented for $x")
+      case uuid: UUID    => currentParams.put(parameterIndex, Json.obj("discriminator" -> "UUID", "value" -> uuid.toString))
+      case json: JsValue => currentParams.put(parameterIndex, Json.obj("discriminator" -> "Json", "value" -> json))
+      case dt: OffsetDateTime =>
+        currentParams.put(parameterIndex, Json.obj("discriminator" -> "DateTimeTz", "value" -> dt.format(DateTimeFormatter.ISO_OFFSET_DATE_TIME)))
+      case date: LocalDate => currentParams.put(parameterIndex, Json.obj("discriminator" -> "Date", "value" -> date.toString))
+      case time: LocalTime => currentParams.put(parameterIndex, Json.obj("discriminator" -> "Time", "value" -> time.format(DateTimeFormatter.ISO_LOCAL_TIME)))
+      case time: OffsetTime =>
+        currentParams.put(parameterIndex, Json.obj("discriminator" -> "TimeTz", "value" -> time.format(DateTimeFormatter.ISO_OFFSET_TIME)))
+      case _             => sys.error(s"SetObject not implemented for $x")
     }
   }
 
   override def setObject(parameterIndex: Int, x: scala.Any, targetSqlType: Int, scaleOrLength: Int) = ???
 
   override def setDouble(parameterIndex: Int, x: Double) = {
-    currentParams.put(parameterIndex, Json.obj("discriminator" -> "Double", "value" -> x))
+    currentParams.put(parameterIndex, Json.obj("discriminator" -> "Double", "value" -> doubleToJson(x)))
   }
 
   override def setNClob(parameterIndex: Int, value: NClob) = ???
@@ -196,13 +287,39 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
 
   override def getParameterMetaData = ???
 
-  override def setTime(parameterIndex: Int, x: Time) = ???
+  override def setTime(parameterIndex: Int, x: Time) = {
+    if (x != null) {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Time", "value" -> x.toString))
+    } else {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Null", "value" -> JsNull))
+    }
+  }
 
   override def setTime(parameterIndex: Int, x: Time, cal: Calendar) = ???
 
   override def setUnicodeStream(parameterIndex: Int, x: InputStream, length: Int) = ???
 
-  override def setArray(parameterIndex: Int, x: sql.Array) = ???
+  // Arrays created with Connection.createArrayOf keep their element discriminator, others are mapped by their base type name.
+  override def setArray(parameterIndex: Int, x: sql.Array) = {
+    if (x != null) {
+      val elementDiscriminator = x match {
+        case array: RustArray => array.elementDiscriminator
+        case array            => RustArray.discriminatorFor(array.getBaseTypeName)
+      }
+
+      val elements = x.getArray match {
+        case elements: Array[_] => elements
+        case other              => throw new SQLException(s"Array elements must be an array, got $other")
+      }
+
+      currentParams.put(
+        parameterIndex,
+        Json.obj("discriminator" -> "Array", "elementDiscriminator" -> elementDiscriminator, "value" -> RustArray.toJson(elementDiscriminator, elements))
+      )
+    } else {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Null", "value" -> JsNull))
+    }
+  }
 
   override def setURL(parameterIndex: Int, x: URL) = ???
 
@@ -270,18 +387,7 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
 
   override def setTimestamp(parameterIndex: Int, x: Timestamp) = {
     if (x != null) {
-      val isoDate = new DateTime(x.getTime)
-      val date = MagicDateTime(
-        isoDate.year().get(),
-        isoDate.monthOfYear().get(),
-        isoDate.dayOfMonth().get(),
-        isoDate.hourOfDay().get(),
-        isoDate.minuteOfHour().get(),
-        isoDate.secondOfMinute().get(),
-        isoDate.millisOfSecond().get()
-      )
-
-      currentParams.put(parameterIndex, Json.obj("discriminator" -> "DateTime", "value" -> Json.toJson(date)))
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "DateTime", "value" -> Json.toJson(magicDateTime(x))))
     } else {
       currentParams.put(parameterIndex, Json.obj("discriminator" -> "Null", "value" -> JsNull))
     }
@@ -292,15 +398,35 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
     setTimestamp(parameterIndex, x)
   }
 
-  override def setBytes(parameterIndex: Int, x: Array[Byte]) = ???
+  override def setBytes(parameterIndex: Int, x: Array[Byte]) = {
+    if (x != null) {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Bytes", "value" -> Base64.getEncoder.encodeToString(x)))
+    } else {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Null", "value" -> JsNull))
+    }
+  }
 
-  override def setBigDecimal(parameterIndex: Int, x: java.math.BigDecimal) = ???
+  override def setBigDecimal(parameterIndex: Int, x: java.math.BigDecimal) = {
+    if (x != null) {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Decimal", "value" -> x.toPlainString))
+    } else {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Null", "value" -> JsNull))
+    }
+  }
 
-  override def setFloat(parameterIndex: Int, x: Float) = ???
+  override def setFloat(parameterIndex: Int, x: Float) = {
+    currentParams.put(parameterIndex, Json.obj("discriminator" -> "Double", "value" -> floatToJson(x)))
+  }
 
   override def setRowId(parameterIndex: Int, x: RowId) = ???
 
-  override def setDate(parameterIndex: Int, x: Date) = ???
+  override def setDate(parameterIndex: Int, x: Date) = {
+    if (x != null) {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Date", "value" -> x.toString))
+    } else {
+      currentParams.put(parameterIndex, Json.obj("discriminator" -> "Null", "value" -> JsNull))
+    }
+  }
 
   override def setDate(parameterIndex: Int, x: Date, cal: Calendar) = ???
 
@@ -320,7 +446,7 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
 
   override def setPoolable(poolable: Boolean) = ???
 
-  override def getFetchSize = ???
+  override def getFetchSize = fetchSize
 
   override def setQueryTimeout(seconds: Int) = ???
 
@@ -382,7 +508,13 @@ class CustomPreparedStatement(query: String, val bindingAndConnection: BindingAn
 
   override def closeOnCompletion() = ???
 
-  override def setFetchSize(rows: Int) = ???
+  override def setFetchSize(rows: Int) = {
+    if (rows < 0) {
+      throw new SQLException(s"Fetch size must be positive, got $rows")
+    }
+
+    fetchSize = rows
+  }
 
   override def clearWarnings() = ???
 
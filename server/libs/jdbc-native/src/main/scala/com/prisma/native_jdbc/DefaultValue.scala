@@ -5,6 +5,7 @@ sealed trait DefaultValue[T] {
 }
 object DefaultValues {
   object NullDefaultValue             extends DefaultValue[Null]    { def default = null  }
+  implicit object ShortDefaultValue   extends DefaultValue[Short]   { def default = 0     }
   implicit object IntDefaultValue     extends DefaultValue[Int]     { def default = 0     }
   implicit object LongDefaultValue    extends DefaultValue[Long]    { def default = 0l    }
   implicit object BooleanDefaultValue extends DefaultValue[Boolean] { def default = false }
@@ -1,22 +1,58 @@
 package com.prisma.native_jdbc
 
 import java.io.{InputStream, Reader}
-import java.sql.{Blob, Clob, Date, NClob, Ref, ResultSet, RowId, SQLXML, Time, Timestamp}
-import java.util.{Calendar, TimeZone}
+import java.sql.{Blob, Clob, Date, NClob, Ref, ResultSet, RowId, SQLException, SQLXML, Time, Timestamp}
+import java.time.{LocalDate, LocalTime, OffsetDateTime, OffsetTime, ZoneOffset}
+import java.util.{Base64, Calendar, TimeZone, UUID}
 import java.{lang, sql}
 
 import com.prisma.native_jdbc.CustomPreparedStatement._
 import org.joda.time.DateTime
 import play.api.libs.json._
 
-case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with DefaultReads {
+object JsonResultSet {
+  val PositiveInfinity = "infinity"
+  val NegativeInfinity = "-infinity"
+
+  // Same values as org.postgresql.PGStatement.DATE_POSITIVE_INFINITY and DATE_NEGATIVE_INFINITY.
+  val PositiveInfinityMillis = 9223372036825200000L
+  val NegativeInfinityMillis = -9223372036832400000L
+}
+
+// Further rows of a result set that is fetched in chunks through a cursor.
+trait ResultChunks {
+  def fetchSize: Int
+  def fetchNext(): RustResultSet
+  def close(): Unit
+}
+
+case class JsonResultSet(rustResultSet: RustResultSet, chunks: Option[ResultChunks] = None) extends ResultSet with DefaultReads {
   import DefaultValues._
+  import JsonResultSet._
   private var cursor: Int    = -1
   private var lastColWasNull = false
+  private var currentChunk   = rustResultSet
+  private var closed         = false
 
   override def next(): Boolean = {
     cursor += 1
-    cursor < rustResultSet.data.size
+    if (cursor >= currentChunk.data.size) {
+      fetchNextChunk()
+    }
+
+    cursor < currentChunk.data.size
+  }
+
+  // A chunk smaller than the fetch size is the last one, the cursor is closed as soon as it is reached.
+  private def fetchNextChunk(): Unit = chunks.filter(_ => !closed).foreach { c =>
+    if (currentChunk.data.size == c.fetchSize) {
+      currentChunk = c.fetchNext()
+      cursor = 0
+    }
+
+    if (currentChunk.data.size < c.fetchSize) {
+      close()
+    }
   }
 
   /**
@@ -33,7 +69,7 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
   override def getType                           = ???
   override def relative(rows: Int)               = ???
   override def getWarnings                       = ???
-  override def close()                           = ???
+  override def close()                           = if (!closed) { closed = true; chunks.foreach(_.close()) }
   override def moveToCurrentRow()                = ???
   override def setFetchSize(rows: Int)           = ???
   override def clearWarnings()                   = ???
@@ -45,7 +81,7 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
   override def first()                           = ???
   override def getCursorName                     = ???
   override def getHoldability                    = ???
-  override def getFetchSize                      = ???
+  override def getFetchSize                      = chunks.map(_.fetchSize).getOrElse(0)
   override def getConcurrency                    = ???
   override def setFetchDirection(direction: Int) = ???
   override def cancelRowUpdates()                = ???
@@ -64,10 +100,10 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
   /**
     * DATA RETRIEVAL METHODS
     */
-  override def getDouble(columnIndex: Int)              = readColumnAs[Double](columnIndex)
-  override def getDouble(columnLabel: String)           = readColumnAs[Double](columnLabel)
-  override def getString(columnIndex: Int)              = readColumnAs[String](columnIndex)
-  override def getString(columnLabel: String)           = readColumnAs[String](columnLabel)
+  override def getDouble(columnIndex: Int)              = readColumnAs[Double](columnIndex)(numericAsDouble, DoubleDefaultValue)
+  override def getDouble(columnLabel: String)           = readColumnAs[Double](columnLabel)(numericAsDouble, DoubleDefaultValue)
+  override def getString(columnIndex: Int)              = readColumnAs[String](columnIndex)(jsonAsString(columnIndex), nullDefault)
+  override def getString(columnLabel: String)           = getString(columnIndexOf(columnLabel))
   override def getBoolean(columnIndex: Int): Boolean    = readColumnAs[Boolean](columnIndex)
   override def getBoolean(columnLabel: String): Boolean = readColumnAs[Boolean](columnLabel)
   override def getInt(columnIndex: Int)                 = readColumnAs[Int](columnIndex)
@@ -101,38 +137,33 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
     getTimestamp(labelPos + 1)
   }
 
-  override def getTimestamp(columnIndex: Int) = {
-    val readValue = readColumnAs[MagicDateTime](columnIndex)
-    if (readValue == null) {
-      null
-    } else {
-      val dt = new DateTime(
-        readValue.year,
-        readValue.month,
-        readValue.day,
-        readValue.hour,
-        readValue.minute,
-        readValue.seconds,
-        readValue.millis
-      )
-
-      new Timestamp(dt.toInstant.getMillis)
-    }
-  }
+  override def getTimestamp(columnIndex: Int) = readColumnAs[Timestamp](columnIndex)(timestampReads, nullDefault)
 
   override def getObject(columnIndex: Int): java.lang.Object = {
     val column = rustResultSet.columns.lift(columnIndex - 1).getOrElse(sys.error(s"Column not found at $columnIndex"))
 
     column.discriminator match {
-      case "String"   => getString(columnIndex)
-      case "Int"      => new Integer(getInt(columnIndex))
-      case "Long"     => new java.lang.Long(getLong(columnIndex))
-      case "Double"   => new java.lang.Double(getDouble(columnIndex))
-      case "DateTime" => getTimestamp(columnIndex)
-      case "Boolean"  => new lang.Boolean(getBoolean(columnIndex))
-      case "Null"     => null
-      case "VOID"     => null
-      case "UUID"     => ??? //readColumnAs[UUID](columnIndex)
+      case "String"     => getString(columnIndex)
+      case "Int"        => new Integer(getInt(columnIndex))
+      case "Long"       => new java.lang.Long(getLong(columnIndex))
+      case "Double"     => new java.lang.Double(getDouble(columnIndex))
+      case "Decimal"    => getBigDecimal(columnIndex)
+      case "Json"       => getString(columnIndex)
+      case "Bytes"      => getBytes(columnIndex)
+      case "DateTime"   => getTimestamp(columnIndex)
+      case "DateTimeTz" => getTimestamp(columnIndex)
+      case "Date"       => getDate(columnIndex)
+      case "Time"       => getTime(columnIndex)
+      case "TimeTz"     => getTime(columnIndex)
+      case "Interval"   => getString(columnIndex)
+      case "Composite"  => getString(columnIndex)
+      case "Range"      => getString(columnIndex)
+      case "Unknown"    => getString(columnIndex)
+      case "Boolean"    => new lang.Boolean(getBoolean(columnIndex))
+      case "Null"       => null
+      case "VOID"       => null
+      case "Array"      => getArray(columnIndex)
+      case "UUID"       => ??? //readColumnAs[UUID](columnIndex)
     }
   }
 
@@ -140,6 +171,86 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
     ???
   }
 
+  // Decimals are transported as strings to preserve their precision, as are the float values NaN and (-)Infinity.
+  private val numericAsDouble: Reads[Double] = Reads {
+    case JsString(value) => JsSuccess(value.toDouble)
+    case value           => DoubleReads.reads(value)
+  }
+
+  // TIMESTAMP values are MagicDateTime objects, TIMESTAMPTZ values RFC 3339 strings. Infinity is mapped like the Postgres JDBC driver does.
+  // TIMESTAMPTZ strings are always in UTC, the TimeZone of the session doesn't apply. The instant is exact either way.
+  private val timestampReads: Reads[Timestamp] = Reads {
+    case JsString(PositiveInfinity) => JsSuccess(new Timestamp(PositiveInfinityMillis))
+    case JsString(NegativeInfinity) => JsSuccess(new Timestamp(NegativeInfinityMillis))
+    case JsString(value)            => JsSuccess(Timestamp.from(OffsetDateTime.parse(value).toInstant))
+    case value =>
+      value.validate[MagicDateTime].map { mdt =>
+        val dt        = new DateTime(mdt.year, mdt.month, mdt.day, mdt.hour, mdt.minute, mdt.seconds, mdt.millis)
+        val timestamp = new Timestamp(dt.toInstant.getMillis)
+        mdt.micros.foreach(micros => timestamp.setNanos(micros * 1000))
+        timestamp
+      }
+  }
+
+  private val dateReads: Reads[Date] = Reads {
+    case JsString(PositiveInfinity) => JsSuccess(new Date(PositiveInfinityMillis))
+    case JsString(NegativeInfinity) => JsSuccess(new Date(NegativeInfinityMillis))
+    case value                      => value.validate[String].map(v => Date.valueOf(LocalDate.parse(v)))
+  }
+
+  // TIMETZ values are normalized to UTC, as only UTC is handled.
+  private def timeReads(hasZone: Boolean): Reads[Time] = {
+    Reads { value =>
+      value.validate[String].map { v =>
+        if (hasZone) Time.valueOf(OffsetTime.parse(v).withOffsetSameInstant(ZoneOffset.UTC).toLocalTime) else Time.valueOf(LocalTime.parse(v))
+      }
+    }
+  }
+
+  // Json, Interval, Composite and Range columns are embedded in the result, string access returns their JSON text like the Postgres JDBC driver does for Json.
+  private val embeddedJson = Set("Json", "Interval", "Composite", "Range")
+
+  private def jsonAsString(columnIndex: Int): Reads[String] = {
+    val isJson = rustResultSet.columns.lift(columnIndex - 1).exists(c => embeddedJson.contains(c.discriminator))
+    Reads(value => if (isJson) JsSuccess(Json.stringify(value)) else StringReads.reads(value))
+  }
+
+  // Elements are converted like getObject converts columns of the element type. Json elements aren't descended into, as Json arrays are one-dimensional.
+  private def arrayReads(elementDiscriminator: String): Reads[RustArray] = {
+    def element(value: JsValue): AnyRef = (elementDiscriminator, value) match {
+      case (_, JsNull)                           => null
+      case ("Json", json)                        => Json.stringify(json)
+      case (_, JsArray(nested))                  => nested.map(element).toArray
+      case (d, json) if embeddedJson.contains(d) => Json.stringify(json)
+      case ("Int", v)                            => new Integer(v.as[Int])
+      case ("Long", v)                           => new java.lang.Long(v.as[Long])
+      case ("Double", v)                         => new java.lang.Double(v.as(numericAsDouble))
+      case ("Decimal", v)                        => v.as[java.math.BigDecimal]
+      case ("Boolean", v)                        => new lang.Boolean(v.as[Boolean])
+      case ("UUID", v)                           => UUID.fromString(v.as[String])
+      case ("Bytes", v)                          => Base64.getDecoder.decode(v.as[String])
+      case ("DateTime" | "DateTimeTz", v)        => v.as(timestampReads)
+      case ("Date", v)                           => v.as(dateReads)
+      case ("Time", v)                           => v.as(timeReads(hasZone = false))
+      case ("TimeTz", v)                         => v.as(timeReads(hasZone = true))
+      case (_, v)                                => v.as[String]
+    }
+
+    Reads {
+      case JsArray(elements) => JsSuccess(RustArray(elementDiscriminator, elements.map(element).toArray))
+      case value             => JsError(s"Expected an array, got $value")
+    }
+  }
+
+  private def columnIndexOf(columnLabel: String): Int = {
+    val labelPos = rustResultSet.columns.indexWhere(c => c.name == columnLabel)
+    if (labelPos <= -1) {
+      sys.error("Column label not found")
+    }
+
+    labelPos + 1
+  }
+
   private def readColumnAs[T](columnLabel: String)(implicit reads: Reads[T], default: DefaultValue[T]): T = {
     val labelPos = rustResultSet.columns.indexWhere(c => c.name == columnLabel)
     if (labelPos <= -1) {
@@ -150,7 +261,7 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
   }
 
   private def readColumnAs[T](index: Int)(implicit reads: Reads[T], default: DefaultValue[T]): T = {
-    val row         = rustResultSet.data(cursor)
+    val row         = currentChunk.data(cursor)
     val columnValue = row.value(index - 1)
 
     columnValue match {
@@ -167,9 +278,9 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
 
   override def getBigDecimal(columnLabel: String, scale: Int) = ???
 
-  override def getBigDecimal(columnIndex: Int) = ???
+  override def getBigDecimal(columnIndex: Int) = readColumnAs[java.math.BigDecimal](columnIndex)
 
-  override def getBigDecimal(columnLabel: String) = ???
+  override def getBigDecimal(columnLabel: String) = readColumnAs[java.math.BigDecimal](columnLabel)
 
   override def getClob(columnIndex: Int) = ???
 
@@ -199,9 +310,9 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
 
   override def getNCharacterStream(columnLabel: String) = ???
 
-  override def getDate(columnIndex: Int) = ???
+  override def getDate(columnIndex: Int) = readColumnAs[Date](columnIndex)(dateReads, nullDefault)
 
-  override def getDate(columnLabel: String) = ???
+  override def getDate(columnLabel: String) = getDate(columnIndexOf(columnLabel))
 
   override def getDate(columnIndex: Int, cal: Calendar) = ???
 
@@ -219,13 +330,21 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
 
   override def getUnicodeStream(columnLabel: String) = ???
 
-  override def getArray(columnIndex: Int) = ???
+  override def getArray(columnIndex: Int): sql.Array = {
+    val column               = rustResultSet.columns.lift(columnIndex - 1).getOrElse(sys.error(s"Column not found at $columnIndex"))
+    val elementDiscriminator = column.elementDiscriminator.getOrElse(throw new SQLException(s"Column ${column.name} is not an array"))
 
-  override def getArray(columnLabel: String) = ???
+    readColumnAs[RustArray](columnIndex)(arrayReads(elementDiscriminator), nullDefault)
+  }
 
-  override def getBytes(columnIndex: Int) = ???
+  override def getArray(columnLabel: String) = getArray(columnIndexOf(columnLabel))
 
-  override def getBytes(columnLabel: String) = ???
+  override def getBytes(columnIndex: Int) = {
+    val encoded = readColumnAs[String](columnIndex)
+    if (encoded == null) null else Base64.getDecoder.decode(encoded)
+  }
+
+  override def getBytes(columnLabel: String) = getBytes(columnIndexOf(columnLabel))
 
   override def getURL(columnIndex: Int) = ???
 
@@ -235,9 +354,12 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
 
   override def getSQLXML(columnLabel: String) = ???
 
-  override def getTime(columnIndex: Int) = ???
+  override def getTime(columnIndex: Int) = {
+    val hasZone = rustResultSet.columns.lift(columnIndex - 1).exists(_.discriminator == "TimeTz")
+    readColumnAs[Time](columnIndex)(timeReads(hasZone), nullDefault)
+  }
 
-  override def getTime(columnLabel: String) = ???
+  override def getTime(columnLabel: String) = getTime(columnIndexOf(columnLabel))
 
   override def getTime(columnIndex: Int, cal: Calendar) = ???
 
@@ -253,17 +375,17 @@ case class JsonResultSet(rustResultSet: RustResultSet) extends ResultSet with De
 
   override def findColumn(columnLabel: String) = ???
 
-  override def getFloat(columnIndex: Int) = ???
+  override def getFloat(columnIndex: Int) = readColumnAs[Float](columnIndex)(numericAsDouble.map(_.toFloat), FloatDefaultValue)
 
-  override def getFloat(columnLabel: String) = ???
+  override def getFloat(columnLabel: String) = getFloat(columnIndexOf(columnLabel))
 
   override def getRef(columnIndex: Int) = ???
 
   override def getRef(columnLabel: String) = ???
 
-  override def getShort(columnIndex: Int) = ???
+  override def getShort(columnIndex: Int) = readColumnAs[Short](columnIndex)
 
-  override def getShort(columnLabel: String) = ???
+  override def getShort(columnLabel: String) = readColumnAs[Short](columnLabel)
 
   /**
     * Intentionally unimplemented because we don't need it
@@ -0,0 +1,110 @@
+package com.prisma.native_jdbc
+
+import java.sql.{Date, SQLException, Time, Timestamp, Types}
+import java.time.format.DateTimeFormatter
+import java.time.{LocalDate, LocalTime, OffsetDateTime, OffsetTime}
+import java.util.{Base64, UUID}
+
+import com.prisma.native_jdbc.CustomPreparedStatement._
+import play.api.libs.json._
+
+object RustArray {
+  // Element discriminator -> (java.sql.Types, Postgres type name).
+  private val baseTypes = Map(
+    "Int"        -> (Types.INTEGER, "int4"),
+    "Long"       -> (Types.BIGINT, "int8"),
+    "Double"     -> (Types.DOUBLE, "float8"),
+    "Decimal"    -> (Types.NUMERIC, "numeric"),
+    "String"     -> (Types.VARCHAR, "text"),
+    "Boolean"    -> (Types.BOOLEAN, "bool"),
+    "UUID"       -> (Types.OTHER, "uuid"),
+    "Json"       -> (Types.OTHER, "jsonb"),
+    "Bytes"      -> (Types.BINARY, "bytea"),
+    "DateTime"   -> (Types.TIMESTAMP, "timestamp"),
+    "DateTimeTz" -> (Types.TIMESTAMP_WITH_TIMEZONE, "timestamptz"),
+    "Date"       -> (Types.DATE, "date"),
+    "Time"       -> (Types.TIME, "time"),
+    "TimeTz"     -> (Types.TIME_WITH_TIMEZONE, "timetz")
+  )
+
+  // Type names accepted by Connection.createArrayOf in addition to the names above, as the Postgres JDBC driver does.
+  private val typeNameAliases = Map(
+    "int2"                     -> "Int",
+    "smallint"                 -> "Int",
+    "integer"                  -> "Int",
+    "int"                      -> "Int",
+    "bigint"                   -> "Long",
+    "float4"                   -> "Double",
+    "real"                     -> "Double",
+    "double precision"         -> "Double",
+    "decimal"                  -> "Decimal",
+    "varchar"                  -> "String",
+    "character varying"        -> "String",
+    "boolean"                  -> "Boolean",
+    "json"                     -> "Json",
+    "timestamp with time zone" -> "DateTimeTz",
+    "time with time zone"      -> "TimeTz"
+  )
+
+  def discriminatorFor(typeName: String): String = {
+    val name = typeName.toLowerCase
+    baseTypes
+      .collectFirst { case (discriminator, (_, `name`)) => discriminator }
+      .orElse(typeNameAliases.get(name))
+      .getOrElse(throw new SQLException(s"Arrays of type $typeName are not supported"))
+  }
+
+  // The value of an Array parameter: nested arrays are the inner dimensions of a multi-dimensional array.
+  def toJson(elementDiscriminator: String, elements: Array[_]): JsArray = JsArray(elements.toVector.map(elementToJson(elementDiscriminator, _)))
+
+  private def elementToJson(discriminator: String, element: Any): JsValue = (discriminator, element) match {
+    case (_, null)                                => JsNull
+    case ("Json", json: JsValue)                  => json
+    case ("Json", json: String)                   => Json.parse(json)
+    case ("Bytes", bytes: Array[Byte])            => JsString(Base64.getEncoder.encodeToString(bytes))
+    case (_, nested: Array[_])                    => toJson(discriminator, nested)
+    case ("Int" | "Long", n: java.lang.Number)    => JsNumber(n.longValue())
+    case ("Double", n: java.lang.Number)          => doubleToJson(n.doubleValue())
+    case ("Decimal", d: java.math.BigDecimal)     => JsString(d.toPlainString)
+    case ("Decimal", n: java.lang.Number)         => JsString(n.toString)
+    case ("String", s: String)                    => JsString(s)
+    case ("Boolean", b: java.lang.Boolean)        => JsBoolean(b)
+    case ("UUID", uuid: UUID)                     => JsString(uuid.toString)
+    case ("UUID", uuid: String)                   => JsString(uuid)
+    case ("DateTime", t: Timestamp)               => Json.toJson(magicDateTime(t))
+    case ("DateTimeTz", t: Timestamp)             => JsString(t.toInstant.toString)
+    case ("DateTimeTz", dt: OffsetDateTime)       => JsString(dt.format(DateTimeFormatter.ISO_OFFSET_DATE_TIME))
+    case ("Date", date: Date)                     => JsString(date.toString)
+    case ("Date", date: LocalDate)                => JsString(date.toString)
+    case ("Time", time: Time)                     => JsString(time.toString)
+    case ("Time", time: LocalTime)                => JsString(time.format(DateTimeFormatter.ISO_LOCAL_TIME))
+    case ("TimeTz", time: OffsetTime)             => JsString(time.format(DateTimeFormatter.ISO_OFFSET_TIME))
+    case (d, x)                                   => throw new SQLException(s"Can't use $x as element of an array of $d")
+  }
+}
+
+// Elements are boxed like getObject returns column values, the inner dimensions of multi-dimensional arrays are nested arrays.
+case class RustArray(elementDiscriminator: String, elements: Array[AnyRef]) extends java.sql.Array {
+  import RustArray._
+
+  private def baseType = baseTypes.getOrElse(elementDiscriminator, (Types.OTHER, elementDiscriminator))
+
+  override def getBaseTypeName = baseType._2
+  override def getBaseType     = baseType._1
+  override def getArray        = elements
+  override def getArray(index: Long, count: Int) = {
+    if (index < 1 || count < 0 || index - 1 + count > elements.length) {
+      throw new SQLException(s"Invalid array slice: index $index, count $count, length ${elements.length}")
+    }
+
+    elements.slice((index - 1).toInt, (index - 1).toInt + count)
+  }
+
+  override def getArray(map: java.util.Map[String, Class[_]])                               = ???
+  override def getArray(index: Long, count: Int, map: java.util.Map[String, Class[_]])     = ???
+  override def getResultSet                                                               = ???
+  override def getResultSet(map: java.util.Map[String, Class[_]])                         = ???
+  override def getResultSet(index: Long, count: Int)                                      = ???
+  override def getResultSet(index: Long, count: Int, map: java.util.Map[String, Class[_]]) = ???
+  override def free()                                                                     = ()
+}
@@ -1,21 +1,66 @@
 package com.prisma.native_jdbc
 
+import java.sql.SQLException
+
 trait RustConnection
 trait RustPreparedStatement
+trait RustCursor
+trait RustExport
+trait RustPool
+
+// What a batch does after one of its rows failed, see executePreparedstatementWith.
+object BatchErrorPolicy {
+  val Continue         = 0
+  val StopAtFirstError = 1
+  val RollBack         = 2
+
+  def parse(name: String): Int = name match {
+    case "continue" => Continue
+    case "stop"     => StopAtFirstError
+    case "rollback" => RollBack
+    case x          => throw new SQLException(s"Invalid batch error policy: $x")
+  }
+}
 
 trait RustBinding {
   type Conn <: RustConnection
   type Stmt <: RustPreparedStatement
+  type Cursor <: RustCursor
+  type Export <: RustExport
+  type Pool <: RustPool
 
   def newConnection(url: String): Conn
   def prepareStatement(connection: Conn, query: String): Stmt
   def closeStatement(stmt: Stmt): RustCallResult
   def startTransaction(connection: Conn): RustCallResult
+  def startTransactionWith(connection: Conn, isolationLevel: Int, readOnly: Boolean, deferrable: Boolean): RustCallResult
+  def setTransactionIsolation(connection: Conn, isolationLevel: Int): RustCallResult
+  def setReadOnly(connection: Conn, readOnly: Boolean): RustCallResult
+  def executeTransaction(connection: Conn, statements: String, maxAttempts: Int, backoffMs: Long): RustCallResult
   def commitTransaction(connection: Conn): RustCallResult
   def rollbackTransaction(connection: Conn): RustCallResult
+  def createSavepoint(connection: Conn, name: String): RustCallResult
+  def releaseSavepoint(connection: Conn, name: String): RustCallResult
+  def rollbackToSavepoint(connection: Conn, name: String): RustCallResult
   def closeConnection(connection: Conn): RustCallResult
   def sqlExecute(connection: Conn, query: String, params: String): RustCallResult
   def sqlQuery(connection: Conn, query: String, params: String): RustCallResult
   def executePreparedstatement(stmt: Stmt, params: String): RustCallResult
+  def executePreparedstatementWith(stmt: Stmt, params: String, errorPolicy: Int): RustCallResult
   def queryPreparedstatement(stmt: Stmt, params: String): RustCallResult
+  def sqlQueryBinary(connection: Conn, query: String, params: String): RustResultSet
+  def queryPreparedstatementBinary(stmt: Stmt, params: String): RustResultSet
+  def openCursor(connection: Conn, query: String, params: String): Cursor
+  def fetchCursor(connection: Conn, cursor: Cursor, count: Int): RustCallResult
+  def fetchCursorBinary(connection: Conn, cursor: Cursor, count: Int): RustResultSet
+  def closeCursor(connection: Conn, cursor: Cursor): RustCallResult
+  def copyIn(connection: Conn, schema: String, table: String, columns: String, format: String, data: String): RustCallResult
+  def openExport(connection: Conn, query: String, format: String, chunkSize: Int): Export
+  def fetchExport(export: Export): Array[Byte]
+  def closeExport(export: Export): RustCallResult
+  def newPool(url: String, minSize: Int, maxSize: Int, idleTimeoutMs: Long): Pool
+  def checkoutConnection(pool: Pool): Conn
+  def checkinConnection(pool: Pool, connection: Conn): RustCallResult
+  def poolStats(pool: Pool): RustPoolStats
+  def closePool(pool: Pool): RustCallResult
 }
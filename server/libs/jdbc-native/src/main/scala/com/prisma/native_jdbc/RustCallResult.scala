@@ -9,6 +9,8 @@ object RustCallResult {
   implicit val resultColumnFormat = Json.format[ResultColumn]
   implicit val resultSetFormat    = Json.format[RustResultSet]
   implicit val errorFormat        = Json.format[RustError]
+  implicit val poolStatsFormat    = Json.format[RustPoolStats]
+  implicit val batchErrorFormat   = Json.format[RustBatchError]
   implicit val protocolFormat     = Json.format[RustCallResult]
 
   def fromString(str: String): RustCallResult = {
@@ -21,7 +23,7 @@ object RustCallResult {
       }
 
       if (protocol.isCount && protocol.counts.contains(-3)) {
-        throw new BatchUpdateException(protocol.counts.toArray)
+        throw RustBatchUpdateException(protocol.counts, protocol.errors.getOrElse(Vector.empty))
       }
 
       protocol
@@ -29,11 +31,20 @@ object RustCallResult {
   }
 }
 
-case class RustCallResult(ty: String, counts: Vector[Int], rows: Option[RustResultSet], error: Option[RustError]) {
+// attempts is only reported by executeTransaction, which retries serialization failures and deadlocks.
+// errors is only reported for batches, it holds the errors of the failed rows.
+case class RustCallResult(ty: String,
+                          counts: Vector[Int],
+                          rows: Option[RustResultSet],
+                          error: Option[RustError],
+                          stats: Option[RustPoolStats],
+                          attempts: Option[Int] = None,
+                          errors: Option[Vector[RustBatchError]] = None) {
   def isResultSet = ty == "RESULT_SET"
   def isError     = ty == "ERROR"
   def isCount     = ty == "COUNT"
   def isEmpty     = ty == "EMPTY"
+  def isPoolStats = ty == "POOL_STATS"
   def toResultSet = JsonResultSet(rows.get)
 }
 
@@ -42,5 +53,22 @@ object RustResultSet {
 }
 
 case class RustResultSet(columns: Vector[ResultColumn], data: IndexedSeq[JsArray])
-case class ResultColumn(name: String, discriminator: String)
+case class ResultColumn(name: String, discriminator: String, elementDiscriminator: Option[String] = None, typeName: Option[String] = None)
 case class RustError(code: String, message: String)
+case class RustBatchError(index: Int, code: String, message: String, constraint: Option[String])
+
+object RustBatchUpdateException {
+  // Reports the first failed row like a single statement would, the errors of all failed rows are chained as next exceptions.
+  def apply(counts: Vector[Int], errors: Vector[RustBatchError]): RustBatchUpdateException = {
+    val first     = errors.headOption
+    val exception = new RustBatchUpdateException(first.map(e => s"Row ${e.index}: ${e.message}").orNull, first.map(_.code).orNull, counts.toArray, errors)
+
+    errors.foreach(e => exception.setNextException(new SQLException(s"Row ${e.index}: ${e.message}", e.code)))
+    exception
+  }
+}
+
+class RustBatchUpdateException(reason: String, sqlState: String, counts: Array[Int], val errors: Vector[RustBatchError])
+    extends BatchUpdateException(reason, sqlState, counts)
+
+case class RustPoolStats(idle: Int, active: Int, total: Int, min: Int, max: Int, replaced: Long)
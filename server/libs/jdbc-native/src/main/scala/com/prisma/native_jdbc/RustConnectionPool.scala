@@ -0,0 +1,22 @@
+package com.prisma.native_jdbc
+
+// Connections of a native pool (see pool.rs). Closing a connection checked out here returns it to the pool.
+class RustConnectionPool(val binding: RustBinding, url: String, minSize: Int, maxSize: Int, idleTimeoutMs: Long) {
+  private val pool = binding.newPool(url, minSize, maxSize, idleTimeoutMs)
+
+  def checkout(): BindingAndConnection = {
+    val connection = binding.checkoutConnection(pool)
+    pooled(connection)
+  }
+
+  def stats: RustPoolStats = binding.poolStats(pool)
+
+  def close(): RustCallResult = binding.closePool(pool)
+
+  private def pooled(conn: binding.Conn): BindingAndConnection = new BindingAndConnection {
+    override val binding: RustConnectionPool.this.binding.type = RustConnectionPool.this.binding
+    override val connection: binding.Conn                      = conn
+
+    override def release(): RustCallResult = binding.checkinConnection(pool, connection)
+  }
+}
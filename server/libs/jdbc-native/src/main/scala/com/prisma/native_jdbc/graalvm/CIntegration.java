@@ -3,7 +3,7 @@ package com.prisma.native_jdbc.graalvm;
 import org.graalvm.nativeimage.c.CContext;
 import org.graalvm.nativeimage.c.struct.CField;
 import org.graalvm.nativeimage.c.struct.CStruct;
-import org.graalvm.nativeimage.c.type.CCharPointer;
+import org.graalvm.word.Pointer;
 import org.graalvm.word.PointerBase;
 
 import java.util.Collections;
@@ -20,18 +20,30 @@ public class CIntegration {
         }
     }
 
-    @CStruct(value = "PsqlConnection", isIncomplete = true)
-    public interface RustConnection extends PointerBase {}
+    @CStruct(value = "HandleAndError")
+    public interface HandleAndError extends PointerBase {
+        @CField("error")
+        Pointer error();
+
+        @CField("error_len")
+        long errorLen();
 
-    @CStruct(value = "PsqlPreparedStatement", isIncomplete = true)
-    public interface RustStatement extends PointerBase {}
+        @CField("handle")
+        long handle();
+    }
 
-    @CStruct(value = "PointerAndError")
-    public interface PointerAndError extends PointerBase {
+    @CStruct(value = "ProtocolBuffer")
+    public interface ProtocolBuffer extends PointerBase {
         @CField("error")
-        CCharPointer error();
+        Pointer error();
+
+        @CField("error_len")
+        long errorLen();
+
+        @CField("data")
+        Pointer data();
 
-        @CField("pointer")
-        PointerBase pointer();
+        @CField("data_len")
+        long dataLen();
     }
 }
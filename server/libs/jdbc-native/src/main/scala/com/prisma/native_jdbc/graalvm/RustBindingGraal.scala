@@ -1,17 +1,27 @@
 package com.prisma.native_jdbc.graalvm
 
-import com.prisma.native_jdbc.{RustBinding, RustCallResult, RustConnection, RustPreparedStatement}
-import org.graalvm.nativeimage.c.`type`.{CCharPointer, CTypeConversion}
+import java.nio.charset.StandardCharsets
 
-class RustConnectionGraal(val conn: CIntegration.RustConnection)       extends RustConnection
-class RustPreparedStatementGraal(val stmt: CIntegration.RustStatement) extends RustPreparedStatement
+import com.prisma.native_jdbc.{BinaryResultSet, RustBinding, RustCallResult, RustConnection, RustCursor, RustExport, RustPool, RustPoolStats, RustPreparedStatement, RustResultSet}
+import org.graalvm.nativeimage.c.`type`.CTypeConversion
+import org.graalvm.word.Pointer
+
+import scala.util.Try
+
+class RustConnectionGraal(val conn: Long)        extends RustConnection
+class RustPreparedStatementGraal(val stmt: Long) extends RustPreparedStatement
+class RustCursorGraal(val cursor: Long)          extends RustCursor
+class RustExportGraal(val export: Long)          extends RustExport
+class RustPoolGraal(val pool: Long)              extends RustPool
 
 object RustBindingGraal extends RustBinding {
-  def toJavaString(str: CCharPointer): String                    = CTypeConversion.toJavaString(str)
   def toCString(str: String): CTypeConversion.CCharPointerHolder = CTypeConversion.toCString(str)
 
-  override type Conn = RustConnectionGraal
-  override type Stmt = RustPreparedStatementGraal
+  override type Conn   = RustConnectionGraal
+  override type Stmt   = RustPreparedStatementGraal
+  override type Cursor = RustCursorGraal
+  override type Export = RustExportGraal
+  override type Pool   = RustPoolGraal
 
   def initialize() = {
     RustInterfaceGraal.jdbc_initialize()
@@ -21,112 +31,307 @@ object RustBindingGraal extends RustBinding {
   // todo Same todos as in JWT native apply - resources leak in error cases. Need to be solved with appropriate try-with-resources code, which doesn't compile at the moment.
 
   override def newConnection(url: String): RustConnectionGraal = {
-    val _url       = toCString(url)
-    val connection = new RustConnectionGraal(RustInterfaceGraal.newConnection(_url.get()))
+    val _url                                      = toCString(url)
+    val handleAndErr: CIntegration.HandleAndError = RustInterfaceGraal.newConnection(_url.get())
 
     _url.close()
-    connection
+    val result = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.errorLen)) }
+      .map(_ => new RustConnectionGraal(handleAndErr.handle))
+
+    RustInterfaceGraal.destroy(handleAndErr) // The handle stays valid after dropping the envelope
+    result.get
   }
 
   override def closeConnection(connection: RustConnectionGraal): RustCallResult = {
-    val raw    = RustInterfaceGraal.closeConnection(connection.conn)
-    val result = RustCallResult.fromString(toJavaString(raw))
-
-    RustInterfaceGraal.destroy_string(raw)
-    result
+    val buffer = RustInterfaceGraal.closeConnection(connection.conn)
+    processCallResult(buffer)
   }
 
   override def prepareStatement(connection: RustConnectionGraal, query: String): RustPreparedStatementGraal = {
     val _query: CTypeConversion.CCharPointerHolder = toCString(query)
-    val ptrAndErr: CIntegration.PointerAndError    = RustInterfaceGraal.prepareStatement(connection.conn, _query.get())
+    val handleAndErr: CIntegration.HandleAndError  = RustInterfaceGraal.prepareStatement(connection.conn, _query.get())
 
     _query.close()
-    RustCallResult.fromString(toJavaString(ptrAndErr.error))
+    RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.errorLen))
 
-    val result = new RustPreparedStatementGraal(ptrAndErr.pointer.asInstanceOf[CIntegration.RustStatement])
-    RustInterfaceGraal.destroy(ptrAndErr) // The pointer will still be valid after dropping the envelope
+    val result = new RustPreparedStatementGraal(handleAndErr.handle)
+    RustInterfaceGraal.destroy(handleAndErr) // The handle stays valid after dropping the envelope
     result
   }
 
   override def closeStatement(stmt: RustPreparedStatementGraal): RustCallResult = {
-    val raw    = RustInterfaceGraal.closeStatement(stmt.stmt)
-    val result = RustCallResult.fromString(toJavaString(raw))
-
-    RustInterfaceGraal.destroy_string(raw)
-    result
+    val buffer = RustInterfaceGraal.closeStatement(stmt.stmt)
+    processCallResult(buffer)
   }
 
   override def startTransaction(connection: RustConnectionGraal): RustCallResult = {
-    val raw    = RustInterfaceGraal.startTransaction(connection.conn)
-    val result = RustCallResult.fromString(toJavaString(raw))
+    val buffer = RustInterfaceGraal.startTransaction(connection.conn)
+    processCallResult(buffer)
+  }
 
-    RustInterfaceGraal.destroy_string(raw)
-    result
+  override def startTransactionWith(connection: RustConnectionGraal, isolationLevel: Int, readOnly: Boolean, deferrable: Boolean): RustCallResult = {
+    val buffer = RustInterfaceGraal.startTransactionWith(connection.conn, isolationLevel, readOnly, deferrable)
+    processCallResult(buffer)
   }
 
-  override def commitTransaction(connection: RustConnectionGraal): RustCallResult = {
-    val raw    = RustInterfaceGraal.commitTransaction(connection.conn)
-    val result = RustCallResult.fromString(toJavaString(raw))
+  override def setTransactionIsolation(connection: RustConnectionGraal, isolationLevel: Int): RustCallResult = {
+    val buffer = RustInterfaceGraal.setTransactionIsolation(connection.conn, isolationLevel)
+    processCallResult(buffer)
+  }
 
-    RustInterfaceGraal.destroy_string(raw)
-    result
+  override def setReadOnly(connection: RustConnectionGraal, readOnly: Boolean): RustCallResult = {
+    val buffer = RustInterfaceGraal.setReadOnly(connection.conn, readOnly)
+    processCallResult(buffer)
+  }
+
+  override def executeTransaction(connection: RustConnectionGraal, statements: String, maxAttempts: Int, backoffMs: Long): RustCallResult = {
+    val _statements = toCString(statements)
+    val buffer      = RustInterfaceGraal.executeTransaction(connection.conn, _statements.get(), maxAttempts, backoffMs)
+
+    _statements.close()
+    processCallResult(buffer)
+  }
+
+  override def commitTransaction(connection: RustConnectionGraal): RustCallResult = {
+    val buffer = RustInterfaceGraal.commitTransaction(connection.conn)
+    processCallResult(buffer)
   }
 
   override def rollbackTransaction(connection: RustConnectionGraal): RustCallResult = {
-    val raw    = RustInterfaceGraal.rollbackTransaction(connection.conn)
-    val result = RustCallResult.fromString(toJavaString(raw))
+    val buffer = RustInterfaceGraal.rollbackTransaction(connection.conn)
+    processCallResult(buffer)
+  }
 
-    RustInterfaceGraal.destroy_string(raw)
-    result
+  override def createSavepoint(connection: RustConnectionGraal, name: String): RustCallResult = {
+    val _name  = toCString(name)
+    val buffer = RustInterfaceGraal.createSavepoint(connection.conn, _name.get())
+
+    _name.close()
+    processCallResult(buffer)
+  }
+
+  override def releaseSavepoint(connection: RustConnectionGraal, name: String): RustCallResult = {
+    val _name  = toCString(name)
+    val buffer = RustInterfaceGraal.releaseSavepoint(connection.conn, _name.get())
+
+    _name.close()
+    processCallResult(buffer)
+  }
+
+  override def rollbackToSavepoint(connection: RustConnectionGraal, name: String): RustCallResult = {
+    val _name  = toCString(name)
+    val buffer = RustInterfaceGraal.rollbackToSavepoint(connection.conn, _name.get())
+
+    _name.close()
+    processCallResult(buffer)
   }
 
   override def sqlExecute(connection: RustConnectionGraal, query: String, params: String): RustCallResult = {
     val _query  = toCString(query)
     val _params = toCString(params)
-    val raw     = RustInterfaceGraal.sqlExecute(connection.conn, _query.get(), _params.get())
+    val buffer  = RustInterfaceGraal.sqlExecute(connection.conn, _query.get(), _params.get())
 
     _query.close()
     _params.close()
 
-    val result = RustCallResult.fromString(toJavaString(raw))
-
-    RustInterfaceGraal.destroy_string(raw)
-    result
+    processCallResult(buffer)
   }
 
   override def sqlQuery(connection: RustConnectionGraal, query: String, params: String): RustCallResult = {
     val _query  = toCString(query)
     val _params = toCString(params)
-    val raw     = RustInterfaceGraal.sqlQuery(connection.conn, _query.get(), _params.get())
+    val buffer  = RustInterfaceGraal.sqlQuery(connection.conn, _query.get(), _params.get())
 
     _query.close()
     _params.close()
 
-    val result = RustCallResult.fromString(toJavaString(raw))
-
-    RustInterfaceGraal.destroy_string(raw)
-    result
+    processCallResult(buffer)
   }
 
   override def executePreparedstatement(stmt: RustPreparedStatementGraal, params: String): RustCallResult = {
     val _params = toCString(params)
-    val raw     = RustInterfaceGraal.executePreparedstatement(stmt.stmt, _params.get())
+    val buffer  = RustInterfaceGraal.executePreparedstatement(stmt.stmt, _params.get())
 
     _params.close()
-    val result = RustCallResult.fromString(toJavaString(raw))
+    processCallResult(buffer)
+  }
 
-    RustInterfaceGraal.destroy_string(raw)
-    result
+  override def executePreparedstatementWith(stmt: RustPreparedStatementGraal, params: String, errorPolicy: Int): RustCallResult = {
+    val _params = toCString(params)
+    val buffer  = RustInterfaceGraal.executePreparedstatementWith(stmt.stmt, _params.get(), errorPolicy)
+
+    _params.close()
+    processCallResult(buffer)
   }
 
   override def queryPreparedstatement(stmt: RustPreparedStatementGraal, params: String): RustCallResult = {
     val _params = toCString(params)
-    val raw     = RustInterfaceGraal.queryPreparedstatement(stmt.stmt, _params.get())
+    val buffer  = RustInterfaceGraal.queryPreparedstatement(stmt.stmt, _params.get())
 
     _params.close()
-    val result = RustCallResult.fromString(toJavaString(raw))
+    processCallResult(buffer)
+  }
 
-    RustInterfaceGraal.destroy_string(raw)
-    result
+  override def sqlQueryBinary(connection: RustConnectionGraal, query: String, params: String): RustResultSet = {
+    val _query  = toCString(query)
+    val _params = toCString(params)
+    val buffer  = RustInterfaceGraal.sqlQueryBinary(connection.conn, _query.get(), _params.get())
+
+    _query.close()
+    _params.close()
+
+    processBinaryResult(buffer)
+  }
+
+  override def queryPreparedstatementBinary(stmt: RustPreparedStatementGraal, params: String): RustResultSet = {
+    val _params = toCString(params)
+    val buffer  = RustInterfaceGraal.queryPreparedstatementBinary(stmt.stmt, _params.get())
+
+    _params.close()
+    processBinaryResult(buffer)
+  }
+
+  override def openCursor(connection: RustConnectionGraal, query: String, params: String): RustCursorGraal = {
+    val _query                                    = toCString(query)
+    val _params                                   = toCString(params)
+    val handleAndErr: CIntegration.HandleAndError = RustInterfaceGraal.openCursor(connection.conn, _query.get(), _params.get())
+
+    _query.close()
+    _params.close()
+
+    val result = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.errorLen)) }
+      .map(_ => new RustCursorGraal(handleAndErr.handle))
+
+    RustInterfaceGraal.destroy(handleAndErr) // The handle stays valid after dropping the envelope
+    result.get
+  }
+
+  override def fetchCursor(connection: RustConnectionGraal, cursor: RustCursorGraal, count: Int): RustCallResult = {
+    val buffer = RustInterfaceGraal.fetchCursor(connection.conn, cursor.cursor, count)
+    processCallResult(buffer)
+  }
+
+  override def fetchCursorBinary(connection: RustConnectionGraal, cursor: RustCursorGraal, count: Int): RustResultSet = {
+    val buffer = RustInterfaceGraal.fetchCursorBinary(connection.conn, cursor.cursor, count)
+    processBinaryResult(buffer)
+  }
+
+  override def closeCursor(connection: RustConnectionGraal, cursor: RustCursorGraal): RustCallResult = {
+    val buffer = RustInterfaceGraal.closeCursor(connection.conn, cursor.cursor)
+    processCallResult(buffer)
+  }
+
+  override def copyIn(connection: RustConnectionGraal, schema: String, table: String, columns: String, format: String, data: String): RustCallResult = {
+    val _schema  = toCString(schema)
+    val _table   = toCString(table)
+    val _columns = toCString(columns)
+    val _format  = toCString(format)
+    val _data    = toCString(data)
+    val buffer   = RustInterfaceGraal.copyIn(connection.conn, _schema.get(), _table.get(), _columns.get(), _format.get(), _data.get())
+
+    _schema.close()
+    _table.close()
+    _columns.close()
+    _format.close()
+    _data.close()
+
+    processCallResult(buffer)
+  }
+
+  override def openExport(connection: RustConnectionGraal, query: String, format: String, chunkSize: Int): RustExportGraal = {
+    val _query                                    = toCString(query)
+    val _format                                   = toCString(format)
+    val handleAndErr: CIntegration.HandleAndError = RustInterfaceGraal.openExport(connection.conn, _query.get(), _format.get(), chunkSize)
+
+    _query.close()
+    _format.close()
+
+    val result = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.errorLen)) }
+      .map(_ => new RustExportGraal(handleAndErr.handle))
+
+    RustInterfaceGraal.destroy(handleAndErr) // The handle stays valid after dropping the envelope
+    result.get
+  }
+
+  override def fetchExport(export: RustExportGraal): Array[Byte] = {
+    val buffer = RustInterfaceGraal.fetchExport(export.export)
+    processBytes(buffer)
+  }
+
+  override def closeExport(export: RustExportGraal): RustCallResult = {
+    val buffer = RustInterfaceGraal.closeExport(export.export)
+    processCallResult(buffer)
+  }
+
+  override def newPool(url: String, minSize: Int, maxSize: Int, idleTimeoutMs: Long): RustPoolGraal = {
+    val _url                                      = toCString(url)
+    val handleAndErr: CIntegration.HandleAndError = RustInterfaceGraal.newPool(_url.get(), minSize, maxSize, idleTimeoutMs)
+
+    _url.close()
+    val result = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.errorLen)) }
+      .map(_ => new RustPoolGraal(handleAndErr.handle))
+
+    RustInterfaceGraal.destroy(handleAndErr) // The handle stays valid after dropping the envelope
+    result.get
+  }
+
+  override def checkoutConnection(pool: RustPoolGraal): RustConnectionGraal = {
+    val handleAndErr: CIntegration.HandleAndError = RustInterfaceGraal.checkoutConnection(pool.pool)
+    val result = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.errorLen)) }
+      .map(_ => new RustConnectionGraal(handleAndErr.handle))
+
+    RustInterfaceGraal.destroy(handleAndErr) // The handle stays valid after dropping the envelope
+    result.get
+  }
+
+  override def checkinConnection(pool: RustPoolGraal, connection: RustConnectionGraal): RustCallResult = {
+    val buffer = RustInterfaceGraal.checkinConnection(pool.pool, connection.conn)
+    processCallResult(buffer)
+  }
+
+  override def poolStats(pool: RustPoolGraal): RustPoolStats = {
+    val buffer = RustInterfaceGraal.poolStats(pool.pool)
+    processCallResult(buffer).stats.get
+  }
+
+  override def closePool(pool: RustPoolGraal): RustCallResult = {
+    val buffer = RustInterfaceGraal.closePool(pool.pool)
+    processCallResult(buffer)
+  }
+
+  def processCallResult(buffer: CIntegration.ProtocolBuffer): RustCallResult = processBuffer(buffer) { data =>
+    RustCallResult.fromString(readString(data, buffer.dataLen))
+  }
+
+  // The data is copied while decoding, the buffer can be destroyed afterwards.
+  def processBinaryResult(buffer: CIntegration.ProtocolBuffer): RustResultSet = processBuffer(buffer) { data =>
+    BinaryResultSet.decode(CTypeConversion.asByteBuffer(data, buffer.dataLen.toInt))
+  }
+
+  def processBytes(buffer: CIntegration.ProtocolBuffer): Array[Byte] = processBuffer(buffer) { data =>
+    val bytes = new Array[Byte](buffer.dataLen.toInt)
+    CTypeConversion.asByteBuffer(data, bytes.length).get(bytes)
+    bytes
+  }
+
+  // Errors are serialized call results, reading them throws the contained error.
+  private def processBuffer[T](buffer: CIntegration.ProtocolBuffer)(read: Pointer => T): T = {
+    val result = Try {
+      if (buffer.error.isNonNull) {
+        RustCallResult.fromString(readString(buffer.error, buffer.errorLen))
+      }
+
+      read(buffer.data)
+    }
+
+    RustInterfaceGraal.destroy_buffer(buffer)
+    result.get
+  }
+
+  // Results are length delimited instead of NUL terminated.
+  def readString(ptr: Pointer, len: Long): String = {
+    val bytes = new Array[Byte](len.toInt)
+    CTypeConversion.asByteBuffer(ptr, len.toInt).get(bytes)
+    new String(bytes, StandardCharsets.UTF_8)
   }
 }
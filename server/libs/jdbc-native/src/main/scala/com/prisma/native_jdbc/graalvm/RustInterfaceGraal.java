@@ -10,41 +10,110 @@ public class RustInterfaceGraal {
     static native void jdbc_initialize();
 
     @CFunction
-    static native CIntegration.RustConnection newConnection(CCharPointer url);
+    static native CIntegration.HandleAndError newConnection(CCharPointer url);
 
     @CFunction
-    static native CIntegration.PointerAndError prepareStatement(CIntegration.RustConnection conn, CCharPointer query);
+    static native CIntegration.HandleAndError prepareStatement(long conn, CCharPointer query);
 
     @CFunction
-    static native CCharPointer startTransaction(CIntegration.RustConnection connection);
+    static native CIntegration.ProtocolBuffer startTransaction(long connection);
 
     @CFunction
-    static native CCharPointer commitTransaction(CIntegration.RustConnection connection);
+    static native CIntegration.ProtocolBuffer startTransactionWith(long connection, int isolationLevel, boolean readOnly, boolean deferrable);
 
     @CFunction
-    static native CCharPointer rollbackTransaction(CIntegration.RustConnection connection);
+    static native CIntegration.ProtocolBuffer setTransactionIsolation(long connection, int isolationLevel);
 
     @CFunction
-    static native CCharPointer closeConnection(CIntegration.RustConnection connection);
+    static native CIntegration.ProtocolBuffer setReadOnly(long connection, boolean readOnly);
 
     @CFunction
-    static native CCharPointer closeStatement(CIntegration.RustStatement stmt);
+    static native CIntegration.ProtocolBuffer executeTransaction(long connection, CCharPointer statements, int maxAttempts, long backoffMs);
 
     @CFunction
-    static native CCharPointer executePreparedstatement(CIntegration.RustStatement stmt, CCharPointer params);
+    static native CIntegration.ProtocolBuffer commitTransaction(long connection);
 
     @CFunction
-    static native CCharPointer queryPreparedstatement(CIntegration.RustStatement stmt, CCharPointer params);
+    static native CIntegration.ProtocolBuffer rollbackTransaction(long connection);
 
     @CFunction
-    static native CCharPointer sqlExecute(CIntegration.RustConnection connection, CCharPointer query, CCharPointer params);
+    static native CIntegration.ProtocolBuffer closeConnection(long connection);
 
     @CFunction
-    static native CCharPointer sqlQuery(CIntegration.RustConnection connection, CCharPointer query, CCharPointer params);
+    static native CIntegration.ProtocolBuffer closeStatement(long stmt);
 
     @CFunction
-    static native void destroy(CIntegration.PointerAndError pointerAndError);
+    static native CIntegration.ProtocolBuffer executePreparedstatement(long stmt, CCharPointer params);
 
     @CFunction
-    static native void destroy_string(CCharPointer s);
+    static native CIntegration.ProtocolBuffer executePreparedstatementWith(long stmt, CCharPointer params, int errorPolicy);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer queryPreparedstatement(long stmt, CCharPointer params);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer sqlExecute(long connection, CCharPointer query, CCharPointer params);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer sqlQuery(long connection, CCharPointer query, CCharPointer params);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer queryPreparedstatementBinary(long stmt, CCharPointer params);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer sqlQueryBinary(long connection, CCharPointer query, CCharPointer params);
+
+    @CFunction
+    static native CIntegration.HandleAndError newPool(CCharPointer url, int minSize, int maxSize, long idleTimeoutMs);
+
+    @CFunction
+    static native CIntegration.HandleAndError checkoutConnection(long pool);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer checkinConnection(long pool, long connection);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer poolStats(long pool);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer closePool(long pool);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer createSavepoint(long connection, CCharPointer name);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer releaseSavepoint(long connection, CCharPointer name);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer rollbackToSavepoint(long connection, CCharPointer name);
+
+    @CFunction
+    static native CIntegration.HandleAndError openCursor(long connection, CCharPointer query, CCharPointer params);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer fetchCursor(long connection, long cursor, int count);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer fetchCursorBinary(long connection, long cursor, int count);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer closeCursor(long connection, long cursor);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer copyIn(long connection, CCharPointer schema, CCharPointer table, CCharPointer columns, CCharPointer format, CCharPointer data);
+
+    @CFunction
+    static native CIntegration.HandleAndError openExport(long connection, CCharPointer query, CCharPointer format, int chunkSize);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer fetchExport(long export);
+
+    @CFunction
+    static native CIntegration.ProtocolBuffer closeExport(long export);
+
+    @CFunction
+    static native void destroy(CIntegration.HandleAndError handleAndError);
+
+    @CFunction
+    static native void destroy_buffer(CIntegration.ProtocolBuffer buffer);
 }
@@ -0,0 +1,18 @@
+package com.prisma.native_jdbc.jna;
+
+import com.sun.jna.Pointer;
+import com.sun.jna.Structure;
+
+import java.util.Arrays;
+import java.util.List;
+
+public class HandleAndError extends Structure implements Structure.ByReference {
+    public Pointer error;
+    public SizeT error_len;
+    public long handle;
+
+    @Override
+    protected List<String> getFieldOrder() {
+        return Arrays.asList("error", "error_len", "handle");
+    }
+}
@@ -1,34 +1,79 @@
 package com.prisma.native_jdbc.jna;
 
 import com.sun.jna.Library;
-import com.sun.jna.Pointer;
 
 public interface JnaRustBridge extends Library {
     void jdbc_initialize();
 
-    PointerAndError prepareStatement(Pointer connection, String query);
+    HandleAndError prepareStatement(long connection, String query);
 
-    Pointer newConnection(String url);
+    HandleAndError newConnection(String url);
 
-    Pointer startTransaction(Pointer connection);
+    ProtocolBuffer startTransaction(long connection);
 
-    Pointer commitTransaction(Pointer connection);
+    ProtocolBuffer startTransactionWith(long connection, int isolationLevel, boolean readOnly, boolean deferrable);
 
-    Pointer rollbackTransaction(Pointer connection);
+    ProtocolBuffer setTransactionIsolation(long connection, int isolationLevel);
 
-    Pointer closeConnection(Pointer connection);
+    ProtocolBuffer setReadOnly(long connection, boolean readOnly);
 
-    Pointer sqlExecute(Pointer connection, String query, String params);
+    ProtocolBuffer executeTransaction(long connection, String statements, int maxAttempts, long backoffMs);
 
-    Pointer sqlQuery(Pointer connection, String query, String params);
+    ProtocolBuffer commitTransaction(long connection);
 
-    Pointer executePreparedstatement(Pointer stmt, String params);
+    ProtocolBuffer rollbackTransaction(long connection);
 
-    Pointer queryPreparedstatement(Pointer stmt, String params);
+    ProtocolBuffer closeConnection(long connection);
 
-    Pointer closeStatement(Pointer stmt);
+    ProtocolBuffer sqlExecute(long connection, String query, String params);
 
-    void destroy(PointerAndError pointerAndError);
+    ProtocolBuffer sqlQuery(long connection, String query, String params);
 
-    void destroy_string(Pointer s);
+    ProtocolBuffer executePreparedstatement(long stmt, String params);
+
+    ProtocolBuffer executePreparedstatementWith(long stmt, String params, int errorPolicy);
+
+    ProtocolBuffer queryPreparedstatement(long stmt, String params);
+
+    ProtocolBuffer sqlQueryBinary(long connection, String query, String params);
+
+    ProtocolBuffer queryPreparedstatementBinary(long stmt, String params);
+
+    ProtocolBuffer closeStatement(long stmt);
+
+    HandleAndError newPool(String url, int minSize, int maxSize, long idleTimeoutMs);
+
+    HandleAndError checkoutConnection(long pool);
+
+    ProtocolBuffer checkinConnection(long pool, long connection);
+
+    ProtocolBuffer poolStats(long pool);
+
+    ProtocolBuffer closePool(long pool);
+
+    ProtocolBuffer createSavepoint(long connection, String name);
+
+    ProtocolBuffer releaseSavepoint(long connection, String name);
+
+    ProtocolBuffer rollbackToSavepoint(long connection, String name);
+
+    HandleAndError openCursor(long connection, String query, String params);
+
+    ProtocolBuffer fetchCursor(long connection, long cursor, int count);
+
+    ProtocolBuffer fetchCursorBinary(long connection, long cursor, int count);
+
+    ProtocolBuffer closeCursor(long connection, long cursor);
+
+    ProtocolBuffer copyIn(long connection, String schema, String table, String columns, String format, String data);
+
+    HandleAndError openExport(long connection, String query, String format, int chunkSize);
+
+    ProtocolBuffer fetchExport(long export);
+
+    ProtocolBuffer closeExport(long export);
+
+    void destroy(HandleAndError handleAndError);
+
+    void destroy_buffer(ProtocolBuffer buffer);
 }
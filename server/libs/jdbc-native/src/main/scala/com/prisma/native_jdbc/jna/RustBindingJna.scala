@@ -1,16 +1,24 @@
 package com.prisma.native_jdbc.jna
 
+import java.nio.charset.StandardCharsets
+
 import com.prisma.native_jdbc._
 import com.sun.jna.{Native, Pointer}
 
 import scala.util.Try
 
-class RustConnectionJna(val conn: Pointer)        extends RustConnection
-class RustPreparedStatementJna(val stmt: Pointer) extends RustPreparedStatement
+class RustConnectionJna(val conn: Long)        extends RustConnection
+class RustPreparedStatementJna(val stmt: Long) extends RustPreparedStatement
+class RustCursorJna(val cursor: Long)          extends RustCursor
+class RustExportJna(val export: Long)          extends RustExport
+class RustPoolJna(val pool: Long)              extends RustPool
 
 object RustBindingJna extends RustBinding {
-  type Conn = RustConnectionJna
-  type Stmt = RustPreparedStatementJna
+  type Conn   = RustConnectionJna
+  type Stmt   = RustPreparedStatementJna
+  type Cursor = RustCursorJna
+  type Export = RustExportJna
+  type Pool   = RustPoolJna
 
   val currentDir = System.getProperty("user.dir")
 
@@ -21,70 +29,224 @@ object RustBindingJna extends RustBinding {
   library.jdbc_initialize()
 
   override def newConnection(url: String): RustConnectionJna = {
-    new RustConnectionJna(library.newConnection(url))
+    val handleAndErr: HandleAndError = library.newConnection(url)
+    val result = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.error_len)) }.map(_ => new RustConnectionJna(handleAndErr.handle))
+
+    // The handle stays valid after dropping the envelope
+    library.destroy(handleAndErr)
+    result.get
   }
 
   override def prepareStatement(connection: RustConnectionJna, query: String): RustPreparedStatementJna = {
-    val ptrAndErr: PointerAndError = library.prepareStatement(connection.conn, query)
-    RustCallResult.fromString(ptrAndErr.error)
-    val result = new RustPreparedStatementJna(ptrAndErr.pointer)
+    val handleAndErr: HandleAndError = library.prepareStatement(connection.conn, query)
+    RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.error_len))
+    val result = new RustPreparedStatementJna(handleAndErr.handle)
 
-    // The pointer will still be valid after dropping the envelope
-    library.destroy(ptrAndErr)
+    // The handle stays valid after dropping the envelope
+    library.destroy(handleAndErr)
     result
   }
 
   override def startTransaction(connection: RustConnectionJna): RustCallResult = {
-    val ptr = library.startTransaction(connection.conn)
-    processCallResult(ptr)
+    val buffer = library.startTransaction(connection.conn)
+    processCallResult(buffer)
+  }
+
+  override def startTransactionWith(connection: RustConnectionJna, isolationLevel: Int, readOnly: Boolean, deferrable: Boolean): RustCallResult = {
+    val buffer = library.startTransactionWith(connection.conn, isolationLevel, readOnly, deferrable)
+    processCallResult(buffer)
+  }
+
+  override def setTransactionIsolation(connection: RustConnectionJna, isolationLevel: Int): RustCallResult = {
+    val buffer = library.setTransactionIsolation(connection.conn, isolationLevel)
+    processCallResult(buffer)
+  }
+
+  override def setReadOnly(connection: RustConnectionJna, readOnly: Boolean): RustCallResult = {
+    val buffer = library.setReadOnly(connection.conn, readOnly)
+    processCallResult(buffer)
+  }
+
+  override def executeTransaction(connection: RustConnectionJna, statements: String, maxAttempts: Int, backoffMs: Long): RustCallResult = {
+    val buffer = library.executeTransaction(connection.conn, statements, maxAttempts, backoffMs)
+    processCallResult(buffer)
   }
 
   override def commitTransaction(connection: RustConnectionJna): RustCallResult = {
-    val ptr = library.commitTransaction(connection.conn)
-    processCallResult(ptr)
+    val buffer = library.commitTransaction(connection.conn)
+    processCallResult(buffer)
   }
 
   override def rollbackTransaction(connection: RustConnectionJna): RustCallResult = {
-    val ptr = library.rollbackTransaction(connection.conn)
-    processCallResult(ptr)
+    val buffer = library.rollbackTransaction(connection.conn)
+    processCallResult(buffer)
+  }
+
+  override def createSavepoint(connection: RustConnectionJna, name: String): RustCallResult = {
+    val buffer = library.createSavepoint(connection.conn, name)
+    processCallResult(buffer)
+  }
+
+  override def releaseSavepoint(connection: RustConnectionJna, name: String): RustCallResult = {
+    val buffer = library.releaseSavepoint(connection.conn, name)
+    processCallResult(buffer)
+  }
+
+  override def rollbackToSavepoint(connection: RustConnectionJna, name: String): RustCallResult = {
+    val buffer = library.rollbackToSavepoint(connection.conn, name)
+    processCallResult(buffer)
   }
 
   override def closeConnection(connection: RustConnectionJna): RustCallResult = {
-    val ptr = library.closeConnection(connection.conn)
-    processCallResult(ptr)
+    val buffer = library.closeConnection(connection.conn)
+    processCallResult(buffer)
   }
 
   override def sqlExecute(connection: RustConnectionJna, query: String, params: String): RustCallResult = {
-    val ptr = library.sqlExecute(connection.conn, query, params)
-    processCallResult(ptr)
+    val buffer = library.sqlExecute(connection.conn, query, params)
+    processCallResult(buffer)
   }
 
   override def sqlQuery(connection: RustConnectionJna, query: String, params: String): RustCallResult = {
-    val ptr = library.sqlQuery(connection.conn, query, params)
-    processCallResult(ptr)
+    val buffer = library.sqlQuery(connection.conn, query, params)
+    processCallResult(buffer)
   }
 
   override def executePreparedstatement(stmt: RustPreparedStatementJna, params: String): RustCallResult = {
-    val ptr = library.executePreparedstatement(stmt.stmt, params)
-    processCallResult(ptr)
+    val buffer = library.executePreparedstatement(stmt.stmt, params)
+    processCallResult(buffer)
+  }
+
+  override def executePreparedstatementWith(stmt: RustPreparedStatementJna, params: String, errorPolicy: Int): RustCallResult = {
+    val buffer = library.executePreparedstatementWith(stmt.stmt, params, errorPolicy)
+    processCallResult(buffer)
   }
 
   override def queryPreparedstatement(stmt: RustPreparedStatementJna, params: String): RustCallResult = {
-    val ptr = library.queryPreparedstatement(stmt.stmt, params)
-    processCallResult(ptr)
+    val buffer = library.queryPreparedstatement(stmt.stmt, params)
+    processCallResult(buffer)
+  }
+
+  override def sqlQueryBinary(connection: RustConnectionJna, query: String, params: String): RustResultSet = {
+    val buffer = library.sqlQueryBinary(connection.conn, query, params)
+    processBinaryResult(buffer)
+  }
+
+  override def queryPreparedstatementBinary(stmt: RustPreparedStatementJna, params: String): RustResultSet = {
+    val buffer = library.queryPreparedstatementBinary(stmt.stmt, params)
+    processBinaryResult(buffer)
   }
 
   override def closeStatement(stmt: RustPreparedStatementJna): RustCallResult = {
-    val ptr = library.closeStatement(stmt.stmt)
-    processCallResult(ptr)
+    val buffer = library.closeStatement(stmt.stmt)
+    processCallResult(buffer)
+  }
+
+  override def openCursor(connection: RustConnectionJna, query: String, params: String): RustCursorJna = {
+    val handleAndErr: HandleAndError = library.openCursor(connection.conn, query, params)
+    val result                       = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.error_len)) }.map(_ => new RustCursorJna(handleAndErr.handle))
+
+    // The handle stays valid after dropping the envelope
+    library.destroy(handleAndErr)
+    result.get
+  }
+
+  override def fetchCursor(connection: RustConnectionJna, cursor: RustCursorJna, count: Int): RustCallResult = {
+    val buffer = library.fetchCursor(connection.conn, cursor.cursor, count)
+    processCallResult(buffer)
+  }
+
+  override def fetchCursorBinary(connection: RustConnectionJna, cursor: RustCursorJna, count: Int): RustResultSet = {
+    val buffer = library.fetchCursorBinary(connection.conn, cursor.cursor, count)
+    processBinaryResult(buffer)
+  }
+
+  override def closeCursor(connection: RustConnectionJna, cursor: RustCursorJna): RustCallResult = {
+    val buffer = library.closeCursor(connection.conn, cursor.cursor)
+    processCallResult(buffer)
+  }
+
+  override def copyIn(connection: RustConnectionJna, schema: String, table: String, columns: String, format: String, data: String): RustCallResult = {
+    val buffer = library.copyIn(connection.conn, schema, table, columns, format, data)
+    processCallResult(buffer)
+  }
+
+  override def openExport(connection: RustConnectionJna, query: String, format: String, chunkSize: Int): RustExportJna = {
+    val handleAndErr: HandleAndError = library.openExport(connection.conn, query, format, chunkSize)
+    val result                       = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.error_len)) }.map(_ => new RustExportJna(handleAndErr.handle))
+
+    // The handle stays valid after dropping the envelope
+    library.destroy(handleAndErr)
+    result.get
+  }
+
+  override def fetchExport(export: RustExportJna): Array[Byte] = {
+    val buffer = library.fetchExport(export.export)
+    processBytes(buffer)
+  }
+
+  override def closeExport(export: RustExportJna): RustCallResult = {
+    val buffer = library.closeExport(export.export)
+    processCallResult(buffer)
+  }
+
+  override def newPool(url: String, minSize: Int, maxSize: Int, idleTimeoutMs: Long): RustPoolJna = {
+    val handleAndErr: HandleAndError = library.newPool(url, minSize, maxSize, idleTimeoutMs)
+    val result                       = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.error_len)) }.map(_ => new RustPoolJna(handleAndErr.handle))
+
+    // The handle stays valid after dropping the envelope
+    library.destroy(handleAndErr)
+    result.get
+  }
+
+  override def checkoutConnection(pool: RustPoolJna): RustConnectionJna = {
+    val handleAndErr: HandleAndError = library.checkoutConnection(pool.pool)
+    val result                       = Try { RustCallResult.fromString(readString(handleAndErr.error, handleAndErr.error_len)) }.map(_ => new RustConnectionJna(handleAndErr.handle))
+
+    // The handle stays valid after dropping the envelope
+    library.destroy(handleAndErr)
+    result.get
+  }
+
+  override def checkinConnection(pool: RustPoolJna, connection: RustConnectionJna): RustCallResult = {
+    val buffer = library.checkinConnection(pool.pool, connection.conn)
+    processCallResult(buffer)
+  }
+
+  override def poolStats(pool: RustPoolJna): RustPoolStats = {
+    val buffer = library.poolStats(pool.pool)
+    processCallResult(buffer).stats.get
+  }
+
+  override def closePool(pool: RustPoolJna): RustCallResult = {
+    val buffer = library.closePool(pool.pool)
+    processCallResult(buffer)
   }
 
-  def processCallResult(ptr: Pointer) = {
-    val str = ptr.getString(0)
-    (Try { RustCallResult.fromString(str) } match {
-      case x @ _ =>
-        library.destroy_string(ptr)
-        x
-    }).get
+  def processCallResult(buffer: ProtocolBuffer): RustCallResult = processBuffer(buffer) { data =>
+    RustCallResult.fromString(readString(data, buffer.data_len))
   }
+
+  // The data is copied while decoding, the buffer can be destroyed afterwards.
+  def processBinaryResult(buffer: ProtocolBuffer): RustResultSet = processBuffer(buffer) { data =>
+    BinaryResultSet.decode(data.getByteBuffer(0, buffer.data_len.longValue()))
+  }
+
+  def processBytes(buffer: ProtocolBuffer): Array[Byte] = processBuffer(buffer) { data =>
+    data.getByteArray(0, buffer.data_len.intValue())
+  }
+
+  // Errors are serialized call results, reading them throws the contained error.
+  private def processBuffer[T](buffer: ProtocolBuffer)(read: Pointer => T): T = {
+    val result = Try {
+      Option(buffer.error).foreach(error => RustCallResult.fromString(readString(error, buffer.error_len)))
+      read(buffer.data)
+    }
+
+    library.destroy_buffer(buffer)
+    result.get
+  }
+
+  // Results are length delimited instead of NUL terminated.
+  def readString(ptr: Pointer, len: SizeT): String = new String(ptr.getByteArray(0, len.intValue()), StandardCharsets.UTF_8)
 }
@@ -0,0 +1,15 @@
+package com.prisma.native_jdbc.jna;
+
+import com.sun.jna.IntegerType;
+import com.sun.jna.Native;
+
+// A usize on the Rust side. NativeLong has the size of a C long, which is only 32 bits on 64-bit Windows.
+public class SizeT extends IntegerType {
+    public SizeT() {
+        this(0);
+    }
+
+    public SizeT(long value) {
+        super(Native.SIZE_T_SIZE, value, true);
+    }
+}
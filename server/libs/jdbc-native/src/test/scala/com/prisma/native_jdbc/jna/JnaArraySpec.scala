@@ -0,0 +1,107 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.{Date, SQLException, Time, Timestamp, Types}
+import java.time.OffsetTime
+import java.util.UUID
+
+import com.prisma.native_jdbc.CustomJdbcConnection
+import org.scalatest.{BeforeAndAfterAll, Matchers, WordSpec}
+
+class JnaArraySpec extends WordSpec with Matchers with BeforeAndAfterAll with JnaSpecFixture {
+  val table      = "jdbc_native_array_spec"
+  val connection = new CustomJdbcConnection(url, RustBindingJna)
+
+  override protected def beforeAll(): Unit = {
+    connection.prepareStatement(s"DROP TABLE IF EXISTS $table").execute()
+    connection.prepareStatement(s"CREATE TABLE $table (id int PRIMARY KEY, ints int4[], names text[][])").execute()
+  }
+
+  override protected def afterAll(): Unit = {
+    connection.prepareStatement(s"DROP TABLE IF EXISTS $table").execute()
+    connection.close()
+  }
+
+  def insert(id: Int, ints: java.sql.Array, names: java.sql.Array) = {
+    val statement = connection.prepareStatement(s"INSERT INTO $table (id, ints, names) VALUES (?, ?, ?)")
+    statement.setInt(1, id)
+    statement.setArray(2, ints)
+    statement.setArray(3, names)
+    statement.executeUpdate()
+  }
+
+  def select(id: Int) = {
+    val statement = connection.prepareStatement(s"SELECT ints, names FROM $table WHERE id = ?")
+    statement.setInt(1, id)
+    val resultSet = statement.executeQuery()
+    resultSet.next() should be(true)
+    resultSet
+  }
+
+  def roundTrip(typeName: String, elements: Array[AnyRef]): Array[AnyRef] = {
+    val statement = connection.prepareStatement(s"SELECT ?::$typeName[]")
+    statement.setArray(1, connection.createArrayOf(typeName, elements))
+    val resultSet = statement.executeQuery()
+    resultSet.next() should be(true)
+    resultSet.getArray(1).getArray.asInstanceOf[Array[AnyRef]]
+  }
+
+  "Array columns" should {
+    "round trip one-dimensional arrays with nulls" in {
+      insert(1, connection.createArrayOf("int4", Array[AnyRef](Int.box(1), null, Int.box(3))), null)
+
+      val resultSet = select(1)
+      val ints      = resultSet.getArray(1)
+      ints.getBaseType should be(Types.INTEGER)
+      ints.getBaseTypeName should be("int4")
+      ints.getArray should be(Array[AnyRef](Int.box(1), null, Int.box(3)))
+      resultSet.getObject(1).asInstanceOf[java.sql.Array].getArray should be(Array[AnyRef](Int.box(1), null, Int.box(3)))
+
+      resultSet.getArray(2) should be(null)
+      resultSet.wasNull() should be(true)
+    }
+
+    "round trip multi-dimensional arrays" in {
+      val names = Array[AnyRef](Array[AnyRef]("a", "b"), Array[AnyRef]("c", null))
+      insert(2, connection.createArrayOf("int4", Array.empty[AnyRef]), connection.createArrayOf("text", names))
+
+      val resultSet = select(2)
+      resultSet.getArray("ints").getArray should be(Array.empty[AnyRef])
+      resultSet.getArray("names").getArray should be(names)
+    }
+
+    "round trip the elements of every supported type" in {
+      val instant = Timestamp.valueOf("2019-03-04 05:06:07.000008")
+      val elements = Seq[(String, AnyRef)](
+        "int4"        -> Int.box(-7),
+        "int8"        -> Long.box(Long.MaxValue),
+        "numeric"     -> new java.math.BigDecimal("12345678901234567890.50"),
+        "text"        -> "größe",
+        "bool"        -> Boolean.box(true),
+        "uuid"        -> UUID.fromString("3a9c3a3e-5d1b-4a6a-9a8e-2f3c1d0b7e61"),
+        "jsonb"       -> """{"a":[1,"x"]}""",
+        "bytea"       -> Array[Byte](0, 1, -1),
+        "timestamp"   -> instant,
+        "timestamptz" -> instant,
+        "date"        -> Date.valueOf("2019-03-04"),
+        "time"        -> Time.valueOf("10:11:12")
+      )
+
+      for ((typeName, element) <- elements) {
+        roundTrip(typeName, Array[AnyRef](element, null)) should be(Array[AnyRef](element, null))
+      }
+
+      val zoned = OffsetTime.parse("10:11:12+02:00")
+      roundTrip("timetz", Array[AnyRef](zoned)) should be(Array[AnyRef](Time.valueOf("08:11:12")))
+    }
+
+    "round trip doubles that have no JSON number" in {
+      val doubles = Array[AnyRef](Double.box(1.5), Double.box(Double.NaN), Double.box(Double.PositiveInfinity), Double.box(Double.NegativeInfinity))
+      roundTrip("float8", doubles).map(_.toString) should be(doubles.map(_.toString))
+    }
+
+    "reject unsupported element types" in {
+      an[SQLException] should be thrownBy connection.createArrayOf("point", Array.empty[AnyRef])
+      an[SQLException] should be thrownBy insert(3, connection.createArrayOf("int4", Array[AnyRef]("not a number")), null)
+    }
+  }
+}
@@ -0,0 +1,103 @@
+package com.prisma.native_jdbc.jna
+
+import com.prisma.native_jdbc.{BatchErrorPolicy, RustBatchError, RustBatchUpdateException}
+import org.scalatest.{BeforeAndAfterEach, Matchers, WordSpec}
+
+class JnaBatchErrorSpec extends WordSpec with Matchers with JnaTableFixture with BeforeAndAfterEach {
+  val table   = "jdbc_native_batch_error_spec"
+  val columns = "id int PRIMARY KEY, name text NOT NULL"
+  val insert  = s"INSERT INTO $table (id, name) VALUES ($$1, $$2)"
+
+  override protected def beforeEach(): Unit = {
+    binding.sqlExecute(connection, s"DELETE FROM $table", "[]")
+  }
+
+  def failBatch(ids: Seq[Int], policy: Int): RustBatchUpdateException = {
+    val stmt = binding.prepareStatement(connection, insert)
+    try intercept[RustBatchUpdateException](binding.executePreparedstatementWith(stmt, rows(ids), policy))
+    finally binding.closeStatement(stmt)
+  }
+
+  val duplicate = RustBatchError(2, "23505", s"""duplicate key value violates unique constraint "${table}_pkey"""", Some(s"${table}_pkey"))
+
+  "A failed batch" should {
+    "report the error of every failed row" in {
+      val e = failBatch(Seq(1, 2, 1, 3, 2), BatchErrorPolicy.Continue)
+
+      e.getUpdateCounts.toVector should be(Vector(1, 1, -3, 1, -3))
+      e.errors should be(Vector(duplicate, duplicate.copy(index = 4)))
+      e.getSQLState should be("23505")
+      e.getNextException.getMessage should include("Row 2")
+      count() should be(3)
+    }
+
+    "stop at the first failed row" in {
+      val e = failBatch(Seq(1, 2, 1, 3), BatchErrorPolicy.StopAtFirstError)
+
+      e.getUpdateCounts.toVector should be(Vector(1, 1, -3))
+      e.errors should be(Vector(duplicate))
+      count() should be(2)
+    }
+
+    "roll back the whole batch" in {
+      val e = failBatch(Seq(1, 2, 1, 3), BatchErrorPolicy.RollBack)
+
+      e.getUpdateCounts.toVector should be(Vector(-3, -3, -3, -3))
+      e.errors should be(Vector(duplicate))
+      count() should be(0)
+    }
+
+    "roll back only the batch within a transaction" in {
+      binding.startTransaction(connection)
+      binding.sqlExecute(connection, s"INSERT INTO $table (id, name) VALUES (100, 'before')", "[]")
+
+      failBatch(Seq(1, 2, 1), BatchErrorPolicy.RollBack).errors.map(_.index) should be(Vector(2))
+      binding.sqlExecute(connection, s"INSERT INTO $table (id, name) VALUES (101, 'after')", "[]")
+      binding.commitTransaction(connection)
+
+      count() should be(2)
+    }
+
+    "execute the rows after a failed row within a transaction" in {
+      binding.startTransaction(connection)
+      binding.sqlExecute(connection, s"INSERT INTO $table (id, name) VALUES (100, 'before')", "[]")
+
+      val e = failBatch(Seq(1, 2, 1, 3), BatchErrorPolicy.Continue)
+      e.getUpdateCounts.toVector should be(Vector(1, 1, -3, 1))
+      e.errors should be(Vector(duplicate))
+
+      binding.sqlExecute(connection, s"INSERT INTO $table (id, name) VALUES (101, 'after')", "[]")
+      binding.commitTransaction(connection)
+
+      ids() should be(Vector(1, 2, 3, 100, 101))
+    }
+
+    "roll back only the batch within a transaction without closing an open cursor" in {
+      binding.startTransaction(connection)
+      binding.sqlExecute(connection, s"INSERT INTO $table (id, name) VALUES (100, 'before')", "[]")
+      val cursor = binding.openCursor(connection, "SELECT generate_series(1, 3)", "[]")
+
+      failBatch(Seq(1, 2, 1), BatchErrorPolicy.RollBack).errors.map(_.index) should be(Vector(2))
+
+      binding.fetchCursor(connection, cursor, 10).rows.get.data should have size 3
+      binding.closeCursor(connection, cursor)
+      binding.commitTransaction(connection)
+
+      ids() should be(Vector(100))
+    }
+
+    "report rows failing for reasons other than constraints" in {
+      val stmt = binding.prepareStatement(connection, insert)
+      val params =
+        """[[{"discriminator": "Int", "value": 1}, {"discriminator": "Null", "value": null}],
+          | [{"discriminator": "Int", "value": 2}, {"discriminator": "String", "value": "row 2"}]]""".stripMargin
+
+      val e =
+        try intercept[RustBatchUpdateException](binding.executePreparedstatementWith(stmt, params, BatchErrorPolicy.Continue))
+        finally binding.closeStatement(stmt)
+
+      e.errors.map(e => (e.index, e.code)) should be(Vector((0, "23502")))
+      e.getUpdateCounts.toVector should be(Vector(-3, 1))
+    }
+  }
+}
@@ -0,0 +1,55 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.BatchUpdateException
+
+import org.scalatest.{BeforeAndAfterEach, Matchers, WordSpec}
+
+class JnaBatchSpec extends WordSpec with Matchers with JnaTableFixture with BeforeAndAfterEach {
+  val table   = "jdbc_native_batch_spec"
+  val columns = "id int PRIMARY KEY, name text NOT NULL"
+
+  override protected def beforeEach(): Unit = {
+    binding.sqlExecute(connection, s"DELETE FROM $table", "[]")
+  }
+
+  def executeBatch(query: String, params: String) = {
+    val stmt = binding.prepareStatement(connection, query)
+    try binding.executePreparedstatement(stmt, params).counts
+    finally binding.closeStatement(stmt)
+  }
+
+  "Batch execution" should {
+    "insert batches larger than a single multi-row insert" in {
+      executeBatch(s"INSERT INTO $table (id, name) VALUES ($$1, $$2)", rows(1 to 2500)) should be(Vector.fill(2500)(1))
+      count() should be(2500)
+    }
+
+    "report the failed rows of a chunk that fails" in {
+      val e = intercept[BatchUpdateException] {
+        executeBatch(s"INSERT INTO $table (id, name) VALUES ($$1, $$2)", rows(Seq(1, 2, 1, 3)))
+      }
+
+      e.getUpdateCounts.toVector should be(Vector(1, 1, -3, 1))
+      count() should be(3)
+    }
+
+    "keep the transaction usable if a chunk fails" in {
+      binding.startTransaction(connection)
+      binding.sqlExecute(connection, s"INSERT INTO $table (id, name) VALUES (100, 'before')", "[]")
+      executeBatch(s"INSERT INTO $table (id, name) VALUES ($$1, $$2)", rows(Seq(1, 2, 3))) should be(Vector(1, 1, 1))
+      intercept[BatchUpdateException] {
+        executeBatch(s"INSERT INTO $table (id, name) VALUES ($$1, $$2)", rows(Seq(4, 100)))
+      }.getUpdateCounts.last should be(-3)
+      binding.rollbackTransaction(connection)
+
+      count() should be(0)
+    }
+
+    "execute statements that can't be rewritten row by row" in {
+      binding.sqlExecute(connection, s"INSERT INTO $table (id, name) VALUES (2, 'existing')", "[]")
+
+      executeBatch(s"INSERT INTO $table (id, name) VALUES ($$1, $$2) ON CONFLICT DO NOTHING", rows(1 to 3)) should be(Vector(1, 0, 1))
+      executeBatch(s"UPDATE $table SET name = $$2 WHERE id = $$1", rows(Seq(1, 4))) should be(Vector(1, 0))
+    }
+  }
+}
@@ -0,0 +1,67 @@
+package com.prisma.native_jdbc.jna
+
+import java.nio.ByteBuffer
+import java.nio.charset.StandardCharsets
+
+import com.prisma.native_jdbc.BinaryResultSet
+import org.scalatest.{Matchers, WordSpec}
+import play.api.libs.json.{JsNull, JsNumber, JsString}
+
+class JnaBinaryResultSpec extends WordSpec with Matchers with JnaSpecFixture {
+  def sameResults(query: String) = withConnection { connection =>
+    binding.sqlQueryBinary(connection, query, "[]") should be(binding.sqlQuery(connection, query, "[]").rows.get)
+  }
+
+  // A result of a single column and row with the given value.
+  def encoded(discriminator: String, length: Int, value: Array[Byte]): ByteBuffer = {
+    val buffer = ByteBuffer.allocate(1024)
+    def string(s: String) = {
+      val bytes = s.getBytes(StandardCharsets.UTF_8)
+      buffer.putInt(bytes.length).put(bytes)
+    }
+
+    buffer.put(BinaryResultSet.FormatVersion.toByte).putInt(1).putInt(1)
+    Seq("value", discriminator, "", "").foreach(string)
+    buffer.putInt(length).put(value)
+    buffer.flip()
+    buffer
+  }
+
+  def decoded(discriminator: String, length: Int, value: Array[Byte]) =
+    BinaryResultSet.decode(encoded(discriminator, length, value)).data.head.value.head
+
+  "The binary result format" should {
+    "read numbers of every width like the JSON format" in {
+      sameResults("SELECT true, 1::int2, (-2147483648)::int4, 9223372036854775807::int8, 4294967295::oid, 1.5::float4, 0.1::float8")
+    }
+
+    "read NULL and non-finite doubles like the JSON format" in {
+      sameResults("SELECT NULL::int4, NULL::text, 'NaN'::float8, 'Infinity'::float4, '-Infinity'::float8")
+    }
+
+    "read values transmitted as JSON text like the JSON format" in {
+      sameResults(
+        """SELECT '{"a": [1, "x"]}'::jsonb, '2019-03-04 05:06:07.000008'::timestamp, '1 day 00:00:01'::interval, ARRAY[1, NULL, 3], int4range(1, 5), 'text'""")
+    }
+
+    "decode values by their discriminator" in {
+      decoded("Boolean", 1, Array(1)) should be(play.api.libs.json.JsBoolean(true))
+      decoded("Int", 4, ByteBuffer.allocate(4).putInt(-7).array()) should be(JsNumber(-7))
+      decoded("Long", 8, ByteBuffer.allocate(8).putLong(Long.MinValue).array()) should be(JsNumber(Long.MinValue))
+      decoded("Double", 8, ByteBuffer.allocate(8).putDouble(Double.NaN).array()) should be(JsString("NaN"))
+      decoded("String", 2, "ab".getBytes) should be(JsString("ab"))
+      decoded("Int", -1, Array.empty) should be(JsNull)
+    }
+
+    "fail on values with a length other than their width" in {
+      an[RuntimeException] should be thrownBy decoded("Int", 8, ByteBuffer.allocate(8).putLong(1).array())
+      an[RuntimeException] should be thrownBy decoded("Long", 4, ByteBuffer.allocate(4).putInt(1).array())
+      an[RuntimeException] should be thrownBy decoded("Boolean", 0, Array.empty)
+    }
+
+    "fail on lengths beyond the end of the result" in {
+      an[RuntimeException] should be thrownBy decoded("String", 100, "ab".getBytes)
+      an[RuntimeException] should be thrownBy decoded("String", -2, Array.empty)
+    }
+  }
+}
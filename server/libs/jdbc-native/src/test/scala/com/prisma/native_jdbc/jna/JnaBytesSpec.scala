@@ -0,0 +1,64 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.SQLException
+
+import com.prisma.native_jdbc.CustomJdbcConnection
+import org.scalatest.{BeforeAndAfterAll, Matchers, WordSpec}
+
+class JnaBytesSpec extends WordSpec with Matchers with BeforeAndAfterAll with JnaSpecFixture {
+  val table      = "jdbc_native_bytes_spec"
+  val connection = new CustomJdbcConnection(url, RustBindingJna)
+
+  override protected def beforeAll(): Unit = {
+    connection.prepareStatement(s"DROP TABLE IF EXISTS $table").execute()
+    connection.prepareStatement(s"CREATE TABLE $table (id int PRIMARY KEY, data bytea)").execute()
+  }
+
+  override protected def afterAll(): Unit = {
+    connection.prepareStatement(s"DROP TABLE IF EXISTS $table").execute()
+    connection.close()
+  }
+
+  def roundTrip(id: Int, bytes: Array[Byte]): Array[Byte] = {
+    val insert = connection.prepareStatement(s"INSERT INTO $table (id, data) VALUES (?, ?)")
+    insert.setInt(1, id)
+    insert.setBytes(2, bytes)
+    insert.executeUpdate()
+
+    val select = connection.prepareStatement(s"SELECT data FROM $table WHERE id = ?")
+    select.setInt(1, id)
+    val resultSet = select.executeQuery()
+    resultSet.next() should be(true)
+    resultSet.getBytes("data")
+  }
+
+  "Bytea columns" should {
+    "round trip every byte value" in {
+      val bytes = (0 until 256).map(_.toByte).toArray
+      roundTrip(1, bytes) should be(bytes)
+    }
+
+    "round trip empty and null values" in {
+      roundTrip(2, Array.empty[Byte]) should be(Array.empty[Byte])
+      roundTrip(3, null) should be(null)
+    }
+
+    "read values computed by the database" in {
+      val resultSet = connection.prepareStatement("SELECT decode('00ff10', 'hex') AS data").executeQuery()
+      resultSet.next() should be(true)
+      resultSet.getBytes(1) should be(Array[Byte](0, -1, 16))
+    }
+
+    "reject Bytes parameters for columns of other types" in {
+      val statement = connection.prepareStatement("SELECT ?::int4")
+      statement.setBytes(1, Array[Byte](1, 2))
+      an[SQLException] should be thrownBy statement.executeQuery()
+    }
+
+    "reject Bytes parameters that aren't base64" in {
+      withConnection { connection =>
+        an[SQLException] should be thrownBy binding.sqlQuery(connection, "SELECT $1::bytea", """[{"discriminator": "Bytes", "value": "not base64!"}]""")
+      }
+    }
+  }
+}
@@ -0,0 +1,43 @@
+package com.prisma.native_jdbc.jna
+
+import org.scalatest.{Matchers, WordSpec}
+
+import scala.concurrent.duration._
+import scala.concurrent.{Await, Future}
+import scala.concurrent.ExecutionContext.Implicits.global
+
+class JnaConcurrencySpec extends WordSpec with Matchers with JnaSpecFixture {
+  "A connection used from multiple threads" should {
+    "serialize the calls" in {
+      val connection = binding.newConnection(url)
+      val stmt       = binding.prepareStatement(connection, "SELECT $1::int4")
+
+      val calls = (1 to 200).map { i =>
+        Future {
+          if (i % 2 == 0) {
+            binding.sqlQuery(connection, "SELECT pg_sleep(0.001), 1", "[]").rows.get.data should have size 1
+          } else {
+            val rows = binding.queryPreparedstatement(stmt, s"""[{"discriminator": "Int", "value": $i}]""").rows.get.data
+            rows.head.value.head.as[Int] should be(i)
+          }
+        }
+      }
+
+      Await.result(Future.sequence(calls), 60.seconds)
+
+      binding.closeStatement(stmt)
+      binding.closeConnection(connection)
+    }
+
+    "keep transactions intact across threads" in {
+      val connection = binding.newConnection(url)
+
+      Await.result(Future { binding.startTransactionWith(connection, 8, false, false) }, 10.seconds)
+      val isolation = Await.result(Future { binding.sqlQuery(connection, "SHOW transaction_isolation", "[]") }, 10.seconds)
+      Await.result(Future { binding.commitTransaction(connection) }, 10.seconds)
+
+      isolation.rows.get.data.head.value.head.as[String] should be("serializable")
+      binding.closeConnection(connection)
+    }
+  }
+}
@@ -0,0 +1,42 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.SQLException
+
+import org.scalatest.{Matchers, WordSpec}
+
+class JnaCopySpec extends WordSpec with Matchers with JnaTableFixture {
+  val table   = "jdbc_native_copy_spec"
+  val columns = "id int PRIMARY KEY, name text, score float8"
+
+  "COPY FROM STDIN" should {
+    "load rows in the parameter format" in {
+      val rows = (1 to 1000)
+        .map(i => s"""[{"discriminator": "Int", "value": $i}, {"discriminator": "String", "value": "row $i"}, {"discriminator": "Null", "value": null}]""")
+        .mkString("[", ",", "]")
+
+      binding.copyIn(connection, "", table, """["id", "name", "score"]""", "json", rows).counts should be(Vector(1000))
+      count("id <= 1000 AND score IS NULL") should be(1000)
+    }
+
+    "load rows as CSV" in {
+      val rows = (1001 to 1010).map(i => s"$i,\"row, $i\",$i.5").mkString("\n")
+
+      binding.copyIn(connection, "public", table, """["id", "name", "score"]""", "csv", rows).counts should be(Vector(10))
+      count("id > 1000 AND name LIKE 'row, %'") should be(10)
+    }
+
+    "load nothing if a row fails" in {
+      val rows = """[[{"discriminator": "Int", "value": 2001}], [{"discriminator": "Int", "value": 1}]]"""
+
+      an[SQLException] should be thrownBy binding.copyIn(connection, "", table, """["id"]""", "json", rows)
+      count("id = 2001") should be(0)
+    }
+
+    "reject rows with the wrong number of values" in {
+      val rows = """[[{"discriminator": "Int", "value": 3001}, {"discriminator": "Int", "value": 1}]]"""
+
+      an[SQLException] should be thrownBy binding.copyIn(connection, "", table, """["id"]""", "json", rows)
+      count("id = 3001") should be(0)
+    }
+  }
+}
@@ -0,0 +1,107 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.SQLException
+
+import com.prisma.native_jdbc.{CustomJdbcConnection, CustomPreparedStatement}
+import org.scalatest.{Matchers, WordSpec}
+
+class JnaCursorSpec extends WordSpec with Matchers with JnaTableFixture {
+  val table   = "jdbc_native_cursor_spec"
+  val columns = "id int PRIMARY KEY"
+  val numbers = "SELECT generate_series(1, 10)"
+
+  def fetch(connection: RustConnectionJna, cursor: RustCursorJna, count: Int): Vector[Int] =
+    binding.fetchCursor(connection, cursor, count).rows.get.data.map(row => row.value.head.as[Int]).toVector
+
+  "A cursor" should {
+    "only be opened in a transaction" in {
+      withConnection { connection =>
+        an[SQLException] should be thrownBy binding.openCursor(connection, numbers, "[]")
+      }
+    }
+  }
+
+  "A cursor opened in a transaction" should {
+    "fetch the rows in chunks" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        val cursor = binding.openCursor(connection, numbers, "[]")
+
+        fetch(connection, cursor, 4) should be(Vector(1, 2, 3, 4))
+        fetch(connection, cursor, 4) should be(Vector(5, 6, 7, 8))
+        fetch(connection, cursor, 4) should be(Vector(9, 10))
+        binding.closeCursor(connection, cursor)
+        binding.commitTransaction(connection)
+      }
+    }
+
+    "stay open across statements of the transaction" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        val cursor = binding.openCursor(connection, numbers, "[]")
+
+        fetch(connection, cursor, 2) should be(Vector(1, 2))
+        binding.sqlExecute(connection, s"INSERT INTO $table (id) VALUES (3)", "[]")
+        fetch(connection, cursor, 2) should be(Vector(3, 4))
+
+        binding.closeCursor(connection, cursor)
+        binding.commitTransaction(connection)
+      }
+
+      ids() should contain(3)
+    }
+
+    "end with the transaction" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        val cursor = binding.openCursor(connection, numbers, "[]")
+        binding.commitTransaction(connection)
+
+        an[SQLException] should be thrownBy fetch(connection, cursor, 1)
+        binding.closeCursor(connection, cursor)
+
+        binding.startTransaction(connection)
+        an[SQLException] should be thrownBy fetch(connection, cursor, 1)
+        binding.rollbackTransaction(connection)
+      }
+    }
+  }
+
+  "A statement with a fetch size" should {
+    def query(autoCommit: Boolean, sql: String) = {
+      val connection = new CustomJdbcConnection(url, binding)
+      try {
+        connection.setAutoCommit(autoCommit)
+        val statement = connection.prepareStatement(sql)
+        statement.setFetchSize(4)
+
+        val resultSet = statement.executeQuery()
+        val fetchSize = resultSet.getFetchSize
+        val rows      = Iterator.continually(resultSet.next()).takeWhile(identity).map(_ => resultSet.getInt(1)).toVector
+        if (!autoCommit) connection.commit()
+        (fetchSize, rows)
+      } finally {
+        connection.close()
+      }
+    }
+
+    "read the rows through a cursor in a transaction" in {
+      query(autoCommit = false, numbers) should be((4, (1 to 10).toVector))
+      query(autoCommit = false, s"  -- the numbers\n/* all of them */ WITH n AS ($numbers AS i) SELECT i FROM n") should be((4, (1 to 10).toVector))
+    }
+
+    "read all rows at once in autocommit mode" in {
+      query(autoCommit = true, numbers) should be((0, (1 to 10).toVector))
+    }
+
+    "tell the queries DECLARE accepts" in {
+      CustomPreparedStatement.isCursorQuery("SELECT 1") should be(true)
+      CustomPreparedStatement.isCursorQuery("\n\t(select 1)") should be(true)
+      CustomPreparedStatement.isCursorQuery("/* hint */ -- line\nWITH x AS (SELECT 1) SELECT * FROM x") should be(true)
+      CustomPreparedStatement.isCursorQuery("VALUES (1)") should be(true)
+      CustomPreparedStatement.isCursorQuery("INSERT INTO t VALUES (1) RETURNING id") should be(false)
+      CustomPreparedStatement.isCursorQuery("selector") should be(false)
+      CustomPreparedStatement.isCursorQuery("-- SELECT") should be(false)
+    }
+  }
+}
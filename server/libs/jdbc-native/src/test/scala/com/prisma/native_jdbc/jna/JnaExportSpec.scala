@@ -0,0 +1,69 @@
+package com.prisma.native_jdbc.jna
+
+import java.io.ByteArrayOutputStream
+import java.nio.charset.StandardCharsets
+import java.sql.SQLException
+
+import org.scalatest.{Matchers, WordSpec}
+import play.api.libs.json.Json
+
+class JnaExportSpec extends WordSpec with Matchers with JnaTableFixture {
+  val table   = "jdbc_native_export_spec"
+  val columns = "id int PRIMARY KEY, name text"
+
+  override protected def beforeAll(): Unit = {
+    super.beforeAll()
+    binding.sqlExecute(connection, s"INSERT INTO $table SELECT i, 'row, ' || i FROM generate_series(1, 1000) i", "[]")
+  }
+
+  def exportAll(query: String, format: String): (String, Vector[Int]) = {
+    val export = binding.openExport(connection, query, format, 1024)
+    val out    = new ByteArrayOutputStream()
+
+    Iterator.continually(binding.fetchExport(export)).takeWhile(_.nonEmpty).foreach(chunk => out.write(chunk))
+    (new String(out.toByteArray, StandardCharsets.UTF_8), binding.closeExport(export).counts)
+  }
+
+  "COPY TO STDOUT" should {
+    "export CSV with a header line" in {
+      val (csv, counts) = exportAll(s"SELECT id, name FROM $table ORDER BY id", "csv")
+      val lines         = csv.split("\n")
+
+      counts should be(Vector(1000))
+      lines.length should be(1001)
+      lines.head should be("id,name")
+      lines(1) should be("1,\"row, 1\"")
+    }
+
+    "export NDJSON with one object per row" in {
+      val (ndjson, counts) = exportAll(s"SELECT id, name FROM $table WHERE id <= 10 ORDER BY id", "ndjson")
+      val rows             = ndjson.split("\n").map(Json.parse)
+
+      counts should be(Vector(10))
+      rows.length should be(10)
+      (rows.head \ "id").as[Int] should be(1)
+      (rows.head \ "name").as[String] should be("row, 1")
+    }
+
+    "keep the connection busy until the export is closed" in {
+      val export = binding.openExport(connection, s"SELECT id FROM $table", "csv", 1024)
+
+      an[SQLException] should be thrownBy binding.sqlQuery(connection, "SELECT 1", "[]")
+      binding.closeExport(export)
+      binding.sqlQuery(connection, "SELECT 1", "[]").rows should not be empty
+    }
+
+    "cancel the export if it is closed early" in {
+      val export = binding.openExport(connection, s"SELECT id, name FROM $table", "csv", 16)
+
+      binding.fetchExport(export) should not be empty
+      binding.closeExport(export).counts should be(empty)
+      binding.sqlQuery(connection, "SELECT 1", "[]").rows should not be empty
+    }
+
+    "fail to open for an invalid query" in {
+      an[SQLException] should be thrownBy binding.openExport(connection, "SELECT FROM nowhere_at_all", "csv", 1024)
+      an[SQLException] should be thrownBy binding.openExport(connection, s"SELECT id FROM $table", "xml", 1024)
+    }
+  }
+}
@@ -0,0 +1,55 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.SQLException
+
+import org.scalatest.{Matchers, WordSpec}
+
+class JnaHandleSpec extends WordSpec with Matchers with JnaSpecFixture {
+  def invalidHandle(fn: => Any) = {
+    val e = the[SQLException] thrownBy fn
+    e.getSQLState should be("-5")
+  }
+
+  "Native handles" should {
+    "reject closing a statement twice" in {
+      val connection = binding.newConnection(url)
+      val stmt       = binding.prepareStatement(connection, "SELECT 1")
+
+      binding.closeStatement(stmt)
+      invalidHandle(binding.closeStatement(stmt))
+      binding.closeConnection(connection)
+    }
+
+    "reject using a connection after it was closed" in {
+      val connection = binding.newConnection(url)
+      binding.closeConnection(connection)
+
+      invalidHandle(binding.sqlQuery(connection, "SELECT 1", "[]"))
+      invalidHandle(binding.closeConnection(connection))
+    }
+
+    "invalidate statements and cursors when their connection is closed" in {
+      val connection = binding.newConnection(url)
+      val stmt       = binding.prepareStatement(connection, "SELECT 1")
+      binding.startTransaction(connection)
+      val cursor = binding.openCursor(connection, "SELECT 1", "[]")
+
+      binding.closeConnection(connection)
+
+      invalidHandle(binding.queryPreparedstatement(stmt, "[]"))
+      invalidHandle(binding.fetchCursor(connection, cursor, 10))
+      invalidHandle(binding.closeStatement(stmt))
+    }
+
+    "keep statements usable while their connection is open" in {
+      val connection = binding.newConnection(url)
+      val stmt       = binding.prepareStatement(connection, "SELECT 1")
+
+      binding.queryPreparedstatement(stmt, "[]").rows.get.data should have size 1
+      binding.queryPreparedstatement(stmt, "[]").rows.get.data should have size 1
+
+      binding.closeStatement(stmt)
+      binding.closeConnection(connection)
+    }
+  }
+}
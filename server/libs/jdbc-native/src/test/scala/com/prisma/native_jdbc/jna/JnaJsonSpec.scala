@@ -0,0 +1,53 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.SQLException
+
+import com.prisma.native_jdbc.CustomJdbcConnection
+import org.scalatest.{BeforeAndAfterAll, Matchers, WordSpec}
+import play.api.libs.json.{JsString, Json}
+
+class JnaJsonSpec extends WordSpec with Matchers with BeforeAndAfterAll with JnaSpecFixture {
+  val table      = "jdbc_native_json_spec"
+  val connection = new CustomJdbcConnection(url, RustBindingJna)
+
+  override protected def beforeAll(): Unit = {
+    connection.prepareStatement(s"DROP TABLE IF EXISTS $table").execute()
+    connection.prepareStatement(s"CREATE TABLE $table (id int PRIMARY KEY, plain json, binary jsonb)").execute()
+  }
+
+  override protected def afterAll(): Unit = {
+    connection.prepareStatement(s"DROP TABLE IF EXISTS $table").execute()
+    connection.close()
+  }
+
+  "Json columns" should {
+    "round trip json and jsonb values" in {
+      val document = Json.parse("""{"a": [1, null, "x"], "b": {"c": true}}""")
+
+      val insert = connection.prepareStatement(s"INSERT INTO $table (id, plain, binary) VALUES (?, ?, ?)")
+      insert.setInt(1, 1)
+      insert.setObject(2, document)
+      insert.setObject(3, JsString("just a string"))
+      insert.executeUpdate() should be(1)
+
+      val resultSet = connection.prepareStatement(s"SELECT plain, binary FROM $table WHERE id = 1").executeQuery()
+      resultSet.next() should be(true)
+      Json.parse(resultSet.getString("plain")) should be(document)
+      Json.parse(resultSet.getString("binary")) should be(JsString("just a string"))
+      Json.parse(resultSet.getObject(1).asInstanceOf[String]) should be(document)
+    }
+
+    "embed the values in the result instead of their text" in {
+      withConnection { connection =>
+        single(connection, """SELECT '{"a": [1, 2]}'::jsonb""") should be(Json.obj("a" -> Json.arr(1, 2)))
+        single(connection, """SELECT '"text"'::json""") should be(JsString("text"))
+      }
+    }
+
+    "reject Json parameters for columns of other types" in {
+      val statement = connection.prepareStatement("SELECT ?::int4")
+      statement.setObject(1, Json.obj("a" -> 1))
+      an[SQLException] should be thrownBy statement.executeQuery()
+    }
+  }
+}
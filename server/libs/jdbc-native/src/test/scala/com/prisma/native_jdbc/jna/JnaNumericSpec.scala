@@ -0,0 +1,47 @@
+package com.prisma.native_jdbc.jna
+
+import java.math.BigDecimal
+
+import com.prisma.native_jdbc.CustomJdbcConnection
+import org.scalatest.{BeforeAndAfterAll, Matchers, WordSpec}
+
+class JnaNumericSpec extends WordSpec with Matchers with BeforeAndAfterAll with JnaSpecFixture {
+  val connection = new CustomJdbcConnection(url, binding)
+
+  override protected def afterAll(): Unit = connection.close()
+
+  def roundTrip(long: Long, decimal: BigDecimal) = {
+    val statement = connection.prepareStatement("SELECT ?::int8 AS l, ?::numeric AS d")
+    statement.setLong(1, long)
+    statement.setBigDecimal(2, decimal)
+
+    val resultSet = statement.executeQuery()
+    resultSet.next() should be(true)
+    (resultSet.getLong("l"), resultSet.getBigDecimal("d"))
+  }
+
+  "Numbers" should {
+    "keep INT8 values that don't fit a double" in {
+      val beyondDouble = (1L << 53) + 1
+      roundTrip(beyondDouble, BigDecimal.ONE)._1 should be(beyondDouble)
+      roundTrip(Long.MaxValue, BigDecimal.ONE)._1 should be(Long.MaxValue)
+      roundTrip(Long.MinValue, BigDecimal.ONE)._1 should be(Long.MinValue)
+    }
+
+    "keep all digits of NUMERIC values" in {
+      for (digits <- Seq("12345678901234567890.123456789", "-0.000000000000000000012", "1" + "0" * 40, "1.10")) {
+        val decimal = new BigDecimal(digits)
+        roundTrip(1, decimal)._2 should be(decimal)
+      }
+    }
+
+    "read NUMERIC values computed by the database" in {
+      val statement = connection.prepareStatement("SELECT 2::numeric ^ 100 AS d, 9007199254740993::int8 AS l")
+      val resultSet = statement.executeQuery()
+      resultSet.next() should be(true)
+
+      resultSet.getBigDecimal("d").toBigIntegerExact should be(java.math.BigInteger.valueOf(2).pow(100))
+      resultSet.getLong("l") should be(9007199254740993L)
+    }
+  }
+}
@@ -0,0 +1,111 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.SQLException
+
+import com.prisma.native_jdbc.RustPoolStats
+import org.scalatest.{Matchers, WordSpec}
+
+class JnaPoolSpec extends WordSpec with Matchers with JnaSpecFixture {
+  def withPool[T](minSize: Int, maxSize: Int, idleTimeoutMs: Long = 0)(fn: RustPoolJna => T): T = {
+    val pool = binding.newPool(url, minSize, maxSize, idleTimeoutMs)
+    try fn(pool)
+    finally binding.closePool(pool)
+  }
+
+  def backendPid(connection: RustConnectionJna) = single(connection, "SELECT pg_backend_pid()").as[Int]
+
+  "A connection pool" should {
+    "open min size connections right away" in withPool(2, 4) { pool =>
+      binding.poolStats(pool) should be(RustPoolStats(idle = 2, active = 0, total = 2, min = 2, max = 4, replaced = 0))
+    }
+
+    "hand out checked in connections again" in withPool(0, 1) { pool =>
+      val first = binding.checkoutConnection(pool)
+      val pid   = backendPid(first)
+
+      binding.poolStats(pool).active should be(1)
+      binding.checkinConnection(pool, first)
+      binding.poolStats(pool).idle should be(1)
+
+      val second = binding.checkoutConnection(pool)
+      backendPid(second) should be(pid)
+      binding.checkinConnection(pool, second)
+    }
+
+    "reset the session state on checkin" in withPool(0, 1) { pool =>
+      val first = binding.checkoutConnection(pool)
+      binding.sqlExecute(first, "SET application_name = 'jdbc_native_pool_spec'", "[]")
+      binding.sqlExecute(first, "CREATE TEMP TABLE jdbc_native_pool_spec (id int)", "[]")
+      binding.startTransaction(first)
+      binding.checkinConnection(pool, first)
+
+      val second = binding.checkoutConnection(pool)
+      single(second, "SHOW application_name").as[String] should not be "jdbc_native_pool_spec"
+      single(second, "SELECT to_regclass('jdbc_native_pool_spec') IS NULL").as[Boolean] should be(true)
+      binding.startTransaction(second)
+      binding.rollbackTransaction(second)
+      binding.checkinConnection(pool, second)
+    }
+
+    "invalidate the handle of a checked in connection" in withPool(0, 1) { pool =>
+      val connection = binding.checkoutConnection(pool)
+      binding.checkinConnection(pool, connection)
+
+      (the[SQLException] thrownBy binding.sqlQuery(connection, "SELECT 1", "[]")).getSQLState should be("-5")
+      (the[SQLException] thrownBy binding.checkinConnection(pool, connection)).getSQLState should be("-5")
+      binding.poolStats(pool).idle should be(1)
+    }
+
+    "reject connections that weren't checked out of it" in withPool(0, 1) { pool =>
+      val plain = binding.newConnection(url)
+      an[SQLException] should be thrownBy binding.checkinConnection(pool, plain)
+      binding.sqlQuery(plain, "SELECT 1", "[]").rows should not be empty
+      binding.closeConnection(plain)
+
+      withPool(0, 1) { other =>
+        val foreign = binding.checkoutConnection(other)
+        an[SQLException] should be thrownBy binding.checkinConnection(pool, foreign)
+        binding.checkinConnection(other, foreign)
+      }
+
+      binding.poolStats(pool) should be(RustPoolStats(idle = 0, active = 0, total = 0, min = 0, max = 1, replaced = 0))
+    }
+
+    "free the slot of a connection that is closed instead of checked in" in withPool(0, 1) { pool =>
+      val connection = binding.checkoutConnection(pool)
+      binding.closeConnection(connection)
+      binding.poolStats(pool).total should be(0)
+
+      // Would wait for the checkout timeout if the slot had leaked.
+      val replacement = binding.checkoutConnection(pool)
+      binding.poolStats(pool).active should be(1)
+      binding.checkinConnection(pool, replacement)
+    }
+
+    "replace connections that were closed instead of checked in up to min size" in withPool(1, 2) { pool =>
+      binding.closeConnection(binding.checkoutConnection(pool))
+
+      // The replacement is opened in the background.
+      Thread.sleep(500)
+      binding.poolStats(pool) should be(RustPoolStats(idle = 1, active = 0, total = 1, min = 1, max = 2, replaced = 0))
+    }
+
+    "evict connections idle for longer than the idle timeout down to min size" in withPool(1, 3, idleTimeoutMs = 100) { pool =>
+      val connections = Vector.fill(3)(binding.checkoutConnection(pool))
+      connections.foreach(binding.checkinConnection(pool, _))
+      binding.poolStats(pool).idle should be(3)
+
+      Thread.sleep(300)
+      binding.poolStats(pool) should be(RustPoolStats(idle = 1, active = 0, total = 1, min = 1, max = 3, replaced = 0))
+    }
+
+    "refuse to close while connections are checked out" in {
+      val pool       = binding.newPool(url, 0, 1, 0)
+      val connection = binding.checkoutConnection(pool)
+
+      an[SQLException] should be thrownBy binding.closePool(pool)
+      binding.checkinConnection(pool, connection)
+      binding.closePool(pool)
+    }
+  }
+}
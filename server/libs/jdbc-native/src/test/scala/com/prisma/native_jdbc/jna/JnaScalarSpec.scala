@@ -0,0 +1,52 @@
+package com.prisma.native_jdbc.jna
+
+import java.math.BigDecimal
+import java.sql.{PreparedStatement, SQLException}
+
+import com.prisma.native_jdbc.CustomJdbcConnection
+import org.scalatest.{BeforeAndAfterAll, Matchers, WordSpec}
+
+class JnaScalarSpec extends WordSpec with Matchers with BeforeAndAfterAll with JnaSpecFixture {
+  val connection = new CustomJdbcConnection(url, binding)
+
+  override protected def afterAll(): Unit = connection.close()
+
+  def roundTrip(short: Short, float: Float, oid: Long, money: BigDecimal) = {
+    val statement = connection.prepareStatement("SELECT ?::int2 AS s, ?::float4 AS f, ?::oid AS o, ?::money AS m")
+    statement.setShort(1, short)
+    statement.setFloat(2, float)
+    statement.setLong(3, oid)
+    statement.setBigDecimal(4, money)
+
+    val resultSet = statement.executeQuery()
+    resultSet.next() should be(true)
+    (resultSet.getShort("s"), resultSet.getFloat("f"), resultSet.getLong("o"), resultSet.getBigDecimal("m"))
+  }
+
+  def rejects(sqlType: String)(set: PreparedStatement => Unit) = {
+    val statement = connection.prepareStatement(s"SELECT ?::$sqlType")
+    set(statement)
+    an[SQLException] should be thrownBy statement.executeQuery()
+  }
+
+  "Numeric scalars" should {
+    "round trip INT2, FLOAT4, OID and MONEY values" in {
+      roundTrip(Short.MinValue, 1.5f, 4294967295L, new BigDecimal("-12.34")) should be((Short.MinValue, 1.5f, 4294967295L, new BigDecimal("-12.34")))
+      roundTrip(Short.MaxValue, Float.MaxValue, 0L, new BigDecimal("92233720368547758.07")) should be(
+        (Short.MaxValue, Float.MaxValue, 0L, new BigDecimal("92233720368547758.07")))
+    }
+
+    "round trip FLOAT4 values that have no JSON number" in {
+      roundTrip(0, Float.NaN, 0L, BigDecimal.ZERO)._2.isNaN should be(true)
+      roundTrip(0, Float.NegativeInfinity, 0L, BigDecimal.ZERO)._2 should be(Float.NegativeInfinity)
+    }
+
+    "reject values that don't fit the target type" in {
+      rejects("int2")(_.setInt(1, 40000))
+      rejects("int2")(_.setDouble(1, 1.5))
+      rejects("float4")(_.setDouble(1, 1e300))
+      rejects("oid")(_.setLong(1, -1))
+      rejects("money")(_.setBigDecimal(1, new BigDecimal("1.234")))
+    }
+  }
+}
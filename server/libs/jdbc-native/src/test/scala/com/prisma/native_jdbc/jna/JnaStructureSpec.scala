@@ -0,0 +1,18 @@
+package com.prisma.native_jdbc.jna
+
+import com.sun.jna.Native
+import org.scalatest.{Matchers, WordSpec}
+
+// The structures have to match the #[repr(C)] structs of lib.rs, whose lengths are usize.
+class JnaStructureSpec extends WordSpec with Matchers {
+  "The JNA structures" should {
+    "map lengths to size_t" in {
+      new SizeT().size() should be(Native.SIZE_T_SIZE)
+    }
+
+    "have the layout of the native structs" in {
+      new ProtocolBuffer().size() should be(2 * Native.POINTER_SIZE + 2 * Native.SIZE_T_SIZE)
+      new HandleAndError().size() should be(Native.POINTER_SIZE + Native.SIZE_T_SIZE + 8)
+    }
+  }
+}
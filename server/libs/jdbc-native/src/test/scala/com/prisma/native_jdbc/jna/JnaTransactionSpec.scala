@@ -0,0 +1,246 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.SQLException
+
+import org.scalatest.{Matchers, WordSpec}
+
+import scala.concurrent.duration._
+import scala.concurrent.{Await, Future}
+import scala.concurrent.ExecutionContext.Implicits.global
+
+class JnaTransactionSpec extends WordSpec with Matchers with JnaTableFixture {
+  val table   = "jdbc_native_transaction_spec"
+  val columns = "id int PRIMARY KEY"
+
+  def insert(connection: RustConnectionJna, id: Int) = binding.sqlExecute(connection, s"INSERT INTO $table (id) VALUES ($id)", "[]")
+
+  "Transactions on a connection" should {
+    "make the changes visible to other connections on commit" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        insert(connection, 1)
+        ids() should not contain 1
+
+        binding.commitTransaction(connection)
+      }
+
+      ids() should contain(1)
+    }
+
+    "discard the changes on rollback" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        insert(connection, 2)
+        binding.rollbackTransaction(connection)
+
+        binding.sqlQuery(connection, s"SELECT id FROM $table WHERE id = 2", "[]").rows.get.data shouldBe empty
+      }
+
+      ids() should not contain 2
+    }
+
+    "discard the changes if the connection is closed without commit" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        insert(connection, 3)
+      }
+
+      ids() should not contain 3
+    }
+
+    "be reusable after a commit or rollback" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        insert(connection, 4)
+        binding.rollbackTransaction(connection)
+
+        binding.startTransaction(connection)
+        insert(connection, 5)
+        binding.commitTransaction(connection)
+      }
+
+      ids() should not contain 4
+      ids() should contain(5)
+    }
+
+    "treat commit and rollback without an open transaction as a no-op" in {
+      withConnection { connection =>
+        binding.commitTransaction(connection).isEmpty should be(true)
+        binding.rollbackTransaction(connection).isEmpty should be(true)
+      }
+    }
+
+    "refuse to start a transaction while one is open" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        insert(connection, 6)
+
+        an[SQLException] should be thrownBy binding.startTransaction(connection)
+
+        binding.commitTransaction(connection)
+      }
+
+      ids() should contain(6)
+    }
+
+    "roll back a failed statement and keep the connection usable" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        insert(connection, 7)
+
+        an[SQLException] should be thrownBy insert(connection, 7)
+
+        binding.rollbackTransaction(connection)
+        insert(connection, 8)
+      }
+
+      ids() should not contain 7
+      ids() should contain(8)
+    }
+  }
+
+  "Savepoints" should {
+    "roll back to an outer savepoint and discard the savepoints nested in it" in {
+      withConnection { connection =>
+        binding.startTransaction(connection)
+        insert(connection, 30)
+        binding.createSavepoint(connection, "outer")
+        insert(connection, 31)
+        binding.createSavepoint(connection, "inner")
+        insert(connection, 32)
+
+        binding.rollbackToSavepoint(connection, "outer")
+        an[SQLException] should be thrownBy binding.releaseSavepoint(connection, "inner")
+        an[SQLException] should be thrownBy binding.rollbackToSavepoint(connection, "inner")
+
+        insert(connection, 33)
+        binding.rollbackToSavepoint(connection, "outer")
+        insert(connection, 34)
+        binding.releaseSavepoint(connection, "outer")
+        binding.commitTransaction(connection)
+      }
+
+      ids().filter(id => id >= 30 && id < 40) should be(Vector(30, 34))
+    }
+
+    "refuse to release savepoints that aren't the innermost active one" in {
+      withConnection { connection =>
+        an[SQLException] should be thrownBy binding.createSavepoint(connection, "outside")
+
+        binding.startTransaction(connection)
+        an[SQLException] should be thrownBy binding.releaseSavepoint(connection, "unknown")
+
+        binding.createSavepoint(connection, "outer")
+        binding.createSavepoint(connection, "inner")
+        an[SQLException] should be thrownBy binding.releaseSavepoint(connection, "outer")
+        an[SQLException] should be thrownBy binding.rollbackToSavepoint(connection, "unknown")
+
+        insert(connection, 40)
+        binding.releaseSavepoint(connection, "inner")
+        binding.releaseSavepoint(connection, "outer")
+        binding.commitTransaction(connection)
+      }
+
+      ids() should contain(40)
+    }
+  }
+
+  "Transaction characteristics" should {
+    def setting(connection: RustConnectionJna, name: String) = single(connection, s"SHOW $name").as[String]
+
+    "open transactions with the given isolation level, read-only and deferrable modes" in {
+      withConnection { connection =>
+        binding.startTransactionWith(connection, 4, false, false)
+        setting(connection, "transaction_isolation") should be("repeatable read")
+        setting(connection, "transaction_read_only") should be("off")
+        binding.commitTransaction(connection)
+
+        binding.startTransactionWith(connection, 8, true, true)
+        setting(connection, "transaction_isolation") should be("serializable")
+        setting(connection, "transaction_read_only") should be("on")
+        setting(connection, "transaction_deferrable") should be("on")
+        binding.commitTransaction(connection)
+
+        setting(connection, "transaction_isolation") should be("read committed")
+      }
+    }
+
+    "fail writes in a read-only transaction" in {
+      withConnection { connection =>
+        binding.startTransactionWith(connection, 2, true, false)
+        intercept[SQLException](insert(connection, 50)).getSQLState should be("25006")
+        binding.rollbackTransaction(connection)
+      }
+
+      ids() should not contain 50
+    }
+
+    "apply the characteristics set on the connection to the transactions started later" in {
+      withConnection { connection =>
+        binding.setTransactionIsolation(connection, 4)
+        binding.setReadOnly(connection, true)
+
+        binding.startTransaction(connection)
+        setting(connection, "transaction_isolation") should be("repeatable read")
+        setting(connection, "transaction_read_only") should be("on")
+
+        an[SQLException] should be thrownBy binding.setReadOnly(connection, false)
+        binding.rollbackTransaction(connection)
+      }
+    }
+
+    "reject unknown isolation levels" in {
+      withConnection { connection =>
+        an[SQLException] should be thrownBy binding.startTransactionWith(connection, 3, false, false)
+        an[SQLException] should be thrownBy binding.setTransactionIsolation(connection, 16)
+      }
+    }
+  }
+
+  "Retried transactions" should {
+    // The first attempt waits for the lock of the open serializable transaction and fails with 40001 once it commits.
+    def conflict(maxAttempts: Int, backoffMs: Long)(whileRetrying: RustConnectionJna => Unit) = withConnection { blocker =>
+      withConnection { connection =>
+        insert(blocker, 20 + maxAttempts)
+        val update = s"UPDATE $table SET id = id WHERE id = ${20 + maxAttempts}"
+
+        binding.startTransactionWith(blocker, 8, false, false)
+        binding.sqlExecute(blocker, update, "[]")
+
+        binding.setTransactionIsolation(connection, 8)
+        val retried = Future { binding.executeTransaction(connection, s"""[{"query": "$update", "params": []}]""", maxAttempts, backoffMs) }
+
+        Thread.sleep(500)
+        binding.commitTransaction(blocker)
+        whileRetrying(connection)
+
+        Await.result(retried, 30.seconds)
+      }
+    }
+
+    "retry serialization failures and report the attempts" in {
+      conflict(maxAttempts = 3, backoffMs = 10)(_ => ()).attempts should be(Some(2))
+    }
+
+    "fail once the attempts are used up" in {
+      an[SQLException] should be thrownBy conflict(maxAttempts = 1, backoffMs = 10)(_ => ())
+    }
+
+    "not hold the connection while backing off" in {
+      conflict(maxAttempts = 2, backoffMs = 3000) { connection =>
+        Thread.sleep(200)
+        val start = System.currentTimeMillis()
+        binding.sqlQuery(connection, "SELECT 1", "[]")
+        (System.currentTimeMillis() - start) should be < 1000L
+      }.attempts should be(Some(2))
+    }
+
+    "reject unreasonable retry settings" in {
+      withConnection { connection =>
+        val statements = """[{"query": "SELECT 1", "params": []}]"""
+        an[SQLException] should be thrownBy binding.executeTransaction(connection, statements, Int.MaxValue, 10)
+        an[SQLException] should be thrownBy binding.executeTransaction(connection, statements, 3, Long.MaxValue)
+      }
+    }
+  }
+}
@@ -0,0 +1,77 @@
+package com.prisma.native_jdbc.jna
+
+import java.sql.SQLException
+
+import com.prisma.native_jdbc.CustomJdbcConnection
+import org.scalatest.{BeforeAndAfterAll, Matchers, WordSpec}
+import play.api.libs.json.Json
+
+class JnaUserTypeSpec extends WordSpec with Matchers with BeforeAndAfterAll with JnaSpecFixture {
+  val connection = new CustomJdbcConnection(url, binding)
+
+  def execute(sql: String) = connection.prepareStatement(sql).execute()
+
+  override protected def beforeAll(): Unit = {
+    dropTypes()
+    execute("CREATE TYPE jdbc_native_mood AS ENUM ('sad', 'happy')")
+    execute("CREATE DOMAIN jdbc_native_positive AS int4 CHECK (VALUE > 0)")
+    execute("CREATE TYPE jdbc_native_pair AS (a int4, b text)")
+  }
+
+  override protected def afterAll(): Unit = {
+    dropTypes()
+    connection.close()
+  }
+
+  def dropTypes() = {
+    execute("DROP TYPE IF EXISTS jdbc_native_mood, jdbc_native_pair, jdbc_native_later")
+    execute("DROP DOMAIN IF EXISTS jdbc_native_positive")
+  }
+
+  def failure(sql: String)(set: java.sql.PreparedStatement => Unit): SQLException = {
+    val statement = connection.prepareStatement(sql)
+    set(statement)
+    intercept[SQLException](statement.executeQuery())
+  }
+
+  "User-defined types" should {
+    "round trip enums as strings and domains as their base type" in {
+      val statement = connection.prepareStatement("SELECT ?::jdbc_native_mood AS mood, ?::jdbc_native_positive AS positive")
+      statement.setString(1, "happy")
+      statement.setInt(2, 3)
+
+      val resultSet = statement.executeQuery()
+      resultSet.next() should be(true)
+      resultSet.getString("mood") should be("happy")
+      resultSet.getInt("positive") should be(3)
+    }
+
+    "read composites and ranges as JSON objects" in {
+      val statement = connection.prepareStatement("SELECT ROW(1, ?)::jdbc_native_pair AS pair, int4range(1, 5) AS range, 'empty'::int4range AS empty")
+      statement.setString(1, "x")
+
+      val resultSet = statement.executeQuery()
+      resultSet.next() should be(true)
+      Json.parse(resultSet.getString("pair")) should be(Json.obj("a" -> 1, "b" -> "x"))
+      Json.parse(resultSet.getString("range")) should be(
+        Json.obj("empty" -> false, "lower" -> 1, "lowerInclusive" -> true, "upper" -> 5, "upperInclusive" -> false))
+      (Json.parse(resultSet.getString("empty")) \ "empty").as[Boolean] should be(true)
+    }
+
+    "resolve types created after the connection was opened" in {
+      connection.prepareStatement("SELECT 'sad'::jdbc_native_mood").executeQuery()
+      execute("CREATE TYPE jdbc_native_later AS ENUM ('x')")
+
+      val resultSet = connection.prepareStatement("SELECT 'x'::jdbc_native_later AS later, ARRAY['sad'::jdbc_native_mood] AS moods").executeQuery()
+      resultSet.next() should be(true)
+      resultSet.getString("later") should be("x")
+      resultSet.getArray("moods").getArray should be(Array[AnyRef]("sad"))
+    }
+
+    "reject values the types don't accept" in {
+      failure("SELECT ?::jdbc_native_mood")(_.setString(1, "angry")).getSQLState should be("22P02")
+      failure("SELECT ?::jdbc_native_positive")(_.setInt(1, -3)).getSQLState should be("23514")
+      failure("SELECT ?::jdbc_native_mood")(_.setInt(1, 1))
+    }
+  }
+}
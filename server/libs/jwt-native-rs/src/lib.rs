@@ -12,6 +12,7 @@ mod protocol_buffer;
 mod ffi_utils;
 mod grant;
 mod logging;
+mod panics;
 
 use std::os::raw::c_char;
 use std::str::FromStr;
@@ -25,7 +26,8 @@ use grant::Grant;
 pub type Result<T> = std::result::Result<T, ProtocolError>;
 
 pub enum ProtocolError {
-    GenericError(String)
+    GenericError(String),
+    PanicError(String),
 }
 
 #[derive(Serialize, Deserialize)]
@@ -45,74 +47,91 @@ pub struct Claims {
 
 #[no_mangle]
 pub extern "C" fn jwt_initialize() {
-    logging::init();
+    guard_void(|| {
+        logging::init();
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn create_token(algorithm: *const c_char, secret: *const c_char, expiration_in_seconds: i64, allowed_target: *const c_char, allowed_action: *const c_char) -> *mut ProtocolBuffer {
-    let alg_string = to_str(algorithm);
-    let use_algorithm = match Algorithm::from_str(alg_string) {
-        Ok(a) => a,
-        Err(e) => return ProtocolBuffer::from(ProtocolError::GenericError(format!("Invalid algorithm: {}", alg_string))).into_boxed_ptr()
-    };
-
-    let secret_str = to_str(secret);
-    let expiration = if expiration_in_seconds < 0 {
-        None
-    } else {
-        Some(expiration_in_seconds)
-    };
-
-    let grant_to_encode = Grant::from(allowed_target, allowed_action).map(|g| vec!(g));
-    let now = Utc::now().timestamp();
-    let claims = Claims {
-        iat: Some(now),
-        nbf: Some(now),
-        exp: expiration,
-        grants: grant_to_encode,
-    };
-
-    let header = Header::new(use_algorithm);
-    let token = encode( &header, &claims, secret_str.as_ref()).unwrap();
-    let ptr = ProtocolBuffer::from(token).into_boxed_ptr();
-
-    trace!("Create - handing out: {:?}", ptr);
-    ptr
+    guard_buffer(|| {
+        let alg_string = to_str(algorithm);
+        let use_algorithm = match Algorithm::from_str(alg_string) {
+            Ok(a) => a,
+            Err(e) => return ProtocolBuffer::from(ProtocolError::GenericError(format!("Invalid algorithm: {}", alg_string))).into_boxed_ptr()
+        };
+
+        let secret_str = to_str(secret);
+        let expiration = if expiration_in_seconds < 0 {
+            None
+        } else {
+            Some(expiration_in_seconds)
+        };
+
+        let grant_to_encode = Grant::from(allowed_target, allowed_action).map(|g| vec!(g));
+        let now = Utc::now().timestamp();
+        let claims = Claims {
+            iat: Some(now),
+            nbf: Some(now),
+            exp: expiration,
+            grants: grant_to_encode,
+        };
+
+        let header = Header::new(use_algorithm);
+        let token = encode( &header, &claims, secret_str.as_ref()).unwrap();
+        let ptr = ProtocolBuffer::from(token).into_boxed_ptr();
+
+        trace!("Create - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn verify_token(token: *const c_char, secrets: *const *const c_char, num_secrets: i64, expect_target: *const c_char, expect_action: *const c_char) -> *mut ProtocolBuffer {
-    let parsed_token = to_str(token);
-    let parsed_secrets = str_vec_from_pointers(secrets, num_secrets);
-    let mut last_error: String = String::from("");
-
-    for secret in parsed_secrets {
-        let t = decode::<Claims>(parsed_token, secret.as_ref(), &Validation { validate_exp: false, ..Validation::default()});
-        match t {
-            Ok(x) => {
-                let ptr = validate_claims(x.claims, Grant::from(expect_target, expect_action)).into_boxed_ptr();
-                trace!("Verify - handing out: {:?}", ptr);
-                return ptr;
-            },
-            Err(e) => {
-                let err = format!("{}", e);
-                if last_error != err {
-                    last_error = err;
-                }
-            },
+    guard_buffer(|| {
+        let parsed_token = to_str(token);
+        let parsed_secrets = str_vec_from_pointers(secrets, num_secrets);
+        let mut last_error: String = String::from("");
+
+        for secret in parsed_secrets {
+            let t = decode::<Claims>(parsed_token, secret.as_ref(), &Validation { validate_exp: false, ..Validation::default()});
+            match t {
+                Ok(x) => {
+                    let ptr = validate_claims(x.claims, Grant::from(expect_target, expect_action)).into_boxed_ptr();
+                    trace!("Verify - handing out: {:?}", ptr);
+                    return ptr;
+                },
+                Err(e) => {
+                    let err = format!("{}", e);
+                    if last_error != err {
+                        last_error = err;
+                    }
+                },
+            }
         }
-    }
 
-    let ptr = ProtocolBuffer::from(ProtocolError::GenericError(String::from(last_error))).into_boxed_ptr();
+        let ptr = ProtocolBuffer::from(ProtocolError::GenericError(String::from(last_error))).into_boxed_ptr();
 
-    trace!("Verify - handing out: {:?}", ptr);
-    ptr
+        trace!("Verify - handing out: {:?}", ptr);
+        ptr
+    })
 }
 
 #[no_mangle]
 pub extern "C" fn destroy_buffer(buffer: *mut ProtocolBuffer) {
-    trace!("Dropping buffer at: {:?}", buffer);
-    unsafe { Box::from_raw(buffer) };
+    guard_void(|| {
+        trace!("Dropping buffer at: {:?}", buffer);
+        unsafe { Box::from_raw(buffer) };
+    })
+}
+
+fn guard_buffer<F>(f: F) -> *mut ProtocolBuffer where F: FnOnce() -> *mut ProtocolBuffer {
+    panics::catch(f).unwrap_or_else(|msg| ProtocolBuffer::from(ProtocolError::PanicError(msg)).into_boxed_ptr())
+}
+
+/// Functions without a return channel can only log the panic, which the panic hook already does.
+fn guard_void<F>(f: F) where F: FnOnce() {
+    let _ = panics::catch(f);
 }
 
 fn validate_claims(claims: Claims, grant: Option<Grant>) -> ProtocolBuffer {
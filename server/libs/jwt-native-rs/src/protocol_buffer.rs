@@ -45,6 +45,8 @@ impl From<ProtocolError> for ProtocolBuffer {
     fn from(e: ProtocolError) -> Self {
         let s = match e {
             ProtocolError::GenericError(s) => s,
+            // Panic messages may contain arbitrary data, which must not break the CString conversion.
+            ProtocolError::PanicError(s) => format!("[Rust] {}", s.replace('\0', "")),
         };
 
         let ptr = ffi_utils::string_to_ptr(s);